@@ -9,8 +9,8 @@ use libfuzzer_sys::fuzz_target;
 const PATH: &str = "/tmp/fuzz.mp3";
 
 fuzz_target!(|data: &[u8]| {
-    // Musikr's only input surface is with files, so we write our data to a file in /tmp/
-    // memory and then write our random bytes to it.
+    // Tag::open reads from a file, so we write our random bytes to a file in /tmp/.
+    // Parsing from memory is covered by the id3v2_bytes target.
     // Fuzzing only works on *nix right now, so this is okay.
 
     let path = Path::new(&PATH);
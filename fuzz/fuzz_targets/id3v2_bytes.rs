@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use musikr::id3v2::Tag;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Tag::from_bytes(data);
+});
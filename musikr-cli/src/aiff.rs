@@ -0,0 +1,68 @@
+use crate::mp3::id3v2;
+use crate::show::{DisplayName, DisplayTag, TagFilter};
+use crate::{errorln, print_header};
+use musikr::aiff::Tag;
+use std::path::Path;
+
+pub fn show(path: &Path, filter: TagFilter) {
+    let tag = match Tag::open(path) {
+        Ok(tag) => tag,
+        Err(err) => {
+            errorln!("{}: unable to parse aiff metadata: {}", path.display(), err);
+            return;
+        }
+    };
+
+    let id3v2_tags = match tag.id3v2 {
+        Some(id3v2) => id3v2::show(id3v2, filter.clone()),
+        None => Vec::new(),
+    };
+
+    let filter: Option<Vec<&str>> = filter.map(|tags| tags.collect());
+
+    let mut fields: Vec<(&str, &str, &String)> = Vec::new();
+    fields.extend(tag.name.as_ref().map(|name| ("NAME", "title", name)));
+    fields.extend(tag.author.as_ref().map(|author| ("AUTH", "artist", author)));
+    fields.extend(
+        tag.copyright
+            .as_ref()
+            .map(|copy| ("(c) ", "copyright", copy)),
+    );
+    fields.extend(tag.annotations.iter().map(|anno| ("ANNO", "comment", anno)));
+
+    let mut text_tags = Vec::new();
+
+    for (id, name, value) in fields {
+        let matches = match filter {
+            Some(ref names) => names.iter().any(|other| *other == name || *other == id),
+            None => true,
+        };
+
+        if matches {
+            text_tags.push(DisplayTag {
+                name: DisplayName::Name(name),
+                value: value.clone(),
+            })
+        }
+    }
+
+    if !id3v2_tags.is_empty() || !text_tags.is_empty() {
+        print_header!("Metadata for {}:", path.display());
+    }
+
+    if !id3v2_tags.is_empty() {
+        println!("  ID3v2:");
+
+        for tag in id3v2_tags {
+            tag.print(4)
+        }
+    }
+
+    if !text_tags.is_empty() {
+        println!("  AIFF:");
+
+        for tag in text_tags {
+            tag.print(4)
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use crate::show::{DisplayName, DisplayTag, TagFilter};
+use crate::{errorln, print_header};
+use musikr::ape::{items::Item, Tag};
+use std::path::Path;
+
+pub fn show(path: &Path, filter: TagFilter) {
+    // Musepack, WavPack, and Monkey's Audio files only have APE tags.
+    let tags = match Tag::open(path) {
+        Ok(tag) => show_tag(tag, filter),
+        Err(err) => {
+            errorln!("{}: unable to parse ape tag: {}", path.display(), err);
+            Vec::new()
+        }
+    };
+
+    if !tags.is_empty() {
+        print_header!("Metadata for {}:", path.display());
+        println!("  APE:");
+
+        for tag in tags {
+            tag.print(4)
+        }
+    }
+}
+
+pub fn show_tag(tag: Tag, filter: TagFilter) -> Vec<DisplayTag> {
+    let filter: Option<Vec<&str>> = filter.map(|tags| tags.collect());
+    let mut tags = Vec::new();
+
+    for (key, item) in tag.items.iter() {
+        let display_tag = transform_item(key, item);
+
+        let matches = match filter {
+            // APE keys are case-insensitive, so the filter should be as well.
+            Some(ref names) => names.iter().any(|name| match display_tag.name {
+                DisplayName::Name(display_name) => {
+                    *name == display_name || name.eq_ignore_ascii_case(key)
+                }
+                _ => name.eq_ignore_ascii_case(key),
+            }),
+            None => true,
+        };
+
+        if matches {
+            tags.push(display_tag)
+        }
+    }
+
+    tags.sort();
+
+    tags
+}
+
+fn transform_item(key: &str, item: &Item) -> DisplayTag {
+    let name = SHOW_ANALOGUES
+        .iter()
+        .find(|(other, _)| other.eq_ignore_ascii_case(key))
+        .map(|(_, name)| DisplayName::Name(name))
+        .unwrap_or_else(|| DisplayName::Unknown(String::from(key)));
+
+    DisplayTag {
+        name,
+        value: item.to_string(),
+    }
+}
+
+// All APE keys that musikr knows a name for.
+#[rustfmt::skip]
+static SHOW_ANALOGUES: &[(&str, &str)] = &[
+    ("Title", "title"),
+    ("Subtitle", "subtitle"),
+    ("Artist", "artist"),
+    ("Album Artist", "album_artist"),
+    ("Album", "album"),
+    ("Composer", "composer"),
+    ("Conductor", "conductor"),
+    ("Publisher", "publisher"),
+    ("Copyright", "copyright"),
+    ("Genre", "genre"),
+    ("Year", "date"),
+    ("Track", "track"),
+    ("Disc", "disc"),
+    ("Comment", "comment"),
+    ("Language", "language"),
+    ("ISRC", "isrc"),
+    ("Media", "media_type"),
+    ("Cover Art (Front)", "picture"),
+];
@@ -0,0 +1,40 @@
+use crate::show::{DisplayName, DisplayTag, TagFilter};
+use crate::xiph;
+use crate::{errorln, print_header};
+use musikr::flac::Tag;
+use std::path::Path;
+
+pub fn show(path: &Path, filter: TagFilter) {
+    let tag = match Tag::open(path) {
+        Ok(tag) => tag,
+        Err(err) => {
+            errorln!("{}: unable to parse flac metadata: {}", path.display(), err);
+            return;
+        }
+    };
+
+    let mut tags = xiph::show_comments(&tag.comments, filter.clone());
+    let filter: Option<Vec<&str>> = filter.map(|tags| tags.collect());
+
+    for picture in &tag.pictures {
+        let display_tag = DisplayTag {
+            name: DisplayName::Name("picture"),
+            value: picture.to_string(),
+        };
+
+        if xiph::matches_filter(&filter, &display_tag, "METADATA_BLOCK_PICTURE") {
+            tags.push(display_tag)
+        }
+    }
+
+    tags.sort();
+
+    if !tags.is_empty() {
+        print_header!("Metadata for {}:", path.display());
+        println!("  FLAC:");
+
+        for tag in tags {
+            tag.print(4)
+        }
+    }
+}
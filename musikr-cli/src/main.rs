@@ -1,8 +1,15 @@
 #![forbid(unsafe_code)]
 
+mod aiff;
+mod ape;
+mod flac;
 mod mp3;
+mod mp4;
+mod ogg;
+mod riff;
 mod show;
 mod stdout;
+mod xiph;
 
 #[macro_use]
 extern crate clap;
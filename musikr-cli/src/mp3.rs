@@ -1,5 +1,7 @@
-mod id3v2;
+mod id3v1;
+pub mod id3v2;
 
+use crate::ape;
 use crate::{print_header, errorln};
 use crate::show::TagFilter;
 use std::path::Path;
@@ -8,19 +10,57 @@ pub fn show(path: &Path, filter: TagFilter) {
     // MP3 contains 3 major metadata formats.
     // Try them all and see if any of them give any output.
     let id3v2_tags = match musikr::id3v2::Tag::open(path) {
-        Ok(tag) => id3v2::show(tag, filter),
+        Ok(tag) => id3v2::show(tag, filter.clone()),
         Err(err) => {
             errorln!("{}: unable to parse id3v2 tag: {}", path.display(), err);
             Vec::new()
         }
     };
 
-    if !id3v2_tags.is_empty() {
+    // APE and ID3v1 tags are rarer than ID3v2 tags, so don't bother reporting their absence.
+    let ape_tags = match musikr::ape::Tag::open(path) {
+        Ok(tag) => ape::show_tag(tag, filter.clone()),
+        Err(musikr::ape::ParseError::NotFound) => Vec::new(),
+        Err(err) => {
+            errorln!("{}: unable to parse ape tag: {}", path.display(), err);
+            Vec::new()
+        }
+    };
+
+    let id3v1_tags = match musikr::id3v1::Tag::open(path) {
+        Ok(tag) => id3v1::show(tag, filter),
+        Err(musikr::id3v1::ParseError::NotFound) => Vec::new(),
+        Err(err) => {
+            errorln!("{}: unable to parse id3v1 tag: {}", path.display(), err);
+            Vec::new()
+        }
+    };
+
+    if !id3v2_tags.is_empty() || !ape_tags.is_empty() || !id3v1_tags.is_empty() {
         print_header!("Metadata for {}:", path.display());
+    }
+
+    if !id3v2_tags.is_empty() {
         println!("  ID3v2:");
 
         for tag in id3v2_tags {
             tag.print(4)
         }
     }
+
+    if !ape_tags.is_empty() {
+        println!("  APE:");
+
+        for tag in ape_tags {
+            tag.print(4)
+        }
+    }
+
+    if !id3v1_tags.is_empty() {
+        println!("  ID3v1:");
+
+        for tag in id3v1_tags {
+            tag.print(4)
+        }
+    }
 }
@@ -0,0 +1,49 @@
+use crate::show::{DisplayName, DisplayTag, TagFilter};
+use musikr::id3v1::Tag;
+
+pub fn show(tag: Tag, filter: TagFilter) -> Vec<DisplayTag> {
+    // ID3v1 has no frame IDs, so only readable names can be filtered.
+    let filter_names: Option<Vec<&str>> = filter.map(|tags| tags.collect());
+
+    let mut tags = Vec::new();
+
+    let mut push = |name: &'static str, value: String| {
+        let matches = match filter_names {
+            Some(ref names) => names.contains(&name),
+            None => true,
+        };
+
+        if matches && !value.is_empty() {
+            tags.push(DisplayTag {
+                name: DisplayName::Name(name),
+                value,
+            })
+        }
+    };
+
+    // The free-form genre of an enhanced tag takes precedence over the genre index.
+    let genre = match tag.extended {
+        Some(ref ext) if !ext.genre.is_empty() => ext.genre.clone(),
+        _ => tag.genre_name().map(String::from).unwrap_or_default(),
+    };
+
+    push("title", tag.title);
+    push("artist", tag.artist);
+    push("album", tag.album);
+    push("date", tag.year);
+    push("comment", tag.comment);
+    push(
+        "track",
+        tag.track.map(|track| track.to_string()).unwrap_or_default(),
+    );
+    push("genre", genre);
+
+    if let Some(ext) = tag.extended {
+        push("start_time", ext.start_time);
+        push("end_time", ext.end_time);
+    }
+
+    tags.sort();
+
+    tags
+}
@@ -42,7 +42,7 @@ pub fn show(tag: Tag, filter: TagFilter) -> Vec<DisplayTag> {
     tags
 }
 
-fn process_filter(filter: TagFilter) -> (Vec<&str>, Vec<FrameId>) {
+fn process_filter(filter: TagFilter<'_>) -> (Vec<&str>, Vec<FrameId>) {
     let mut filter_names = Vec::new();
     let mut filter_ids = Vec::new();
 
@@ -60,7 +60,7 @@ fn process_filter(filter: TagFilter) -> (Vec<&str>, Vec<FrameId>) {
     (filter_names, filter_ids)
 }
 
-/// --- FRAME TRANSFORMATION ---
+// --- FRAME TRANSFORMATION ---
 
 fn transform_frame(frame: &dyn Frame) -> DisplayTag {
     for analogue in SHOW_ANALOGUES {
@@ -0,0 +1,108 @@
+use crate::show::{DisplayName, DisplayTag, TagFilter};
+use crate::{errorln, print_header};
+use musikr::mp4::{items::ItemValue, Tag};
+use std::path::Path;
+
+pub fn show(path: &Path, filter: TagFilter) {
+    let tag = match Tag::open(path) {
+        Ok(tag) => tag,
+        Err(err) => {
+            errorln!("{}: unable to parse mp4 metadata: {}", path.display(), err);
+            return;
+        }
+    };
+
+    let filter: Option<Vec<&str>> = filter.map(|tags| tags.collect());
+    let mut tags = Vec::new();
+
+    for (key, value) in tag.items.iter() {
+        let display_tag = transform_item(key, value);
+
+        let matches = match filter {
+            Some(ref names) => names.iter().any(|name| match display_tag.name {
+                DisplayName::Name(display_name) => *name == display_name || *name == key,
+                DisplayName::Custom(display_name, ref custom) => {
+                    *name == display_name || name == custom || *name == key
+                }
+                DisplayName::Unknown(_) => *name == key,
+            }),
+            None => true,
+        };
+
+        if matches {
+            tags.push(display_tag)
+        }
+    }
+
+    tags.sort();
+
+    if !tags.is_empty() {
+        print_header!("Metadata for {}:", path.display());
+        println!("  MP4:");
+
+        for tag in tags {
+            tag.print(4)
+        }
+    }
+}
+
+fn transform_item(key: &str, value: &ItemValue) -> DisplayTag {
+    // Freeform items are analogous to TXXX frames, so display them the same way.
+    if let Some((_, name)) = key
+        .strip_prefix("----:")
+        .and_then(|rest| rest.split_once(':'))
+    {
+        return DisplayTag {
+            name: DisplayName::Custom("user_text", String::from(name)),
+            value: value.to_string(),
+        };
+    }
+
+    let name = SHOW_ANALOGUES
+        .iter()
+        .find(|(other, _)| *other == key)
+        .map(|(_, name)| DisplayName::Name(name))
+        .unwrap_or_else(|| DisplayName::Unknown(String::from(key)));
+
+    DisplayTag {
+        name,
+        value: value.to_string(),
+    }
+}
+
+// All iTunes items that musikr knows a name for. These match the names of their ID3v2
+// equivalents where possible.
+#[rustfmt::skip]
+static SHOW_ANALOGUES: &[(&str, &str)] = &[
+    ("\u{a9}nam", "title"),
+    ("\u{a9}ART", "artist"),
+    ("aART", "album_artist"),
+    ("\u{a9}alb", "album"),
+    ("\u{a9}wrt", "composer"),
+    ("\u{a9}day", "date"),
+    ("\u{a9}gen", "genre"),
+    ("\u{a9}cmt", "comment"),
+    ("\u{a9}lyr", "lyrics"),
+    ("\u{a9}too", "encoding"),
+    ("\u{a9}wrk", "category"),
+    ("\u{a9}grp", "grouping"),
+    ("\u{a9}mvn", "movement_name"),
+    ("\u{a9}mvi", "movement_no"),
+    ("cprt", "copyright"),
+    ("trkn", "track"),
+    ("disk", "disc"),
+    ("tmpo", "bpm"),
+    ("covr", "picture"),
+    ("sonm", "sort_title"),
+    ("soar", "sort_artist"),
+    ("soal", "sort_album"),
+    ("soaa", "sort_album_artist"),
+    ("soco", "sort_composer"),
+    ("cpil", "compilation"),
+    ("pcst", "podcast"),
+    ("purl", "podcast_url"),
+    ("catg", "podcast_category"),
+    ("desc", "podcast_desc"),
+    ("keyw", "podcast_keyword"),
+    ("egid", "podcast_id"),
+];
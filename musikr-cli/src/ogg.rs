@@ -0,0 +1,27 @@
+use crate::show::TagFilter;
+use crate::xiph;
+use crate::{errorln, print_header};
+use musikr::ogg::Tag;
+use std::path::Path;
+
+pub fn show(path: &Path, filter: TagFilter) {
+    let tag = match Tag::open(path) {
+        Ok(tag) => tag,
+        Err(err) => {
+            errorln!("{}: unable to parse ogg comments: {}", path.display(), err);
+            return;
+        }
+    };
+
+    let mut tags = xiph::show_comments(&tag.comments, filter);
+    tags.sort();
+
+    if !tags.is_empty() {
+        print_header!("Metadata for {}:", path.display());
+        println!("  {}:", tag.codec());
+
+        for tag in tags {
+            tag.print(4)
+        }
+    }
+}
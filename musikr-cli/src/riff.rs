@@ -0,0 +1,95 @@
+use crate::mp3::id3v2;
+use crate::show::{DisplayName, DisplayTag, TagFilter};
+use crate::{errorln, print_header};
+use musikr::riff::Tag;
+use std::path::Path;
+
+pub fn show(path: &Path, filter: TagFilter) {
+    let tag = match Tag::open(path) {
+        Ok(tag) => tag,
+        Err(err) => {
+            errorln!("{}: unable to parse wav metadata: {}", path.display(), err);
+            return;
+        }
+    };
+
+    let id3v2_tags = match tag.id3v2 {
+        Some(id3v2) => id3v2::show(id3v2, filter.clone()),
+        None => Vec::new(),
+    };
+
+    let filter: Option<Vec<&str>> = filter.map(|tags| tags.collect());
+    let mut info_tags = Vec::new();
+
+    for (id, value) in tag.info.iter() {
+        let display_tag = transform_field(id, value);
+
+        let matches = match filter {
+            Some(ref names) => names.iter().any(|name| match display_tag.name {
+                DisplayName::Name(display_name) => *name == display_name || *name == id,
+                _ => *name == id,
+            }),
+            None => true,
+        };
+
+        if matches {
+            info_tags.push(display_tag)
+        }
+    }
+
+    info_tags.sort();
+
+    if !id3v2_tags.is_empty() || !info_tags.is_empty() {
+        print_header!("Metadata for {}:", path.display());
+    }
+
+    if !id3v2_tags.is_empty() {
+        println!("  ID3v2:");
+
+        for tag in id3v2_tags {
+            tag.print(4)
+        }
+    }
+
+    if !info_tags.is_empty() {
+        println!("  RIFF INFO:");
+
+        for tag in info_tags {
+            tag.print(4)
+        }
+    }
+}
+
+fn transform_field(id: &str, value: &str) -> DisplayTag {
+    let name = SHOW_ANALOGUES
+        .iter()
+        .find(|(other, _)| *other == id)
+        .map(|(_, name)| DisplayName::Name(name))
+        .unwrap_or_else(|| DisplayName::Unknown(String::from(id)));
+
+    DisplayTag {
+        name,
+        value: String::from(value),
+    }
+}
+
+// All RIFF INFO fields that musikr knows a name for.
+#[rustfmt::skip]
+static SHOW_ANALOGUES: &[(&str, &str)] = &[
+    ("INAM", "title"),
+    ("IART", "artist"),
+    ("IPRD", "album"),
+    ("ICMT", "comment"),
+    ("ICRD", "date"),
+    ("IGNR", "genre"),
+    ("ITRK", "track"),
+    ("IPRT", "track"),
+    ("ICOP", "copyright"),
+    ("IENG", "engineer"),
+    ("ISFT", "encoding"),
+    ("ISRC", "source"),
+    ("ILNG", "language"),
+    ("IMUS", "composer"),
+    ("IWRI", "writer"),
+    ("IPUB", "publisher"),
+];
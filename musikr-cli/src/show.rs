@@ -1,9 +1,9 @@
-use crate::mp3;
+use crate::{aiff, ape, flac, mp3, mp4, ogg, riff};
 use crate::{errorln, print_entry};
 
 use std::error;
 use std::fmt::{self, Display, Formatter};
-use std::io::{self, ErrorKind};
+use std::io;
 
 use clap::Values;
 use std::cmp::{Ord, Ordering, PartialOrd};
@@ -92,11 +92,11 @@ impl Display for DisplayName {
     }
 }
 
-/// Show plan:
-/// -f Filters tags to specific names, like album or comment (desc), or IDs, like TPE1
-///    If a tag is not found, then nothing happens.
-/// -t Describes specific tag(s) to show. If it is not supplied, then all tags are
-///    shown in order of their position in the media file.
+// Show plan:
+// -f Filters tags to specific names, like album or comment (desc), or IDs, like TPE1
+//    If a tag is not found, then nothing happens.
+// -t Describes specific tag(s) to show. If it is not supplied, then all tags are
+//    shown in order of their position in the media file.
 
 #[derive(Debug)]
 pub enum ShowError {
@@ -146,7 +146,34 @@ fn show_file<'a>(path: &'a str, filter: TagFilter<'a>) -> ShowResult {
     let path = new_path_safe(path)?;
 
     match path.extension() {
-        Some(ext) if ext == "mp3" => Ok(mp3::show(path, filter)),
+        Some(ext) if ext == "mp3" => {
+            mp3::show(path, filter);
+            Ok(())
+        }
+        Some(ext) if ext == "flac" => {
+            flac::show(path, filter);
+            Ok(())
+        }
+        Some(ext) if ext == "m4a" || ext == "m4b" || ext == "mp4" => {
+            mp4::show(path, filter);
+            Ok(())
+        }
+        Some(ext) if ext == "ogg" || ext == "oga" || ext == "opus" => {
+            ogg::show(path, filter);
+            Ok(())
+        }
+        Some(ext) if ext == "wav" => {
+            riff::show(path, filter);
+            Ok(())
+        }
+        Some(ext) if ext == "aif" || ext == "aiff" || ext == "aifc" => {
+            aiff::show(path, filter);
+            Ok(())
+        }
+        Some(ext) if ext == "mpc" || ext == "wv" || ext == "ape" => {
+            ape::show(path, filter);
+            Ok(())
+        }
         _ => Err(ShowError::Unsupported),
     }
 }
@@ -157,7 +184,7 @@ fn new_path_safe(string: &str) -> Result<&Path, io::Error> {
     path.metadata()?;
 
     if path.is_dir() {
-        return Err(io::Error::new(ErrorKind::Other, "Is a directory"));
+        return Err(io::Error::other("Is a directory"));
     }
 
     Ok(path)
@@ -0,0 +1,78 @@
+use crate::show::{DisplayName, DisplayTag, TagFilter};
+use musikr::xiph::VorbisComment;
+
+pub fn show_comments(comments: &VorbisComment, filter: TagFilter) -> Vec<DisplayTag> {
+    let filter: Option<Vec<&str>> = filter.map(|tags| tags.collect());
+    let mut tags = Vec::new();
+
+    for (key, value) in comments.iter() {
+        let display_tag = transform_field(key, value);
+
+        if matches_filter(&filter, &display_tag, key) {
+            tags.push(display_tag)
+        }
+    }
+
+    tags.sort();
+
+    tags
+}
+
+pub fn matches_filter(filter: &Option<Vec<&str>>, tag: &DisplayTag, key: &str) -> bool {
+    // Field names are case-insensitive, so the filter should be as well.
+    match filter {
+        Some(names) => names.iter().any(|name| match tag.name {
+            DisplayName::Name(display_name) => {
+                *name == display_name || name.eq_ignore_ascii_case(key)
+            }
+            _ => name.eq_ignore_ascii_case(key),
+        }),
+        None => true,
+    }
+}
+
+fn transform_field(key: &str, value: &str) -> DisplayTag {
+    let name = SHOW_ANALOGUES
+        .iter()
+        .find(|(other, _)| other.eq_ignore_ascii_case(key))
+        .map(|(_, name)| DisplayName::Name(name))
+        .unwrap_or_else(|| DisplayName::Unknown(String::from(key)));
+
+    DisplayTag {
+        name,
+        value: String::from(value),
+    }
+}
+
+// All Vorbis comment fields that musikr knows a name for.
+#[rustfmt::skip]
+static SHOW_ANALOGUES: &[(&str, &str)] = &[
+    ("TITLE", "title"),
+    ("VERSION", "subtitle"),
+    ("ALBUM", "album"),
+    ("ARTIST", "artist"),
+    ("ALBUMARTIST", "album_artist"),
+    ("COMPOSER", "composer"),
+    ("CONDUCTOR", "conductor"),
+    ("LYRICIST", "writer"),
+    ("REMIXER", "remixer"),
+    ("ORGANIZATION", "publisher"),
+    ("COPYRIGHT", "copyright"),
+    ("GENRE", "genre"),
+    ("DATE", "date"),
+    ("TRACKNUMBER", "track"),
+    ("DISCNUMBER", "disc"),
+    ("COMMENT", "comment"),
+    ("DESCRIPTION", "comment"),
+    ("LANGUAGE", "language"),
+    ("ISRC", "isrc"),
+    ("ENCODER", "encoding"),
+    ("ENCODED-BY", "encoded_by"),
+    ("GROUPING", "grouping"),
+    ("BPM", "bpm"),
+    ("MOOD", "mood"),
+    ("ALBUMSORT", "sort_album"),
+    ("ARTISTSORT", "sort_artist"),
+    ("TITLESORT", "sort_title"),
+    ("ALBUMARTISTSORT", "sort_album_artist"),
+];
@@ -0,0 +1,351 @@
+//! AIFF and AIFF-C metadata reading and writing.
+//!
+//! AIFF files are a big-endian `FORM` container of chunks. Metadata is stored either as an
+//! ID3v2 tag in an `ID3 ` chunk, which is exposed as an [`id3v2::Tag`](crate::id3v2::Tag),
+//! or as the simple `NAME`, `AUTH`, `(c) `, and `ANNO` text chunks:
+//!
+//! ```no_run
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! use musikr::aiff::Tag;
+//! let mut tag = Tag::open("music.aiff")?;
+//! tag.name = Some(String::from("Archangel"));
+//! tag.save("music.aiff")?;
+//! #   Ok(())
+//! # }
+//! ```
+//!
+//! When saved, any old metadata chunks are removed and the new chunks are written to the end
+//! of the file. Other chunks, such as the sound data, are not modified.
+
+use crate::core::iff::{self, ByteOrder, ChunkInfo, Form};
+use crate::core::io::{BufStream, SaveOptions};
+use crate::core::string::{self, Encoding};
+use crate::id3v2;
+
+use log::{error, warn};
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+
+/// The metadata of an AIFF or AIFF-C file.
+///
+/// More information can be found in the [module documentation](self).
+#[derive(Debug, Clone, Default)]
+pub struct Tag {
+    id3v2_id: Option<[u8; 4]>,
+    /// The ID3v2 tag embedded in this file, if present.
+    pub id3v2: Option<id3v2::Tag>,
+    /// The `NAME` chunk, which is the title of the sound.
+    pub name: Option<String>,
+    /// The `AUTH` chunk, which is the author of the sound.
+    pub author: Option<String>,
+    /// The `(c) ` chunk, which is the copyright notice of the sound.
+    pub copyright: Option<String>,
+    /// Every `ANNO` chunk, which are comments about the sound.
+    pub annotations: Vec<String>,
+}
+
+impl Tag {
+    /// Creates an empty tag.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attempts to open and parse the metadata in `path`.
+    ///
+    /// A file without any metadata will result in an empty tag.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be opened, is not an AIFF file, or if the metadata is malformed, an
+    /// error will be returned with a general reason for why. Specific information about
+    /// parsing errors will be logged.
+    pub fn open<P: AsRef<Path>>(path: P) -> ParseResult<Self> {
+        let mut file = BufReader::new(File::open(path)?);
+        let form = read_form(&mut file)?;
+        let mut tag = Self::new();
+
+        for chunk in &form.chunks {
+            if is_id3v2(chunk) {
+                if tag.id3v2.is_some() {
+                    warn!("dropping duplicate id3v2 chunk");
+                    continue;
+                }
+
+                let data = Form::read_chunk(&mut file, chunk)?;
+
+                let id3v2 = id3v2::Tag::from_bytes(&data).map_err(|err| {
+                    error!("could not parse id3v2 chunk: {}", err);
+                    ParseError::MalformedData
+                })?;
+
+                tag.id3v2_id = Some(chunk.id);
+                tag.id3v2 = Some(id3v2);
+            } else if is_text(chunk) {
+                let data = Form::read_chunk(&mut file, chunk)?;
+
+                // Text chunks are plain ASCII, but some writers add a NUL terminator.
+                let text = string::read_terminated(Encoding::Latin1, &mut BufStream::new(&data));
+
+                match &chunk.id {
+                    b"NAME" => tag.name = Some(text),
+                    b"AUTH" => tag.author = Some(text),
+                    b"(c) " => tag.copyright = Some(text),
+                    _ => tag.annotations.push(text),
+                }
+            }
+        }
+
+        Ok(tag)
+    }
+
+    /// Saves the metadata to `path`.
+    ///
+    /// All existing text and ID3v2 chunks are replaced with the contents of this tag.
+    /// Empty metadata will not be written. If the ID3v2 tag was read from an `id3 ` chunk,
+    /// that chunk ID will be kept, otherwise `ID3 ` will be used.
+    ///
+    /// # Errors
+    ///
+    /// If `path` is not an AIFF file, if the tag is too large, or if the file cannot be
+    /// written, an error with a general reason will be returned. Specific information
+    /// about saving errors will be logged.
+    pub fn save<P: AsRef<Path>>(&mut self, path: P) -> SaveResult<()> {
+        self.save_with(path, &SaveOptions::default())
+    }
+
+    /// Saves the metadata to `path` with the given [`SaveOptions`](crate::core::SaveOptions).
+    ///
+    /// This behaves like [`Tag::save`](Tag::save), except that the file can be written
+    /// atomically. The ID3v2 tag is checked for read-only frames and restrictions like it is
+    /// in [`id3v2::Tag::save_with`](crate::id3v2::Tag::save_with), but is never padded.
+    ///
+    /// # Errors
+    ///
+    /// If `path` is not a AIFF file, if the ID3v2 tag cannot be rendered, or if the file
+    /// cannot be written, an error with a general reason will be returned. Specific information
+    /// about saving errors will be logged.
+    pub fn save_with<P: AsRef<Path>>(&mut self, path: P, options: &SaveOptions) -> SaveResult<()> {
+        let form = match read_form(&mut File::open(&path)?) {
+            Ok(form) => form,
+            Err(ParseError::IoError(err)) => return Err(SaveError::IoError(err)),
+            Err(_) => {
+                error!("file is not an aiff file");
+                return Err(SaveError::NotAiff);
+            }
+        };
+
+        let mut new = Vec::new();
+
+        let text = [
+            (b"NAME", self.name.as_ref()),
+            (b"AUTH", self.author.as_ref()),
+            (b"(c) ", self.copyright.as_ref()),
+        ];
+
+        for (id, text) in text {
+            if let Some(text) = text {
+                new.extend(render_text(id, text))
+            }
+        }
+
+        for annotation in &self.annotations {
+            new.extend(render_text(b"ANNO", annotation))
+        }
+
+        if let Some(tag) = &mut self.id3v2 {
+            let data = tag.render_with(options, |_| 0)?;
+
+            if !data.is_empty() {
+                let id = self.id3v2_id.unwrap_or(*b"ID3 ");
+                new.extend(iff::render_chunk(&id, &data, ByteOrder::Big));
+            }
+        }
+
+        form.replace_chunks(
+            path,
+            |chunk| is_id3v2(chunk) || is_text(chunk),
+            &new,
+            options,
+        )?;
+
+        Ok(())
+    }
+}
+
+fn read_form<R: io::Read + io::Seek>(src: &mut R) -> ParseResult<Form> {
+    match Form::read(src, b"FORM", ByteOrder::Big)? {
+        Some(form) if &form.kind == b"AIFF" || &form.kind == b"AIFC" => Ok(form),
+        _ => Err(ParseError::NotFound),
+    }
+}
+
+fn render_text(id: &[u8; 4], text: &str) -> Vec<u8> {
+    iff::render_chunk(id, &string::render(Encoding::Latin1, text), ByteOrder::Big)
+}
+
+fn is_id3v2(chunk: &ChunkInfo) -> bool {
+    &chunk.id == b"ID3 " || &chunk.id == b"id3 "
+}
+
+fn is_text(chunk: &ChunkInfo) -> bool {
+    [b"NAME", b"AUTH", b"(c) ", b"ANNO"].contains(&&chunk.id)
+}
+
+/// The result given after a parsing operation.
+pub type ParseResult<T> = Result<T, ParseError>;
+
+/// The error type returned when parsing AIFF metadata.
+#[derive(Debug)]
+pub enum ParseError {
+    /// Generic IO errors. This either means that a problem occurred while opening the file
+    /// for a tag, or an unexpected EOF was encountered while parsing.
+    IoError(io::Error),
+    /// A metadata chunk was not valid.
+    MalformedData,
+    /// The file was not an AIFF file.
+    NotFound,
+}
+
+impl From<io::Error> for ParseError {
+    fn from(other: io::Error) -> Self {
+        ParseError::IoError(other)
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(err) => err.fmt(f),
+            Self::MalformedData => write![f, "malformed data"],
+            Self::NotFound => write![f, "not found"],
+        }
+    }
+}
+
+impl error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::IoError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// The result given after a save operation.
+pub type SaveResult<T> = Result<T, SaveError>;
+
+/// The error type returned when saving AIFF metadata.
+#[derive(Debug)]
+pub enum SaveError {
+    /// Generic IO errors. This means that a problem occurred while writing the tag to a file.
+    IoError(io::Error),
+    /// The ID3v2 tag could not be rendered.
+    Id3v2(id3v2::SaveError),
+    /// The file being written to was not an AIFF file.
+    NotAiff,
+}
+
+impl From<io::Error> for SaveError {
+    fn from(other: io::Error) -> Self {
+        SaveError::IoError(other)
+    }
+}
+
+impl From<id3v2::SaveError> for SaveError {
+    fn from(other: id3v2::SaveError) -> Self {
+        SaveError::Id3v2(other)
+    }
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(err) => err.fmt(f),
+            Self::Id3v2(err) => err.fmt(f),
+            Self::NotAiff => write![f, "file is not an aiff file"],
+        }
+    }
+}
+
+impl error::Error for SaveError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::IoError(err) => Some(err),
+            Self::Id3v2(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id3v2::frames::{FrameId, TextFrame};
+    use std::env;
+    use std::fs;
+
+    const AIFF_DATA: &[u8] = b"FORM\x00\x00\x00\x42AIFF\
+                               COMM\x00\x00\x00\x03abc\x00\
+                               NAME\x00\x00\x00\x05Title\x00\
+                               ANNO\x00\x00\x00\x03One\x00\
+                               ANNO\x00\x00\x00\x04Two\x00\
+                               SSND\x00\x00\x00\x04\x16\x16\x16\x16";
+
+    #[test]
+    fn read_text() {
+        let path = env::temp_dir().join("musikr_aiff_read.aiff");
+        fs::write(&path, AIFF_DATA).unwrap();
+
+        let tag = Tag::open(&path).unwrap();
+        assert_eq!(tag.name.as_deref(), Some("Title"));
+        assert_eq!(tag.author, None);
+        assert_eq!(tag.annotations, vec!["One", "Two"]);
+        assert!(tag.id3v2.is_none());
+    }
+
+    #[test]
+    fn save_aiff() {
+        let path = env::temp_dir().join("musikr_aiff_save.aiff");
+        fs::write(&path, AIFF_DATA).unwrap();
+
+        let mut tag = Tag::open(&path).unwrap();
+        tag.author = Some(String::from("Author"));
+        tag.annotations.clear();
+
+        let mut frame = TextFrame::new(FrameId::new(b"TIT2"));
+        frame.text = vec![String::from("Title")];
+
+        let mut id3v2 = id3v2::Tag::new();
+        id3v2.frames.insert(frame);
+        tag.id3v2 = Some(id3v2);
+
+        tag.save(&path).unwrap();
+
+        let data = fs::read(&path).unwrap();
+        let form = read_form(&mut File::open(&path).unwrap()).unwrap();
+        let ids: Vec<&[u8; 4]> = form.chunks.iter().map(|chunk| &chunk.id).collect();
+
+        assert_eq!(ids, [b"COMM", b"SSND", b"NAME", b"AUTH", b"ID3 "]);
+        assert_eq!(
+            u32::from_be_bytes(data[4..8].try_into().unwrap()) as usize,
+            data.len() - 8
+        );
+
+        let new_tag = Tag::open(&path).unwrap();
+        assert_eq!(new_tag.name.as_deref(), Some("Title"));
+        assert_eq!(new_tag.author.as_deref(), Some("Author"));
+        assert!(new_tag.annotations.is_empty());
+        assert_eq!(new_tag.id3v2.unwrap().frames["TIT2"].to_string(), "Title");
+    }
+
+    #[test]
+    fn not_aiff() {
+        let path = env::var("CARGO_MANIFEST_DIR").unwrap() + "/res/test/example.mp3";
+
+        assert!(matches!(Tag::open(&path), Err(ParseError::NotFound)));
+    }
+}
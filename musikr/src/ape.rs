@@ -0,0 +1,555 @@
+//! APE tag reading and writing.
+//!
+//! APE tags are the native metadata format of Monkey's Audio, Musepack, and WavPack files, and
+//! are also found at the end of some MP3 files. An APE tag is a flat collection of items, each
+//! of which is keyed by a case-insensitive string:
+//!
+//! ```
+//! # use std::error::Error;
+//! # use std::env;
+//! # use std::fs;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! #   let example_path = env::var("CARGO_MANIFEST_DIR").unwrap() + "/res/test/example.mp3";
+//! #   let output_path = env::temp_dir().join("musikr_ape_doctest.mp3");
+//! #   fs::copy(&example_path, &output_path)?;
+//! use musikr::ape::{Tag, items::{Item, ItemValue}};
+//! let mut tag = Tag::new();
+//! let item = Item::new(ItemValue::Text(vec![String::from("Archangel")]));
+//! tag.items.insert("Title", item);
+//! tag.save(&output_path)?;
+//! #   Ok(())
+//! # }
+//! ```
+//!
+//! Both APEv1 and APEv2 tags can be read, but tags are always written as APEv2. The tag is
+//! located by its footer at the end of the file, which may be followed by an ID3v1 tag.
+//! Tags at the beginning of a file are not supported.
+//!
+//! More information about the format can be found [here](https://wiki.hydrogenaud.io/index.php?title=APEv2_specification).
+
+pub mod items;
+
+use crate::core::io::BufStream;
+use crate::id3v1;
+use items::{Item, ItemMap};
+
+use log::{error, info};
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const ID: &[u8] = b"APETAGEX";
+
+const HEADER_SIZE: u32 = 32;
+
+const FLAG_HAS_HEADER: u32 = 1 << 31;
+const FLAG_NO_FOOTER: u32 = 1 << 30;
+const FLAG_IS_HEADER: u32 = 1 << 29;
+const FLAG_READ_ONLY: u32 = 1;
+
+/// An APE tag.
+///
+/// A tag can be created programmatically, or it can be opened from a file. More information
+/// can be found in the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct Tag {
+    version: Version,
+    size: u32,
+    /// Whether this tag should not be modified by a tagger. Musikr does not enforce this.
+    pub read_only: bool,
+    /// The items in this tag.
+    pub items: ItemMap,
+}
+
+impl Tag {
+    /// Creates an empty APEv2 tag.
+    pub fn new() -> Self {
+        Self {
+            version: Version::V2,
+            size: 0,
+            read_only: false,
+            items: ItemMap::new(),
+        }
+    }
+
+    /// Attempts to open and parse a tag in `path`.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be opened, does not contain a tag, or if the tag is malformed,
+    /// an error will be returned with a general reason for why. Specific information about
+    /// parsing errors will be logged.
+    pub fn open<P: AsRef<Path>>(path: P) -> ParseResult<Self> {
+        let mut file = File::open(path)?;
+
+        let (start, header) = locate(&mut file)?;
+
+        // Skip the header if present, since it's identical to the footer.
+        let mut data = vec![0; (header.size - HEADER_SIZE) as usize];
+        file.seek(SeekFrom::Start(start + header.header_len()))?;
+        file.read_exact(&mut data)?;
+
+        let mut stream = BufStream::new(&data);
+        let mut items = ItemMap::new();
+
+        for _ in 0..header.item_count {
+            let (key, item) = Item::parse(header.version, &mut stream)?;
+
+            // Duplicate keys are not allowed, but we don't want to fail on them either.
+            if items.insert(&key, item).is_some() {
+                info!("found duplicate item {}, replacing", key)
+            }
+        }
+
+        Ok(Self {
+            version: header.version,
+            size: header.size + header.header_len() as u32,
+            read_only: header.flags & FLAG_READ_ONLY != 0,
+            items,
+        })
+    }
+
+    /// Returns the version of this tag.
+    ///
+    /// An APEv1 tag will become an APEv2 tag once it is saved.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Returns the total size of this tag, including the header and footer, in bytes.
+    ///
+    /// This value is only updated when the tag is read or saved, so it may not be accurate
+    /// to the current contents of a tag. In a freshly created tag, this value will be `0`.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Saves the tag to `path`.
+    ///
+    /// The tag will always be written as an APEv2 tag with both a header and a footer. Any
+    /// pre-existing tag at the end of the file will be replaced, and any ID3v1 tag after it
+    /// will be kept. If the tag is empty, then the tag will be stripped from the file instead.
+    ///
+    /// # Errors
+    ///
+    /// If the tag cannot be written, then an error with a general reason will be returned.
+    /// Specific information about saving errors will be logged.
+    pub fn save<P: AsRef<Path>>(&mut self, path: P) -> SaveResult<()> {
+        if self.items.is_empty() {
+            info!("tag is empty, deleting tag instead");
+            self.size = 0;
+            return Self::strip(path);
+        }
+
+        let body = self.items.render();
+
+        // The tag size includes the footer, but not the header.
+        let size = match u32::try_from(body.len() + HEADER_SIZE as usize) {
+            Ok(size) => size,
+            Err(_) => {
+                error!("tag was larger than 4gb");
+                return Err(SaveError::TooLarge);
+            }
+        };
+
+        let mut header = TagHeader {
+            version: Version::V2,
+            size,
+            item_count: self.items.len() as u32,
+            flags: FLAG_HAS_HEADER | u32::from(self.read_only),
+        };
+
+        let mut data = Vec::with_capacity((size + HEADER_SIZE) as usize);
+
+        header.flags |= FLAG_IS_HEADER;
+        data.extend(header.render());
+        data.extend(body);
+
+        header.flags &= !FLAG_IS_HEADER;
+        data.extend(header.render());
+
+        write_tag(path, &data)?;
+
+        self.version = Version::V2;
+        self.size = size + HEADER_SIZE;
+
+        Ok(())
+    }
+
+    /// Removes any tag from the end of the file in `path`.
+    ///
+    /// Any ID3v1 tag after the tag will be kept. Nothing will be done if there is no tag present.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be opened or written to, an error will be returned.
+    pub fn strip<P: AsRef<Path>>(path: P) -> SaveResult<()> {
+        write_tag(path, &[])
+    }
+}
+
+impl Default for Tag {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The version of an APE tag.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Version {
+    /// APEv1. This version has no header and supports only text items.
+    V1,
+    /// APEv2.
+    V2,
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::V1 => write![f, "APEv1"],
+            Self::V2 => write![f, "APEv2"],
+        }
+    }
+}
+
+/// An APE tag header or footer. Both have the same layout and differ only in their flags.
+#[derive(Debug, Clone)]
+struct TagHeader {
+    version: Version,
+    size: u32,
+    item_count: u32,
+    flags: u32,
+}
+
+impl TagHeader {
+    fn parse(raw: [u8; 32]) -> ParseResult<Self> {
+        let mut stream = BufStream::new(&raw);
+
+        if stream.slice(8)? != ID {
+            return Err(ParseError::NotFound);
+        }
+
+        let version = match stream.read_le_u32()? {
+            1000 => Version::V1,
+            2000 => Version::V2,
+            version => {
+                error!("APE version {} is not supported", version);
+                return Err(ParseError::Unsupported);
+            }
+        };
+
+        let size = stream.read_le_u32()?;
+        let item_count = stream.read_le_u32()?;
+
+        // APEv1 tags have no flags, so make sure any garbage in them does not get read.
+        let flags = match version {
+            Version::V1 => 0,
+            Version::V2 => stream.read_le_u32()?,
+        };
+
+        // The size must at least include the footer, and every item is at least 11 bytes.
+        if size < HEADER_SIZE || item_count > (size - HEADER_SIZE) / 11 {
+            error!("tag size or item count is invalid");
+            return Err(ParseError::MalformedData);
+        }
+
+        Ok(Self {
+            version,
+            size,
+            item_count,
+            flags,
+        })
+    }
+
+    fn render(&self) -> Vec<u8> {
+        let mut result = Vec::with_capacity(HEADER_SIZE as usize);
+
+        let version: u32 = match self.version {
+            Version::V1 => 1000,
+            Version::V2 => 2000,
+        };
+
+        result.extend(ID);
+        result.extend(version.to_le_bytes());
+        result.extend(self.size.to_le_bytes());
+        result.extend(self.item_count.to_le_bytes());
+        result.extend(self.flags.to_le_bytes());
+        result.extend([0; 8]);
+
+        result
+    }
+
+    fn header_len(&self) -> u64 {
+        if self.flags & FLAG_HAS_HEADER != 0 {
+            HEADER_SIZE as u64
+        } else {
+            0
+        }
+    }
+}
+
+/// Finds the tag at the end of `src`, returning the position where it starts and its footer.
+fn locate<R: Read + Seek>(src: &mut R) -> ParseResult<(u64, TagHeader)> {
+    // An ID3v1 tag may come after the APE tag, so skip it.
+    let end = src.seek(SeekFrom::End(0))? - id3v1::trailer_size(src)?;
+
+    if end < HEADER_SIZE as u64 {
+        return Err(ParseError::NotFound);
+    }
+
+    let mut raw = [0; 32];
+    src.seek(SeekFrom::Start(end - HEADER_SIZE as u64))?;
+    src.read_exact(&mut raw)?;
+
+    let footer = TagHeader::parse(raw)?;
+
+    if footer.flags & (FLAG_IS_HEADER | FLAG_NO_FOOTER) != 0 {
+        error!("found a tag header where a footer was expected");
+        return Err(ParseError::MalformedData);
+    }
+
+    match end.checked_sub(footer.size as u64 + footer.header_len()) {
+        Some(start) => Ok((start, footer)),
+        None => {
+            error!("tag size is larger than the file");
+            Err(ParseError::MalformedData)
+        }
+    }
+}
+
+/// Returns the position where the APE tag at the end of `src` starts. If there is no tag, the
+/// position where the ID3v1 tag starts, or the end of `src`, is returned instead.
+pub(crate) fn trailer_start<R: Read + Seek>(src: &mut R) -> io::Result<u64> {
+    match locate(src) {
+        Ok((start, _)) => Ok(start),
+        Err(ParseError::IoError(err)) => Err(err),
+        Err(_) => Ok(src.seek(SeekFrom::End(0))? - id3v1::trailer_size(src)?),
+    }
+}
+
+fn write_tag<P: AsRef<Path>>(path: P, data: &[u8]) -> SaveResult<()> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+
+    // Find where the old tag was. If there is none, we will write the tag right before
+    // the ID3v1 tag, if present.
+    let (start, end) = match locate(&mut file) {
+        Ok((start, footer)) => {
+            info!("found previously written tag, will be overwritten");
+            (start, start + footer.header_len() + footer.size as u64)
+        }
+        Err(ParseError::IoError(err)) => return Err(SaveError::IoError(err)),
+        Err(_) => {
+            let end = file.seek(SeekFrom::End(0))? - id3v1::trailer_size(&mut file)?;
+            (end, end)
+        }
+    };
+
+    // Anything after the tag is either nothing or an ID3v1 tag, so it's fine to keep it
+    // in memory.
+    let mut trailer = Vec::new();
+    file.seek(SeekFrom::Start(end))?;
+    file.read_to_end(&mut trailer)?;
+
+    file.seek(SeekFrom::Start(start))?;
+    file.write_all(data)?;
+    file.write_all(&trailer)?;
+    file.set_len(start + (data.len() + trailer.len()) as u64)?;
+    file.flush()?;
+
+    Ok(())
+}
+
+/// The result given after a parsing operation.
+pub type ParseResult<T> = Result<T, ParseError>;
+
+/// The error type returned when parsing APE tags.
+#[derive(Debug)]
+pub enum ParseError {
+    /// Generic IO errors. This either means that a problem occurred while opening the file
+    /// for a tag, or an unexpected EOF was encountered while parsing.
+    IoError(io::Error),
+    /// A part of the tag was not valid.
+    MalformedData,
+    /// The tag or a element of the tag is unsupported.
+    Unsupported,
+    /// The tag was not found in the given file.
+    NotFound,
+}
+
+impl From<io::Error> for ParseError {
+    fn from(other: io::Error) -> Self {
+        ParseError::IoError(other)
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(err) => err.fmt(f),
+            Self::MalformedData => write![f, "malformed data"],
+            Self::Unsupported => write![f, "unsupported"],
+            Self::NotFound => write![f, "not found"],
+        }
+    }
+}
+
+impl error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::IoError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// The result given after a save operation.
+pub type SaveResult<T> = Result<T, SaveError>;
+
+/// The error type returned when saving APE tags.
+#[derive(Debug)]
+pub enum SaveError {
+    /// Generic IO errors. This means that a problem occurred while writing the tag to a file.
+    IoError(io::Error),
+    /// The tag was too large to be written.
+    TooLarge,
+}
+
+impl From<io::Error> for SaveError {
+    fn from(other: io::Error) -> Self {
+        SaveError::IoError(other)
+    }
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(err) => err.fmt(f),
+            Self::TooLarge => write![f, "tag is too large to be saved"],
+        }
+    }
+}
+
+impl error::Error for SaveError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::IoError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use items::ItemValue;
+    use std::env;
+    use std::fs;
+
+    const APEV1_DATA: &[u8] = b"\x05\x00\x00\x00\x00\x00\x00\x00\
+                                Title\0\
+                                Title\
+                                APETAGEX\
+                                \xE8\x03\x00\x00\
+                                \x33\x00\x00\x00\
+                                \x01\x00\x00\x00\
+                                \x16\x16\x16\x16\
+                                \x00\x00\x00\x00\x00\x00\x00\x00";
+
+    fn text(string: &str) -> Item {
+        Item::new(ItemValue::Text(vec![String::from(string)]))
+    }
+
+    fn copy_example(name: &str) -> std::path::PathBuf {
+        let path = env::var("CARGO_MANIFEST_DIR").unwrap() + "/res/test/example.mp3";
+        let out = env::temp_dir().join(name);
+        fs::copy(&path, &out).unwrap();
+        out
+    }
+
+    #[test]
+    fn parse_apev1() {
+        let out = copy_example("musikr_apev1.mp3");
+
+        let mut data = fs::read(&out).unwrap();
+        data.extend(APEV1_DATA);
+        fs::write(&out, data).unwrap();
+
+        let tag = Tag::open(&out).unwrap();
+
+        assert_eq!(tag.version(), Version::V1);
+        assert_eq!(tag.size(), 0x33);
+        assert!(!tag.read_only);
+        assert_eq!(tag.items["title"], text("Title"));
+    }
+
+    #[test]
+    fn save_apev2() {
+        let out = copy_example("musikr_apev2.mp3");
+        let len = fs::metadata(&out).unwrap().len();
+
+        assert!(matches!(Tag::open(&out), Err(ParseError::NotFound)));
+
+        let mut tag = Tag::new();
+        tag.read_only = true;
+        tag.items.insert("Title", text("Title"));
+        tag.items.insert(
+            "Cover Art (Front)",
+            Item::new(ItemValue::Binary(vec![0x16; 16])),
+        );
+        tag.items.insert(
+            "Lyrics",
+            Item::new(ItemValue::Link(String::from("lyrics.txt"))),
+        );
+        tag.save(&out).unwrap();
+
+        assert_eq!(fs::metadata(&out).unwrap().len(), len + tag.size() as u64);
+
+        let new_tag = Tag::open(&out).unwrap();
+
+        assert_eq!(new_tag.version(), Version::V2);
+        assert_eq!(new_tag.size(), tag.size());
+        assert!(new_tag.read_only);
+        assert_eq!(new_tag.items, tag.items);
+
+        Tag::strip(&out).unwrap();
+
+        assert_eq!(fs::metadata(&out).unwrap().len(), len);
+    }
+
+    #[test]
+    fn save_before_id3v1() {
+        let out = copy_example("musikr_ape_id3v1.mp3");
+        let len = fs::metadata(&out).unwrap().len();
+
+        let mut id3v1_tag = id3v1::Tag::new();
+        id3v1_tag.title = String::from("Title");
+        id3v1_tag.save(&out).unwrap();
+
+        let mut tag = Tag::new();
+        tag.items.insert("Title", text("Title"));
+        tag.save(&out).unwrap();
+
+        // Re-saving a larger tag must replace the old tag and keep the ID3v1 tag intact.
+        tag.items.insert("Artist", text("Artist"));
+        tag.save(&out).unwrap();
+
+        assert_eq!(
+            fs::metadata(&out).unwrap().len(),
+            len + 128 + tag.size() as u64
+        );
+        assert_eq!(Tag::open(&out).unwrap().items, tag.items);
+        assert_eq!(id3v1::Tag::open(&out).unwrap(), id3v1_tag);
+
+        Tag::strip(&out).unwrap();
+
+        assert_eq!(fs::metadata(&out).unwrap().len(), len + 128);
+        assert_eq!(id3v1::Tag::open(&out).unwrap(), id3v1_tag);
+    }
+}
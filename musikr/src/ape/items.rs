@@ -0,0 +1,367 @@
+//! APE tag items.
+
+use crate::ape::{ParseError, ParseResult, Version};
+use crate::core::io::BufStream;
+use crate::core::string::{self, Encoding};
+
+use log::error;
+use std::fmt::{self, Display, Formatter};
+use std::ops::Index;
+
+/// A single APE tag item.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Item {
+    /// The value of this item.
+    pub value: ItemValue,
+    /// Whether this item should not be modified by a tagger. Musikr does not enforce this.
+    pub read_only: bool,
+}
+
+impl Item {
+    /// Creates a new item that is not read-only.
+    pub fn new(value: ItemValue) -> Self {
+        Self {
+            value,
+            read_only: false,
+        }
+    }
+
+    pub(crate) fn parse(version: Version, stream: &mut BufStream) -> ParseResult<(String, Self)> {
+        let size = stream.read_le_u32()? as usize;
+        let flags = stream.read_le_u32()?;
+
+        let key = String::from_utf8(stream.search(&[0]).to_vec())
+            .ok()
+            .and_then(|key| key.strip_suffix('\0').map(String::from))
+            .filter(|key| is_valid_key(key))
+            .ok_or_else(|| {
+                error!("item key is not valid");
+                ParseError::MalformedData
+            })?;
+
+        let mut data = stream.slice_stream(size)?;
+
+        // APEv1 has no item flags, with all items being text.
+        if version == Version::V1 {
+            return Ok((key, Self::new(ItemValue::Text(parse_text(&mut data)))));
+        }
+
+        let value = match (flags >> 1) & 0x3 {
+            0 => ItemValue::Text(parse_text(&mut data)),
+            1 => ItemValue::Binary(data.take_rest().to_vec()),
+            2 => ItemValue::Link(string::read(Encoding::Utf8, &mut data)),
+            _ => {
+                error!("item type is reserved");
+                return Err(ParseError::MalformedData);
+            }
+        };
+
+        Ok((
+            key,
+            Self {
+                value,
+                read_only: flags & 0x1 != 0,
+            },
+        ))
+    }
+
+    pub(crate) fn render(&self, key: &str) -> Vec<u8> {
+        let (kind, data) = match &self.value {
+            ItemValue::Text(text) => (0, render_text(text)),
+            ItemValue::Binary(data) => (1, data.clone()),
+            ItemValue::Link(link) => (2, string::render(Encoding::Utf8, link)),
+        };
+
+        let flags: u32 = (kind << 1) | u32::from(self.read_only);
+
+        let mut result = Vec::with_capacity(8 + key.len() + 1 + data.len());
+
+        result.extend((data.len() as u32).to_le_bytes());
+        result.extend(flags.to_le_bytes());
+        result.extend(key.as_bytes());
+        result.push(0);
+        result.extend(data);
+
+        result
+    }
+}
+
+impl Display for Item {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+/// The value of an APE tag item.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ItemValue {
+    /// One or more UTF-8 strings.
+    Text(Vec<String>),
+    /// Arbitrary binary data. Cover art is stored as a filename, a NUL terminator,
+    /// and then the image data.
+    Binary(Vec<u8>),
+    /// A UTF-8 locator of information stored elsewhere, such as a URL or file path.
+    Link(String),
+}
+
+impl Display for ItemValue {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Text(text) => write![f, "{}", text.join(", ")],
+            Self::Binary(data) => write![f, "[{} bytes of binary data]", data.len()],
+            Self::Link(link) => write![f, "{}", link],
+        }
+    }
+}
+
+/// A collection of APE tag items.
+///
+/// Items are keyed by a case-insensitive ASCII string, with the casing of the key being
+/// preserved when written. Items are kept in the order that they were added.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ItemMap {
+    items: Vec<(String, Item)>,
+}
+
+impl ItemMap {
+    /// Creates an empty collection.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the item that matches `key`, if present.
+    pub fn get(&self, key: &str) -> Option<&Item> {
+        self.position(key).map(|i| &self.items[i].1)
+    }
+
+    /// Returns a mutable reference to the item that matches `key`, if present.
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Item> {
+        self.position(key).map(move |i| &mut self.items[i].1)
+    }
+
+    /// Returns whether an item matching `key` is present.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// Inserts an item, replacing and returning any item with the same key.
+    ///
+    /// # Panics
+    /// This function will panic if `key` is not a valid item key. If the validity of the key
+    /// cannot be assured, [`try_insert`](ItemMap::try_insert) should be used instead.
+    pub fn insert(&mut self, key: &str, item: Item) -> Option<Item> {
+        self.try_insert(key, item).unwrap()
+    }
+
+    /// Fallibly inserts an item, replacing and returning any item with the same key.
+    ///
+    /// # Errors
+    /// If `key` is not 2-255 printable ASCII characters or is one of the reserved keys
+    /// `ID3`, `TAG`, `OggS`, or `MP+`, then an error will be returned.
+    pub fn try_insert(&mut self, key: &str, item: Item) -> Result<Option<Item>, ItemKeyError> {
+        if !is_valid_key(key) {
+            return Err(ItemKeyError(()));
+        }
+
+        match self.position(key) {
+            Some(i) => Ok(Some(std::mem::replace(&mut self.items[i].1, item))),
+            None => {
+                self.items.push((String::from(key), item));
+                Ok(None)
+            }
+        }
+    }
+
+    /// Removes and returns the item matching `key`, if present.
+    pub fn remove(&mut self, key: &str) -> Option<Item> {
+        self.position(key).map(|i| self.items.remove(i).1)
+    }
+
+    /// Removes all items that do not satisfy the predicate.
+    pub fn retain<F: FnMut(&str, &mut Item) -> bool>(&mut self, mut f: F) {
+        self.items.retain_mut(|(key, item)| f(key, item))
+    }
+
+    /// Removes all items.
+    pub fn clear(&mut self) {
+        self.items.clear()
+    }
+
+    /// Returns an iterator over the keys and items in this collection.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Item)> {
+        self.items.iter().map(|(key, item)| (key.as_str(), item))
+    }
+
+    /// Returns a mutable iterator over the keys and items in this collection.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&str, &mut Item)> {
+        self.items
+            .iter_mut()
+            .map(|(key, item)| (key.as_str(), item))
+    }
+
+    /// Returns an iterator over the keys in this collection.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.items.iter().map(|(key, _)| key.as_str())
+    }
+
+    /// Returns the number of items in this collection.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns whether this collection is empty.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub(crate) fn render(&self) -> Vec<u8> {
+        self.items
+            .iter()
+            .flat_map(|(key, item)| item.render(key))
+            .collect()
+    }
+
+    fn position(&self, key: &str) -> Option<usize> {
+        self.items
+            .iter()
+            .position(|(other, _)| other.eq_ignore_ascii_case(key))
+    }
+}
+
+impl Index<&str> for ItemMap {
+    type Output = Item;
+
+    fn index(&self, key: &str) -> &Self::Output {
+        self.get(key).unwrap()
+    }
+}
+
+impl_newtype_err! {
+    /// The error returned when an APE item key is not valid.
+    ItemKeyError => "item key was not 2-255 printable ascii characters or was reserved"
+}
+
+fn is_valid_key(key: &str) -> bool {
+    (2..=255).contains(&key.len())
+        && key.bytes().all(|ch| (0x20..=0x7E).contains(&ch))
+        && !["ID3", "TAG", "OggS", "MP+"].contains(&key)
+}
+
+fn parse_text(stream: &mut BufStream) -> Vec<String> {
+    // Multiple values are separated by a NUL terminator.
+    let mut text = Vec::new();
+
+    while !stream.is_empty() {
+        text.push(string::read_terminated(Encoding::Utf8, stream))
+    }
+
+    text
+}
+
+fn render_text(text: &[String]) -> Vec<u8> {
+    let mut result = Vec::new();
+
+    for (i, string) in text.iter().enumerate() {
+        if i > 0 {
+            result.push(0)
+        }
+
+        result.extend(string::render(Encoding::Utf8, string))
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT_DATA: &[u8] = b"\x0A\x00\x00\x00\x01\x00\x00\x00\
+                               Artist\0\
+                               Test 1\0Two";
+
+    const BINARY_DATA: &[u8] = b"\x09\x00\x00\x00\x02\x00\x00\x00\
+                                 Cover Art (Front)\0\
+                                 a.png\0\x89PN";
+
+    const LINK_DATA: &[u8] = b"\x12\x00\x00\x00\x04\x00\x00\x00\
+                               Lyrics\0\
+                               https://lyrics.com";
+
+    #[test]
+    fn parse_text() {
+        let (key, item) = Item::parse(Version::V2, &mut BufStream::new(TEXT_DATA)).unwrap();
+
+        assert_eq!(key, "Artist");
+        assert!(item.read_only);
+        assert_eq!(
+            item.value,
+            ItemValue::Text(vec![String::from("Test 1"), String::from("Two")])
+        );
+    }
+
+    #[test]
+    fn parse_binary() {
+        let (key, item) = Item::parse(Version::V2, &mut BufStream::new(BINARY_DATA)).unwrap();
+
+        assert_eq!(key, "Cover Art (Front)");
+        assert!(!item.read_only);
+        assert_eq!(item.value, ItemValue::Binary(b"a.png\0\x89PN".to_vec()));
+    }
+
+    #[test]
+    fn parse_link() {
+        let (key, item) = Item::parse(Version::V2, &mut BufStream::new(LINK_DATA)).unwrap();
+
+        assert_eq!(key, "Lyrics");
+        assert_eq!(
+            item.value,
+            ItemValue::Link(String::from("https://lyrics.com"))
+        );
+    }
+
+    #[test]
+    fn parse_v1() {
+        // APEv1 has no item types, so the flags should be ignored.
+        let (key, item) = Item::parse(Version::V1, &mut BufStream::new(LINK_DATA)).unwrap();
+
+        assert_eq!(key, "Lyrics");
+        assert_eq!(
+            item.value,
+            ItemValue::Text(vec![String::from("https://lyrics.com")])
+        );
+    }
+
+    #[test]
+    fn render_items() {
+        let mut item = Item::new(ItemValue::Text(vec![
+            String::from("Test 1"),
+            String::from("Two"),
+        ]));
+        item.read_only = true;
+
+        assert_eq!(item.render("Artist"), TEXT_DATA);
+
+        let item = Item::new(ItemValue::Binary(b"a.png\0\x89PN".to_vec()));
+        assert_eq!(item.render("Cover Art (Front)"), BINARY_DATA);
+
+        let item = Item::new(ItemValue::Link(String::from("https://lyrics.com")));
+        assert_eq!(item.render("Lyrics"), LINK_DATA);
+    }
+
+    #[test]
+    fn item_keys() {
+        let mut items = ItemMap::new();
+        let title = Item::new(ItemValue::Text(vec![String::from("Title")]));
+
+        assert!(items.insert("Title", title.clone()).is_none());
+        assert!(items.insert("TITLE", title.clone()).is_some());
+        assert_eq!(items.len(), 1);
+        assert_eq!(items.keys().next(), Some("Title"));
+        assert_eq!(items["title"], title);
+
+        assert!(items.try_insert("TAG", title.clone()).is_err());
+        assert!(items.try_insert("A", title.clone()).is_err());
+        assert!(items.try_insert("Caf\u{e9}", title).is_err());
+    }
+}
@@ -0,0 +1,285 @@
+//! Chunk walking for IFF-style containers.
+//!
+//! RIFF and AIFF files are both a "form" chunk that contains a list of `ID` + `size` + `data`
+//! chunks, with the only real difference being the byte order of the sizes. Chunks are padded
+//! to an even length, with the pad byte not being included in the size.
+
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use super::io::{write_spliced_with, SaveOptions, Splice};
+
+/// The byte order of a container.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum ByteOrder {
+    Little,
+    Big,
+}
+
+impl ByteOrder {
+    fn read_u32(&self, raw: [u8; 4]) -> u32 {
+        match self {
+            Self::Little => u32::from_le_bytes(raw),
+            Self::Big => u32::from_be_bytes(raw),
+        }
+    }
+
+    fn render_u32(&self, value: u32) -> [u8; 4] {
+        match self {
+            Self::Little => value.to_le_bytes(),
+            Self::Big => value.to_be_bytes(),
+        }
+    }
+}
+
+/// The location of a chunk inside of a form.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) struct ChunkInfo {
+    pub(crate) id: [u8; 4],
+    /// The type of a `LIST` chunk, such as `INFO`.
+    pub(crate) list_type: Option<[u8; 4]>,
+    /// The offset of the chunk header.
+    pub(crate) start: u64,
+    /// The offset of the end of the chunk, including any pad byte.
+    pub(crate) end: u64,
+    pub(crate) size: u32,
+}
+
+impl ChunkInfo {
+    /// Returns the offset of the chunk data.
+    pub(crate) fn data_start(&self) -> u64 {
+        self.start + 8
+    }
+}
+
+/// A form chunk and the chunks that it contains.
+#[derive(Debug, Clone)]
+pub(crate) struct Form {
+    pub(crate) order: ByteOrder,
+    pub(crate) kind: [u8; 4],
+    pub(crate) chunks: Vec<ChunkInfo>,
+    /// The end of the form, which any data after is not part of the container.
+    end: u64,
+}
+
+impl Form {
+    /// Reads the form in `src` if it starts with `magic`, such as `RIFF`. `None` is returned if
+    /// the magic does not match.
+    pub(crate) fn read<R: Read + Seek>(
+        src: &mut R,
+        magic: &[u8; 4],
+        order: ByteOrder,
+    ) -> io::Result<Option<Self>> {
+        let len = src.seek(SeekFrom::End(0))?;
+        src.seek(SeekFrom::Start(0))?;
+
+        let mut header = [0; 12];
+
+        if len < 12 {
+            return Ok(None);
+        }
+
+        src.read_exact(&mut header)?;
+
+        if &header[0..4] != magic {
+            return Ok(None);
+        }
+
+        let size = order.read_u32(header[4..8].try_into().unwrap());
+        let kind = header[8..12].try_into().unwrap();
+
+        // Some writers do not update the form size, so the form is assumed to extend up to
+        // the end of the file if it's too large.
+        let end = u64::min(u64::from(size) + 8, len);
+        let mut chunks = Vec::new();
+        let mut pos = 12;
+
+        while pos + 8 <= end {
+            let mut header = [0; 8];
+            src.seek(SeekFrom::Start(pos))?;
+            src.read_exact(&mut header)?;
+
+            let id: [u8; 4] = header[0..4].try_into().unwrap();
+            let size = order.read_u32(header[4..8].try_into().unwrap());
+
+            let list_type = if &id == b"LIST" && size >= 4 {
+                let mut list_type = [0; 4];
+                src.read_exact(&mut list_type)?;
+                Some(list_type)
+            } else {
+                None
+            };
+
+            // A truncated chunk is clamped to the end of the form.
+            let chunk_end = u64::min(pos + 8 + u64::from(size) + u64::from(size & 1), end);
+
+            chunks.push(ChunkInfo {
+                id,
+                list_type,
+                start: pos,
+                end: chunk_end,
+                size,
+            });
+
+            pos = chunk_end;
+        }
+
+        Ok(Some(Self {
+            order,
+            kind,
+            chunks,
+            end: pos,
+        }))
+    }
+
+    /// Reads the data of `chunk`.
+    pub(crate) fn read_chunk<R: Read + Seek>(
+        src: &mut R,
+        chunk: &ChunkInfo,
+    ) -> io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        src.seek(SeekFrom::Start(chunk.data_start()))?;
+        src.take(u64::from(chunk.size)).read_to_end(&mut data)?;
+
+        if data.len() != chunk.size as usize {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+
+        Ok(data)
+    }
+
+    /// Removes every chunk that `remove` matches, and then appends `new` to the end of the form,
+    /// updating the form size.
+    ///
+    /// Only the removed chunks, the form size, and the end of the form are written, with the
+    /// chunks in between being moved in blocks. The file is written following `options`.
+    pub(crate) fn replace_chunks<P: AsRef<Path>, F: Fn(&ChunkInfo) -> bool>(
+        &self,
+        path: P,
+        remove: F,
+        new: &[u8],
+        options: &SaveOptions,
+    ) -> io::Result<()> {
+        let removed: Vec<&ChunkInfo> = self.chunks.iter().filter(|chunk| remove(chunk)).collect();
+        let removed_len: u64 = removed.iter().map(|chunk| chunk.end - chunk.start).sum();
+        let form_size = self.form_size(self.end - removed_len + new.len() as u64)?;
+
+        let mut splices = vec![Splice::new(4, 8, &form_size)];
+
+        for chunk in removed {
+            splices.push(Splice::new(chunk.start, chunk.end, &[]));
+        }
+
+        // Anything after the form, such as an ID3v1 tag, is kept after the new chunks.
+        splices.push(Splice::new(self.end, self.end, new));
+
+        write_spliced_with(path, &splices, options, &mut |_, _| {})
+    }
+
+    fn form_size(&self, end: u64) -> io::Result<[u8; 4]> {
+        u32::try_from(end - 8)
+            .map(|size| self.order.render_u32(size))
+            .map_err(|_| io::Error::new(ErrorKind::InvalidInput, "form is larger than 4gb"))
+    }
+}
+
+/// Renders a chunk, adding a pad byte if needed.
+pub(crate) fn render_chunk(id: &[u8; 4], data: &[u8], order: ByteOrder) -> Vec<u8> {
+    let mut result = Vec::with_capacity(data.len() + 9);
+
+    result.extend(id);
+    result.extend(order.render_u32(data.len() as u32));
+    result.extend(data);
+
+    if !data.len().is_multiple_of(2) {
+        result.push(0);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::io::Cursor;
+
+    const FORM_DATA: &[u8] = b"RIFF\x28\x00\x00\x00WAVE\
+                               fmt \x03\x00\x00\x00abc\x00\
+                               LIST\x04\x00\x00\x00INFO\
+                               data\x04\x00\x00\x00\x16\x16\x16\x16";
+
+    #[test]
+    fn read_form() {
+        let form = Form::read(&mut Cursor::new(FORM_DATA), b"RIFF", ByteOrder::Little)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(&form.kind, b"WAVE");
+        assert_eq!(form.chunks.len(), 3);
+        assert_eq!(form.chunks[0].end, 24);
+        assert_eq!(form.chunks[1].list_type, Some(*b"INFO"));
+        assert_eq!(form.end, FORM_DATA.len() as u64);
+
+        assert!(
+            Form::read(&mut Cursor::new(FORM_DATA), b"FORM", ByteOrder::Big)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn replace_chunks() {
+        let path = env::temp_dir().join("musikr_iff_replace.wav");
+        fs::write(&path, FORM_DATA).unwrap();
+
+        let form = Form::read(&mut Cursor::new(FORM_DATA), b"RIFF", ByteOrder::Little)
+            .unwrap()
+            .unwrap();
+
+        // Removing a chunk in the middle moves the chunks after it.
+        let new = render_chunk(b"id3 ", b"tag", ByteOrder::Little);
+        form.replace_chunks(
+            &path,
+            |chunk| &chunk.id == b"LIST",
+            &new,
+            &SaveOptions::default(),
+        )
+        .unwrap();
+
+        let data = fs::read(&path).unwrap();
+        assert_eq!(
+            data,
+            b"RIFF\x28\x00\x00\x00WAVE\
+              fmt \x03\x00\x00\x00abc\x00\
+              data\x04\x00\x00\x00\x16\x16\x16\x16\
+              id3 \x03\x00\x00\x00tag\x00"
+        );
+
+        // Data after the form is kept after the new chunks.
+        let mut data = data;
+        data.extend(b"TAG");
+        fs::write(&path, &data).unwrap();
+
+        let form = Form::read(&mut Cursor::new(&data), b"RIFF", ByteOrder::Little)
+            .unwrap()
+            .unwrap();
+
+        form.replace_chunks(
+            &path,
+            |chunk| &chunk.id == b"id3 ",
+            &[],
+            &SaveOptions::atomic(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            fs::read(&path).unwrap(),
+            b"RIFF\x1C\x00\x00\x00WAVE\
+              fmt \x03\x00\x00\x00abc\x00\
+              data\x04\x00\x00\x00\x16\x16\x16\x16\
+              TAG"
+        );
+    }
+}
@@ -1,10 +1,13 @@
 /// Shared Tag IO.
 use std::error;
+use std::ffi::OsString;
 use std::fmt::{self, Display, Formatter};
-use std::fs::OpenOptions;
-use std::io::{self, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::fs::{self, File, FileTimes, OpenOptions};
+use std::io::{self, BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
 use std::ops::Range;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 /// An ergonomics layer around a byte slice.
 ///
@@ -207,7 +210,7 @@ impl<'a> BufStream<'a> {
     ///
     /// # Errors
     /// If the slice bounds are outside of the range of the stream, then an error is returned.
-    pub fn slice_stream(&mut self, n: usize) -> io::Result<BufStream> {
+    pub fn slice_stream(&mut self, n: usize) -> io::Result<BufStream<'_>> {
         Ok(BufStream::new(self.slice(n)?))
     }
 
@@ -347,40 +350,534 @@ fn oob_error(pos: usize, len: usize) -> io::Error {
     )
 }
 
+/// The size of the blocks used when moving data in a file. This bounds the amount of memory
+/// used when writing a tag, regardless of the size of the file.
+const BLOCK_SIZE: u64 = 64 * 1024;
+
+/// A callback that is given the number of bytes moved so far and the total number of bytes
+/// that need to be moved when writing a file.
+pub type Progress<'a> = &'a mut dyn FnMut(u64, u64);
+
 /// Replace up to `end` bytes in a file with `data`.
 pub fn write_replaced<P: AsRef<Path>>(path: P, data: &[u8], end: u64) -> io::Result<()> {
-    match data.len() as u64 {
-        len if len == end => {
-            let mut file = OpenOptions::new().create(true).write(true).open(&path)?;
+    write_replaced_with(path, data, end, &SaveOptions::default(), &mut |_, _| {})
+}
+
+/// Replace up to `end` bytes in `dst` with `data`, returning the new length of `dst`.
+///
+/// The data after `end` is moved in fixed-size blocks, with `progress` being called after
+/// each block. If the new length is smaller than the old length, any bytes past the new
+/// length are left unchanged, as a generic stream cannot be truncated.
+pub fn write_replaced_in<S: Read + Write + Seek>(
+    dst: &mut S,
+    data: &[u8],
+    end: u64,
+    progress: Progress,
+) -> io::Result<u64> {
+    write_spliced_in(dst, &[Splice::new(0, end, data)], progress)
+}
+
+/// A replacement of the bytes from `start` to `end` in a file with `data`.
+#[derive(Debug, Clone, Copy)]
+pub struct Splice<'a> {
+    pub start: u64,
+    pub end: u64,
+    pub data: &'a [u8],
+}
+
+impl<'a> Splice<'a> {
+    /// Creates a splice that replaces the bytes from `start` to `end` with `data`.
+    pub fn new(start: u64, end: u64, data: &'a [u8]) -> Self {
+        Self { start, end, data }
+    }
+}
+
+/// Applies `splices` to `dst`, returning the new length of `dst`. The splices must be sorted
+/// and must not overlap.
+///
+/// The data between the splices is moved in fixed-size blocks, with `progress` being called
+/// after each block. Like [`write_replaced_in`](write_replaced_in), any bytes past the new
+/// length are left unchanged.
+pub fn write_spliced_in<S: Read + Write + Seek>(
+    dst: &mut S,
+    splices: &[Splice],
+    progress: Progress,
+) -> io::Result<u64> {
+    let len = dst.seek(SeekFrom::End(0))?;
+    let plan = SplicePlan::new(splices, len);
+
+    // Data moving backward is moved from the start of the stream, and data moving forward is
+    // moved from the end, so that no data is overwritten before it has been moved.
+    let (backward, forward): (Vec<_>, Vec<_>) = plan
+        .kept
+        .iter()
+        .filter(|range| range.from != range.to && range.len > 0)
+        .partition(|range| range.to < range.from);
+
+    let total = backward.iter().chain(&forward).map(|range| range.len).sum();
+    let mut done = 0;
+
+    for range in backward.iter().chain(forward.iter().rev()) {
+        move_range(dst, range.from, range.to, range.len, &mut |moved, _| {
+            progress(done + moved, total)
+        })?;
+
+        done += range.len;
+    }
 
-            // The lengths match, we can just blit directly.
-            file.write_all(data)?;
-            file.flush()
+    for (pos, data) in plan.writes {
+        dst.seek(SeekFrom::Start(pos))?;
+        dst.write_all(data)?;
+    }
+
+    dst.flush()?;
+
+    Ok(plan.len)
+}
+
+/// Where the data of a file ends up once splices are applied to it.
+struct SplicePlan<'a> {
+    /// The ranges of the file that are kept, which alternate with the new data.
+    kept: Vec<KeptRange>,
+    /// The new data, alongside the position it's written to.
+    writes: Vec<(u64, &'a [u8])>,
+    /// The new length of the file.
+    len: u64,
+}
+
+/// A range of a file that is kept when splicing, moving from `from` to `to`.
+struct KeptRange {
+    from: u64,
+    to: u64,
+    len: u64,
+}
+
+impl<'a> SplicePlan<'a> {
+    fn new(splices: &[Splice<'a>], len: u64) -> Self {
+        let mut kept = Vec::new();
+        let mut writes = Vec::new();
+        let mut pos = 0;
+        let mut new_pos = 0;
+
+        for splice in splices {
+            let start = u64::min(splice.start, len);
+            let end = u64::min(splice.end, len);
+            debug_assert!(pos <= start && start <= end, "splices overlap");
+
+            kept.push(KeptRange {
+                from: pos,
+                to: new_pos,
+                len: start - pos,
+            });
+
+            new_pos += start - pos;
+            writes.push((new_pos, splice.data));
+            new_pos += splice.data.len() as u64;
+            pos = end;
+        }
+
+        kept.push(KeptRange {
+            from: pos,
+            to: new_pos,
+            len: len - pos,
+        });
+
+        Self {
+            kept,
+            writes,
+            len: new_pos + (len - pos),
         }
+    }
+}
+
+/// Moves `len` bytes at `from` to `to` in `dst`, one block at a time.
+fn move_range<S: Read + Write + Seek>(
+    dst: &mut S,
+    from: u64,
+    to: u64,
+    len: u64,
+    progress: Progress,
+) -> io::Result<()> {
+    let mut buf = vec![0; u64::min(len, BLOCK_SIZE) as usize];
+    let mut moved = 0;
+
+    while moved < len {
+        let n = u64::min(len - moved, BLOCK_SIZE);
+
+        // When moving data forward, start from the end so that no data is overwritten
+        // before it has been moved.
+        let offset = if to > from { len - moved - n } else { moved };
+        let block = &mut buf[..n as usize];
+
+        dst.seek(SeekFrom::Start(from + offset))?;
+        dst.read_exact(block)?;
+        dst.seek(SeekFrom::Start(to + offset))?;
+        dst.write_all(block)?;
+
+        moved += n;
+        progress(moved, len);
+    }
+
+    Ok(())
+}
+
+/// Copies `len` bytes from `src` to `dst`, one block at a time.
+fn copy_blocks<R: Read, W: Write>(
+    src: &mut R,
+    dst: &mut W,
+    len: u64,
+    progress: Progress,
+) -> io::Result<()> {
+    let mut buf = vec![0; u64::min(len, BLOCK_SIZE) as usize];
+    let mut moved = 0;
+
+    while moved < len {
+        let block = &mut buf[..u64::min(len - moved, BLOCK_SIZE) as usize];
+
+        src.read_exact(block)?;
+        dst.write_all(block)?;
+
+        moved += block.len() as u64;
+        progress(moved, len);
+    }
+
+    Ok(())
+}
+
+/// Options for how a tag is written to a file.
+///
+/// By default, a file is modified in-place, which is fast but will destroy the file if the
+/// process is interrupted while writing. If `atomic` is enabled, the file will instead be
+/// copied to a temporary file next to the original, which is then renamed over the original
+/// once it has been fully written and synced to disk. Since the original file is replaced,
+/// any hard links to it will not see the new data.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SaveOptions {
+    /// Whether to write the file to a temporary file and then rename it over the original.
+    /// If the new data is the same size as the data it replaces, such as when a tag fits
+    /// in its existing padding, the file will still be modified in-place.
+    pub atomic: bool,
+    /// Whether an atomically written file should keep the permissions of the original file.
+    /// Files modified in-place will always keep their permissions.
+    pub preserve_permissions: bool,
+    /// Whether the file should keep its original modification time.
+    pub preserve_mtime: bool,
+    /// How much padding to add after a tag.
+    pub padding: PaddingStrategy,
+    /// Whether to write frames that are marked as read-only even if they were modified.
+    pub force: bool,
+    /// How the restrictions declared by an ID3v2.4 tag are handled.
+    pub restrictions: RestrictionMode,
+}
+
+impl SaveOptions {
+    /// Creates options for an atomic save that preserves the file permissions.
+    pub fn atomic() -> Self {
+        Self {
+            atomic: true,
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for SaveOptions {
+    fn default() -> Self {
+        Self {
+            atomic: false,
+            preserve_permissions: true,
+            preserve_mtime: false,
+            padding: PaddingStrategy::default(),
+            force: false,
+            restrictions: RestrictionMode::default(),
+        }
+    }
+}
+
+/// How the restrictions declared in the extended header of an ID3v2.4 tag are handled when
+/// it's saved.
+///
+/// Restrictions are only a declaration of how a tag was encoded, so by default they are written
+/// without being checked. Violations are logged regardless of the mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RestrictionMode {
+    /// Write the tag without checking it against its restrictions.
+    #[default]
+    Ignore,
+    /// Return an error if the tag does not comply with its restrictions.
+    Check,
+    /// Re-encode and truncate text and remove pictures so that the tag complies with its
+    /// restrictions. An error is returned if the tag still does not comply, such as when it
+    /// has too many frames or is too large.
+    Enforce,
+}
 
-        _ => {
-            // The lengths do not match, read the rest of the file and then re-blit all
-            // the data in sequence. This isn't efficent, but theres noting else we can do.
-            let keep = match OpenOptions::new().read(true).open(&path) {
-                Ok(mut file) => {
-                    let mut keep = Vec::with_capacity(file.stream_position()? as usize);
-                    file.seek(SeekFrom::Start(end))?;
-                    file.read_to_end(&mut keep)?;
-                    keep
+/// How much padding is added after a tag when it's saved.
+///
+/// Padding allows a tag to grow without the rest of the file having to be moved. If a tag
+/// is written with the same size as the tag it replaces, then it can be written in-place.
+/// To make this more likely, all strategies except for [`Auto`](PaddingStrategy::Auto) and
+/// [`Minimal`](PaddingStrategy::Minimal) will reuse the space of an existing tag if the
+/// new tag and its padding fit in it.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum PaddingStrategy {
+    /// If the tag fits in the existing tag, use the remaining space up to 1% of the file size.
+    /// Otherwise, add 1 KiB of padding.
+    #[default]
+    Auto,
+    /// Add a fixed number of bytes of padding.
+    Fixed(u64),
+    /// Add a percentage of the file size as padding.
+    Percent(f32),
+    /// Only use the space of an existing tag. If the tag does not fit, no padding is added.
+    ReuseOnly,
+    /// Never add padding, shrinking the tag to its minimum size.
+    Minimal,
+    /// Add padding until the tag is a multiple of the given block size.
+    Block(u64),
+}
+
+impl PaddingStrategy {
+    /// Returns the amount of padding to add to a tag that is `new_len` bytes large. `old_len` is
+    /// the size of the tag being replaced, or 0 if there is none, and `file_len` is the size of
+    /// the file being written to.
+    pub(crate) fn padding(&self, new_len: u64, old_len: u64, file_len: u64) -> u64 {
+        let reuse = match u64::checked_sub(old_len, new_len) {
+            Some(delta) if old_len > 0 => Some(delta),
+            _ => None,
+        };
+
+        let padding = match self {
+            Self::Auto => {
+                return match reuse {
+                    Some(delta) => u64::min(delta, file_len / 100),
+                    None => 1024,
                 }
+            }
+            Self::Minimal => return 0,
+            Self::ReuseOnly => 0,
+            Self::Fixed(padding) => *padding,
+            Self::Percent(percent) => (file_len as f64 * f64::from(*percent) / 100.0) as u64,
+            Self::Block(0) => 0,
+            Self::Block(size) => new_len.div_ceil(*size) * size - new_len,
+        };
+
+        match reuse {
+            Some(delta) if delta >= padding => delta,
+            _ => padding,
+        }
+    }
+}
 
-                Err(_) => Vec::new(),
-            };
+/// Replace up to `end` bytes in a file with `data`, following `options`. `progress` is called
+/// as the rest of the file is moved.
+pub fn write_replaced_with<P: AsRef<Path>>(
+    path: P,
+    data: &[u8],
+    end: u64,
+    options: &SaveOptions,
+    progress: Progress,
+) -> io::Result<()> {
+    write_spliced_with(path, &[Splice::new(0, end, data)], options, progress)
+}
+
+/// Applies `splices` to a file, following `options`. `progress` is called as the data between
+/// the splices is moved. The splices must be sorted and must not overlap.
+pub fn write_spliced_with<P: AsRef<Path>>(
+    path: P,
+    splices: &[Splice],
+    options: &SaveOptions,
+    progress: Progress,
+) -> io::Result<()> {
+    let path = path.as_ref();
+
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => Some(metadata),
+        Err(err) if err.kind() == ErrorKind::NotFound => None,
+        Err(err) => return Err(err),
+    };
+
+    let mtime = match &metadata {
+        Some(metadata) if options.preserve_mtime => Some(metadata.modified()?),
+        _ => None,
+    };
+
+    // A blit of the same length will never leave the file in a truncated state, so there's
+    // no need to copy the whole file.
+    let in_place = match &metadata {
+        Some(metadata) => {
+            let len = metadata.len();
+
+            !options.atomic
+                || splices.iter().all(|splice| {
+                    splice.data.len() as u64
+                        == u64::min(splice.end, len) - u64::min(splice.start, len)
+                })
+        }
+        None => !options.atomic,
+    };
+
+    if in_place {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .write(true)
+            .truncate(false)
+            .open(path)?;
 
-            let mut file = OpenOptions::new()
-                .create(true)
-                .write(true)
-                .truncate(true)
-                .open(&path)?;
+        let len = write_spliced_in(&mut file, splices, progress)?;
 
-            file.write_all(data)?;
-            file.write_all(&keep)?;
-            file.flush()
+        // The file may have shrunk, so remove anything left over at the end.
+        file.set_len(len)?;
+
+        if let Some(mtime) = mtime {
+            file.set_times(FileTimes::new().set_modified(mtime))?;
         }
+
+        return file.flush();
+    }
+
+    let temp_path = temp_path(path)?;
+
+    // The temporary file is only removed on failure once it has been created by this call,
+    // so that a concurrent save of the same file is never disturbed.
+    let mut temp = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&temp_path)?;
+
+    let result = (|| {
+        match &metadata {
+            Some(metadata) => {
+                let plan = SplicePlan::new(splices, metadata.len());
+                let total = plan.kept.iter().map(|range| range.len).sum();
+                let mut src = BufReader::new(File::open(path)?);
+                let mut done = 0;
+
+                // The kept ranges and the new data alternate, starting and ending with a
+                // kept range.
+                for (i, range) in plan.kept.iter().enumerate() {
+                    src.seek(SeekFrom::Start(range.from))?;
+                    copy_blocks(&mut src, &mut temp, range.len, &mut |moved, _| {
+                        progress(done + moved, total)
+                    })?;
+
+                    done += range.len;
+
+                    if let Some((_, data)) = plan.writes.get(i) {
+                        temp.write_all(data)?;
+                    }
+                }
+            }
+            None => {
+                for splice in splices {
+                    temp.write_all(splice.data)?;
+                }
+            }
+        }
+
+        if let Some(metadata) = &metadata {
+            if options.preserve_permissions {
+                temp.set_permissions(metadata.permissions())?;
+            }
+        }
+
+        if let Some(mtime) = mtime {
+            temp.set_times(FileTimes::new().set_modified(mtime))?;
+        }
+
+        // The data must be on disk before the rename, otherwise a crash could still leave an
+        // empty file in place of the original.
+        temp.sync_all()?;
+        fs::rename(&temp_path, path)
+    })();
+
+    if result.is_err() {
+        fs::remove_file(&temp_path).ok();
+    }
+
+    result?;
+
+    // Make sure the rename itself is persisted. This is only possible on unix, and isn't
+    // critical since the original file is intact either way.
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        let dir = if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        };
+
+        File::open(dir).and_then(|dir| dir.sync_all()).ok();
+    }
+
+    Ok(())
+}
+
+/// Returns a path for a temporary file next to `path`. The path is unique to each call, so
+/// that multiple saves of the same file never share a temporary file.
+fn temp_path(path: &Path) -> io::Result<PathBuf> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "path does not point to a file"))?;
+
+    let mut temp_name = OsString::from(".");
+    temp_name.push(name);
+    temp_name.push(format!(
+        ".musikr-{}-{}.tmp",
+        process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    Ok(path.with_file_name(temp_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn splice_stream() {
+        let mut data = Cursor::new(b"aaaBBBcccDDDeee".to_vec());
+
+        // One range grows and the other shrinks, so the data between them moves both ways.
+        let splices = [Splice::new(3, 6, b"bbbbb"), Splice::new(9, 12, b"d")];
+        let len = write_spliced_in(&mut data, &splices, &mut |_, _| {}).unwrap();
+
+        assert_eq!(&data.get_ref()[..len as usize], b"aaabbbbbcccdeee");
+
+        let mut data = Cursor::new(b"aaaBBBccc".to_vec());
+        let mut progress = Vec::new();
+
+        let splices = [Splice::new(0, 3, b""), Splice::new(6, 6, b"new")];
+        let len = write_spliced_in(&mut data, &splices, &mut |moved, total| {
+            progress.push((moved, total))
+        })
+        .unwrap();
+
+        assert_eq!(&data.get_ref()[..len as usize], b"BBBnewccc");
+        assert_eq!(progress, [(3, 3)]);
+    }
+
+    #[test]
+    fn unique_temp_path() {
+        let path = Path::new("dir/file.mp3");
+        let temp = temp_path(path).unwrap();
+
+        assert_eq!(temp.parent(), path.parent());
+        assert_ne!(temp, temp_path(path).unwrap());
+    }
+
+    #[test]
+    fn splice_file_atomic() {
+        let path = std::env::temp_dir().join("musikr_io_splice.bin");
+        fs::write(&path, b"aaaBBBccc").unwrap();
+
+        let splices = [Splice::new(3, 6, b"bbbbb"), Splice::new(9, 9, b"ddd")];
+        write_spliced_with(&path, &splices, &SaveOptions::atomic(), &mut |_, _| {}).unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"aaabbbbbcccddd");
     }
 }
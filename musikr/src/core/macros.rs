@@ -135,7 +135,7 @@ macro_rules! impl_array_newtype {(
         type IntoIter = std::array::IntoIter<u8, $n>;
 
         fn into_iter(self) -> Self::IntoIter {
-            Self::IntoIter::new(self.0)
+            self.0.into_iter()
         }
     }
 
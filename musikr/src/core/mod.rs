@@ -2,10 +2,11 @@
 
 #[macro_use]
 pub(crate) mod macros;
+pub(crate) mod iff;
 pub(crate) mod io;
 pub(crate) mod string;
 
 pub use {
-    io::{BufStream, StreamError},
+    io::{BufStream, PaddingStrategy, RestrictionMode, SaveOptions, StreamError},
     string::Encoding,
 };
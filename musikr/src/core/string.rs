@@ -6,7 +6,7 @@ use log::warn;
 /// Not all tag formats will use encodings in the same way. For example, ID3v2 will give you multiple options
 /// for encoding frames, but Xiph tags are only limited to UTF-8. Generally, the the default encoding of
 /// [`Encoding::Utf8`](Encoding::Utf8) should be used if there's a choice.
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
 pub enum Encoding {
     /// ISO-8859-1, also known as Latin1. This is used in the older tag formats like ID3v1 and ID3v2.
     /// Using this encoding is discouraged, as all Unicode text in a string will be flattened into "?"
@@ -21,6 +21,7 @@ pub enum Encoding {
     Utf16Be,
     /// UTF-8. This will allow the direct mapping of rust strings to tag data, but is not supported
     /// on all tag formats. If it is not supported, then it will be mapped to [`Utf16`](Encoding::Utf16).
+    #[default]
     Utf8,
     /// UTF-16LE with no BOM. This is analogous to [`Utf16`](Encoding::Utf16) and will be written as such.
     Utf16Le,
@@ -35,12 +36,6 @@ impl Encoding {
     }
 }
 
-impl Default for Encoding {
-    fn default() -> Self {
-        Self::Utf8
-    }
-}
-
 /// Consumes the rest of this stream and decodes it into a string according
 /// to the encoding,
 pub(crate) fn read(encoding: Encoding, stream: &mut BufStream) -> String {
@@ -136,7 +131,6 @@ fn decode_utf16(data: &[u8]) -> String {
 fn decode_utf16be(data: &[u8]) -> String {
     String::from_utf16_lossy(
         data.chunks_exact(2)
-            .into_iter()
             .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
             .collect::<Vec<u16>>()
             .as_slice(),
@@ -146,7 +140,6 @@ fn decode_utf16be(data: &[u8]) -> String {
 fn decode_utf16le(data: &[u8]) -> String {
     String::from_utf16_lossy(
         data.chunks_exact(2)
-            .into_iter()
             .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
             .collect::<Vec<u16>>()
             .as_slice(),
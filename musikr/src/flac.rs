@@ -0,0 +1,484 @@
+//! FLAC metadata reading and writing.
+//!
+//! FLAC files store their metadata in a chain of "blocks" that come before the audio data.
+//! Textual metadata is stored in a [`VorbisComment`](crate::xiph::VorbisComment), while
+//! pictures and other information are stored in their own blocks:
+//!
+//! ```no_run
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! use musikr::flac::Tag;
+//! let mut tag = Tag::open("music.flac")?;
+//! tag.comments.set("TITLE", "Archangel");
+//! tag.save("music.flac")?;
+//! #   Ok(())
+//! # }
+//! ```
+//!
+//! Unlike other formats, a FLAC tag cannot be created programmatically, as it must contain
+//! information about the audio stream.
+//!
+//! More information about the format can be found [here](https://xiph.org/flac/format.html#metadata_block).
+
+pub mod blocks;
+
+use crate::core::io::{write_replaced, BufStream};
+use crate::id3v2::tag::TagHeader;
+use crate::xiph::VorbisComment;
+use blocks::{
+    ApplicationBlock, Block, CueSheetBlock, PictureBlock, SeekTableBlock, StreamInfo, UnknownBlock,
+};
+
+use log::{error, info, warn};
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+const ID: &[u8] = b"fLaC";
+
+const MAX_BLOCK_SIZE: usize = 0xFF_FFFF;
+
+/// The metadata of a FLAC file.
+///
+/// More information can be found in the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct Tag {
+    stream_info: StreamInfo,
+    padding: u32,
+    /// The Vorbis comment of this file. This will be empty if the file had none.
+    pub comments: VorbisComment,
+    /// The pictures attached to this file.
+    pub pictures: Vec<PictureBlock>,
+    /// The remaining application, seek table, cue sheet, and unknown blocks.
+    pub blocks: Vec<Block>,
+}
+
+impl Tag {
+    /// Attempts to open and parse the metadata blocks in `path`.
+    ///
+    /// An ID3v2 tag before the FLAC stream will be skipped.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be opened, is not a FLAC file, or if the metadata is malformed,
+    /// an error will be returned with a general reason for why. Specific information about
+    /// parsing errors will be logged.
+    pub fn open<P: AsRef<Path>>(path: P) -> ParseResult<Self> {
+        let mut file = File::open(path)?;
+
+        let (start, end) = locate(&mut file)?;
+
+        let mut data = vec![0; (end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut data)?;
+
+        Self::parse(&mut BufStream::new(&data))
+    }
+
+    fn parse(stream: &mut BufStream) -> ParseResult<Self> {
+        let mut stream_info = None;
+        let mut padding = 0;
+        let mut comments = None;
+        let mut pictures = Vec::new();
+        let mut blocks = Vec::new();
+
+        while !stream.is_empty() {
+            let (kind, _, len) = parse_block_header(stream.read_array()?);
+            let mut data = stream.slice_stream(len)?;
+
+            if kind == blocks::STREAMINFO {
+                stream_info = Some(StreamInfo::parse(&mut data)?);
+                continue;
+            }
+
+            // STREAMINFO must always be the first block.
+            if stream_info.is_none() {
+                error!("first block was not STREAMINFO");
+                return Err(ParseError::MalformedData);
+            }
+
+            match kind {
+                blocks::PADDING => padding += len as u32 + 4,
+                blocks::APPLICATION => {
+                    blocks.push(Block::Application(ApplicationBlock::parse(&mut data)?))
+                }
+                blocks::SEEKTABLE => {
+                    blocks.push(Block::SeekTable(SeekTableBlock::parse(&mut data)?))
+                }
+                blocks::VORBIS_COMMENT => match comments {
+                    Some(_) => warn!("dropping duplicate VORBIS_COMMENT block"),
+                    None => comments = Some(VorbisComment::parse(&mut data)?),
+                },
+                blocks::CUESHEET => blocks.push(Block::CueSheet(CueSheetBlock::parse(&mut data)?)),
+                blocks::PICTURE => pictures.push(PictureBlock::parse(&mut data)?),
+                kind => {
+                    info!("found unknown block {}", kind);
+
+                    blocks.push(Block::Unknown(UnknownBlock {
+                        kind,
+                        data: data.to_vec(),
+                    }))
+                }
+            }
+        }
+
+        let stream_info = match stream_info {
+            Some(stream_info) => stream_info,
+            None => {
+                error!("no STREAMINFO block found");
+                return Err(ParseError::MalformedData);
+            }
+        };
+
+        Ok(Self {
+            stream_info,
+            padding,
+            comments: comments.unwrap_or_default(),
+            pictures,
+            blocks,
+        })
+    }
+
+    /// Returns the information about the audio stream.
+    pub fn stream_info(&self) -> &StreamInfo {
+        &self.stream_info
+    }
+
+    /// Returns the total size of the padding in this file, in bytes.
+    ///
+    /// This value is only updated when the tag is read or saved.
+    pub fn padding(&self) -> u32 {
+        self.padding
+    }
+
+    /// Saves the metadata to `path`.
+    ///
+    /// The block chain will be rewritten in the order of STREAMINFO, VORBIS_COMMENT, PICTURE,
+    /// [`Tag.blocks`](Tag.blocks), and then PADDING. If the new blocks fit in the space of the
+    /// old blocks, the remaining space will be used as padding so that the audio data does not
+    /// have to be rewritten. If they do not fit, 1 KiB of padding will be applied.
+    ///
+    /// # Errors
+    ///
+    /// If `path` is not a FLAC file, a block is too large, or if the file cannot be written,
+    /// an error with a general reason will be returned. Specific information about saving
+    /// errors will be logged.
+    pub fn save<P: AsRef<Path>>(&mut self, path: P) -> SaveResult<()> {
+        // Keep anything before the blocks, such as the marker and an ID3v2 tag.
+        let mut file = File::open(&path)?;
+
+        let (start, end) = match locate(&mut file) {
+            Ok(location) => location,
+            Err(ParseError::IoError(err)) => return Err(SaveError::IoError(err)),
+            Err(_) => {
+                error!("file is not a FLAC file");
+                return Err(SaveError::NotFlac);
+            }
+        };
+
+        let mut data = vec![0; start as usize];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut data)?;
+
+        drop(file);
+
+        let mut chain = vec![
+            (blocks::STREAMINFO, self.stream_info.render()),
+            (blocks::VORBIS_COMMENT, self.comments.render()),
+        ];
+
+        for picture in &self.pictures {
+            chain.push((blocks::PICTURE, picture.render()));
+        }
+
+        for block in &self.blocks {
+            chain.push((block.kind(), block.render()));
+        }
+
+        if let Some((kind, _)) = chain.iter().find(|(_, data)| data.len() > MAX_BLOCK_SIZE) {
+            error!("block {} was larger than 16mb", kind);
+            return Err(SaveError::TooLarge);
+        }
+
+        let chain_size: usize = chain.iter().map(|(_, data)| data.len() + 4).sum();
+
+        // Find a padding size that avoids moving the audio data. If that isn't possible,
+        // use 1KiB. A padding block needs at least 4 bytes for its header.
+        let padding = match (end - start).checked_sub(chain_size as u64) {
+            Some(0) => None,
+            Some(delta) if (4..=MAX_BLOCK_SIZE as u64 + 4).contains(&delta) => {
+                Some(delta as usize - 4)
+            }
+            _ => Some(1024),
+        };
+
+        if let Some(padding) = padding {
+            chain.push((blocks::PADDING, vec![0; padding]));
+        }
+
+        let last = chain.len() - 1;
+
+        for (i, (kind, block)) in chain.into_iter().enumerate() {
+            data.push(u8::from(i == last) << 7 | kind);
+            data.extend(&(block.len() as u32).to_be_bytes()[1..]);
+            data.extend(block);
+        }
+
+        write_replaced(path, &data, end)?;
+
+        self.padding = padding
+            .map(|padding| padding as u32 + 4)
+            .unwrap_or_default();
+
+        Ok(())
+    }
+}
+
+/// Finds the block chain in `src`, returning the positions where it starts and ends.
+fn locate<R: Read + Seek>(src: &mut R) -> ParseResult<(u64, u64)> {
+    let mut start = 0;
+
+    let mut header_raw = [0; 10];
+    src.seek(SeekFrom::Start(0))?;
+    src.read_exact(&mut header_raw)?;
+
+    // Some taggers will write an ID3v2 tag to the beginning of a FLAC file, which we skip.
+    if &header_raw[0..3] == b"ID3" {
+        let header = TagHeader::parse(header_raw).map_err(|_| ParseError::MalformedData)?;
+
+        warn!("skipping ID3v2 tag before the FLAC stream");
+        start = header.size() as u64 + 10;
+
+        if header.flags().footer {
+            start += 10;
+        }
+    }
+
+    let mut id = [0; 4];
+    src.seek(SeekFrom::Start(start))?;
+    src.read_exact(&mut id)?;
+
+    if id != ID {
+        return Err(ParseError::NotFound);
+    }
+
+    start += 4;
+
+    let mut pos = start;
+
+    loop {
+        let mut header = [0; 4];
+        src.seek(SeekFrom::Start(pos))?;
+        src.read_exact(&mut header)?;
+
+        let (_, is_last, len) = parse_block_header(header);
+
+        pos += len as u64 + 4;
+
+        if is_last {
+            break;
+        }
+    }
+
+    Ok((start, pos))
+}
+
+fn parse_block_header(raw: [u8; 4]) -> (u8, bool, usize) {
+    let kind = raw[0] & 0x7F;
+    let is_last = raw[0] & 0x80 != 0;
+    let len = u32::from_be_bytes([0, raw[1], raw[2], raw[3]]) as usize;
+
+    (kind, is_last, len)
+}
+
+/// The result given after a parsing operation.
+pub type ParseResult<T> = Result<T, ParseError>;
+
+/// The error type returned when parsing FLAC metadata.
+#[derive(Debug)]
+pub enum ParseError {
+    /// Generic IO errors. This either means that a problem occurred while opening the file
+    /// for a tag, or an unexpected EOF was encountered while parsing.
+    IoError(io::Error),
+    /// A part of the metadata was not valid.
+    MalformedData,
+    /// The file was not a FLAC file.
+    NotFound,
+}
+
+impl From<io::Error> for ParseError {
+    fn from(other: io::Error) -> Self {
+        ParseError::IoError(other)
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(err) => err.fmt(f),
+            Self::MalformedData => write![f, "malformed data"],
+            Self::NotFound => write![f, "not found"],
+        }
+    }
+}
+
+impl error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::IoError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// The result given after a save operation.
+pub type SaveResult<T> = Result<T, SaveError>;
+
+/// The error type returned when saving FLAC metadata.
+#[derive(Debug)]
+pub enum SaveError {
+    /// Generic IO errors. This means that a problem occurred while writing the tag to a file.
+    IoError(io::Error),
+    /// A block was too large to be written.
+    TooLarge,
+    /// The file being written to was not a FLAC file.
+    NotFlac,
+}
+
+impl From<io::Error> for SaveError {
+    fn from(other: io::Error) -> Self {
+        SaveError::IoError(other)
+    }
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(err) => err.fmt(f),
+            Self::TooLarge => write![f, "block is too large to be saved"],
+            Self::NotFlac => write![f, "file is not a flac file"],
+        }
+    }
+}
+
+impl error::Error for SaveError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::IoError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    const AUDIO: &[u8] = b"\xFF\xF8\x16\x16\x16\x16\x16\x16\x16\x16";
+
+    fn make_flac(name: &str) -> std::path::PathBuf {
+        let mut comments = VorbisComment::new();
+        comments.vendor = String::from("musikr");
+        comments.add("TITLE", "Title");
+
+        let info = StreamInfo {
+            sample_rate: 44100,
+            channels: 2,
+            bits_per_sample: 16,
+            ..Default::default()
+        };
+
+        let mut data = ID.to_vec();
+
+        for (kind, block) in [
+            (blocks::STREAMINFO, info.render()),
+            (blocks::VORBIS_COMMENT, comments.render()),
+            (0x80 | blocks::PADDING, vec![0; 64]),
+        ] {
+            data.push(kind);
+            data.extend(&(block.len() as u32).to_be_bytes()[1..]);
+            data.extend(block);
+        }
+
+        data.extend(AUDIO);
+
+        let path = env::temp_dir().join(name);
+        fs::write(&path, data).unwrap();
+        path
+    }
+
+    fn audio_of(path: &Path) -> Vec<u8> {
+        let data = fs::read(path).unwrap();
+        data[data.len() - AUDIO.len()..].to_vec()
+    }
+
+    #[test]
+    fn parse_flac() {
+        let path = make_flac("musikr_flac_parse.flac");
+        let tag = Tag::open(&path).unwrap();
+
+        assert_eq!(tag.stream_info().sample_rate, 44100);
+        assert_eq!(tag.padding(), 68);
+        assert_eq!(tag.comments.vendor, "musikr");
+        assert_eq!(tag.comments.get("title"), Some("Title"));
+        assert!(tag.pictures.is_empty());
+        assert!(tag.blocks.is_empty());
+    }
+
+    #[test]
+    fn save_into_padding() {
+        let path = make_flac("musikr_flac_padding.flac");
+        let len = fs::metadata(&path).unwrap().len();
+
+        let mut tag = Tag::open(&path).unwrap();
+        tag.comments.add("ARTIST", "Artist");
+        tag.blocks.push(Block::Application(ApplicationBlock {
+            id: *b"test",
+            data: vec![0x16; 4],
+        }));
+        tag.save(&path).unwrap();
+
+        // The new blocks should have eaten into the padding instead of moving the audio.
+        assert_eq!(fs::metadata(&path).unwrap().len(), len);
+        assert_eq!(audio_of(&path), AUDIO);
+
+        let new_tag = Tag::open(&path).unwrap();
+
+        assert_eq!(new_tag.padding(), tag.padding());
+        assert_eq!(new_tag.comments, tag.comments);
+        assert_eq!(new_tag.blocks, tag.blocks);
+    }
+
+    #[test]
+    fn save_past_padding() {
+        let path = make_flac("musikr_flac_grow.flac");
+
+        let mut tag = Tag::open(&path).unwrap();
+        tag.pictures.push(PictureBlock {
+            mime: String::from("image/png"),
+            picture: vec![0x16; 256],
+            ..Default::default()
+        });
+        tag.save(&path).unwrap();
+
+        assert_eq!(tag.padding(), 1028);
+        assert_eq!(audio_of(&path), AUDIO);
+
+        let new_tag = Tag::open(&path).unwrap();
+
+        assert_eq!(new_tag.padding(), 1028);
+        assert_eq!(new_tag.comments, tag.comments);
+        assert_eq!(new_tag.pictures, tag.pictures);
+    }
+
+    #[test]
+    fn not_flac() {
+        let path = env::var("CARGO_MANIFEST_DIR").unwrap() + "/res/test/example.mp3";
+
+        assert!(matches!(Tag::open(&path), Err(ParseError::NotFound)));
+    }
+}
@@ -0,0 +1,530 @@
+//! FLAC metadata blocks.
+//!
+//! Every block except for `PADDING` and `VORBIS_COMMENT` is represented here. Padding is
+//! managed automatically by [`Tag::save`](crate::flac::Tag::save), while the Vorbis comment
+//! is exposed as a [`VorbisComment`](crate::xiph::VorbisComment).
+
+use crate::core::io::BufStream;
+use crate::core::string::{self, Encoding};
+use crate::id3v2::frames::file::PictureType;
+
+use std::fmt::{self, Display, Formatter};
+use std::io;
+
+pub(crate) const STREAMINFO: u8 = 0;
+pub(crate) const PADDING: u8 = 1;
+pub(crate) const APPLICATION: u8 = 2;
+pub(crate) const SEEKTABLE: u8 = 3;
+pub(crate) const VORBIS_COMMENT: u8 = 4;
+pub(crate) const CUESHEET: u8 = 5;
+pub(crate) const PICTURE: u8 = 6;
+
+/// A metadata block that is not directly exposed by [`Tag`](crate::flac::Tag).
+///
+/// These blocks are kept in the order that they were read.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Block {
+    Application(ApplicationBlock),
+    SeekTable(SeekTableBlock),
+    CueSheet(CueSheetBlock),
+    Unknown(UnknownBlock),
+}
+
+impl Block {
+    pub(crate) fn kind(&self) -> u8 {
+        match self {
+            Self::Application(_) => APPLICATION,
+            Self::SeekTable(_) => SEEKTABLE,
+            Self::CueSheet(_) => CUESHEET,
+            Self::Unknown(block) => block.kind,
+        }
+    }
+
+    pub(crate) fn render(&self) -> Vec<u8> {
+        match self {
+            Self::Application(block) => block.render(),
+            Self::SeekTable(block) => block.render(),
+            Self::CueSheet(block) => block.render(),
+            Self::Unknown(block) => block.data.clone(),
+        }
+    }
+}
+
+/// Information about the audio stream.
+///
+/// This block is always present and cannot be modified.
+#[derive(Default, Debug, Clone, Eq, PartialEq)]
+pub struct StreamInfo {
+    pub min_block_size: u16,
+    pub max_block_size: u16,
+    /// The minimum frame size, in bytes. Zero means the value is unknown.
+    pub min_frame_size: u32,
+    /// The maximum frame size, in bytes. Zero means the value is unknown.
+    pub max_frame_size: u32,
+    pub sample_rate: u32,
+    pub channels: u8,
+    pub bits_per_sample: u8,
+    /// The total number of samples. Zero means the value is unknown.
+    pub total_samples: u64,
+    /// The MD5 signature of the unencoded audio data.
+    pub md5: [u8; 16],
+}
+
+impl StreamInfo {
+    pub(crate) fn parse(stream: &mut BufStream) -> io::Result<Self> {
+        let min_block_size = stream.read_be_u16()?;
+        let max_block_size = stream.read_be_u16()?;
+        let min_frame_size = read_u24(stream)?;
+        let max_frame_size = read_u24(stream)?;
+
+        // The next 8 bytes pack the sample rate [20 bits], the channel count - 1 [3 bits],
+        // the bits per sample - 1 [5 bits], and the total samples [36 bits].
+        let packed = stream.read_be_u64()?;
+
+        Ok(Self {
+            min_block_size,
+            max_block_size,
+            min_frame_size,
+            max_frame_size,
+            sample_rate: (packed >> 44) as u32,
+            channels: ((packed >> 41) & 0x7) as u8 + 1,
+            bits_per_sample: ((packed >> 36) & 0x1F) as u8 + 1,
+            total_samples: packed & 0xF_FFFF_FFFF,
+            md5: stream.read_array()?,
+        })
+    }
+
+    pub(crate) fn render(&self) -> Vec<u8> {
+        let mut result = Vec::with_capacity(34);
+
+        result.extend(self.min_block_size.to_be_bytes());
+        result.extend(self.max_block_size.to_be_bytes());
+        result.extend(&self.min_frame_size.to_be_bytes()[1..]);
+        result.extend(&self.max_frame_size.to_be_bytes()[1..]);
+
+        let packed = (u64::from(self.sample_rate) & 0xF_FFFF) << 44
+            | (u64::from(self.channels.saturating_sub(1)) & 0x7) << 41
+            | (u64::from(self.bits_per_sample.saturating_sub(1)) & 0x1F) << 36
+            | self.total_samples & 0xF_FFFF_FFFF;
+
+        result.extend(packed.to_be_bytes());
+        result.extend(self.md5);
+
+        result
+    }
+}
+
+impl Display for StreamInfo {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write![
+            f,
+            "{} Hz, {} channels, {} bits",
+            self.sample_rate, self.channels, self.bits_per_sample
+        ]
+    }
+}
+
+/// Third-party application data.
+#[derive(Default, Debug, Clone, Eq, PartialEq)]
+pub struct ApplicationBlock {
+    /// The registered ID of the application.
+    pub id: [u8; 4],
+    pub data: Vec<u8>,
+}
+
+impl ApplicationBlock {
+    pub(crate) fn parse(stream: &mut BufStream) -> io::Result<Self> {
+        Ok(Self {
+            id: stream.read_array()?,
+            data: stream.take_rest().to_vec(),
+        })
+    }
+
+    pub(crate) fn render(&self) -> Vec<u8> {
+        let mut result = self.id.to_vec();
+        result.extend(&self.data);
+        result
+    }
+}
+
+/// A table of seek points into the audio stream.
+#[derive(Default, Debug, Clone, Eq, PartialEq)]
+pub struct SeekTableBlock {
+    pub points: Vec<SeekPoint>,
+}
+
+impl SeekTableBlock {
+    pub(crate) fn parse(stream: &mut BufStream) -> io::Result<Self> {
+        let mut points = Vec::new();
+
+        while !stream.is_empty() {
+            points.push(SeekPoint {
+                sample: stream.read_be_u64()?,
+                offset: stream.read_be_u64()?,
+                samples: stream.read_be_u16()?,
+            })
+        }
+
+        Ok(Self { points })
+    }
+
+    pub(crate) fn render(&self) -> Vec<u8> {
+        let mut result = Vec::with_capacity(self.points.len() * 18);
+
+        for point in &self.points {
+            result.extend(point.sample.to_be_bytes());
+            result.extend(point.offset.to_be_bytes());
+            result.extend(point.samples.to_be_bytes());
+        }
+
+        result
+    }
+}
+
+/// A single seek point. A sample number of `0xFFFFFFFFFFFFFFFF` marks a placeholder point.
+#[derive(Default, Debug, Clone, Copy, Eq, PartialEq)]
+pub struct SeekPoint {
+    /// The number of the first sample in the target frame.
+    pub sample: u64,
+    /// The offset of the target frame from the first frame, in bytes.
+    pub offset: u64,
+    /// The number of samples in the target frame.
+    pub samples: u16,
+}
+
+/// A CD-DA or other cue sheet.
+#[derive(Default, Debug, Clone, Eq, PartialEq)]
+pub struct CueSheetBlock {
+    /// The media catalog number. Up to 128 ASCII characters can be written.
+    pub catalog: String,
+    /// The number of lead-in samples.
+    pub lead_in: u64,
+    /// Whether this cue sheet corresponds to a compact disc.
+    pub is_cd: bool,
+    pub tracks: Vec<CueTrack>,
+}
+
+impl CueSheetBlock {
+    pub(crate) fn parse(stream: &mut BufStream) -> io::Result<Self> {
+        let catalog = read_ascii(stream, 128)?;
+        let lead_in = stream.read_be_u64()?;
+        let is_cd = stream.read_u8()? & 0x80 != 0;
+
+        stream.skip(258)?;
+
+        let track_count = stream.read_u8()?;
+        let mut tracks = Vec::with_capacity(track_count as usize);
+
+        for _ in 0..track_count {
+            let offset = stream.read_be_u64()?;
+            let number = stream.read_u8()?;
+            let isrc = read_ascii(stream, 12)?;
+            let flags = stream.read_u8()?;
+
+            stream.skip(13)?;
+
+            let index_count = stream.read_u8()?;
+            let mut indices = Vec::with_capacity(index_count as usize);
+
+            for _ in 0..index_count {
+                indices.push(CueIndex {
+                    offset: stream.read_be_u64()?,
+                    number: stream.read_u8()?,
+                });
+
+                stream.skip(3)?;
+            }
+
+            tracks.push(CueTrack {
+                offset,
+                number,
+                isrc,
+                is_audio: flags & 0x80 == 0,
+                pre_emphasis: flags & 0x40 != 0,
+                indices,
+            })
+        }
+
+        Ok(Self {
+            catalog,
+            lead_in,
+            is_cd,
+            tracks,
+        })
+    }
+
+    pub(crate) fn render(&self) -> Vec<u8> {
+        let mut result = Vec::new();
+
+        result.extend(render_ascii(&self.catalog, 128));
+        result.extend(self.lead_in.to_be_bytes());
+        result.push(u8::from(self.is_cd) << 7);
+        result.extend([0; 258]);
+        result.push(self.tracks.len() as u8);
+
+        for track in &self.tracks {
+            result.extend(track.offset.to_be_bytes());
+            result.push(track.number);
+            result.extend(render_ascii(&track.isrc, 12));
+            result.push(u8::from(!track.is_audio) << 7 | u8::from(track.pre_emphasis) << 6);
+            result.extend([0; 13]);
+            result.push(track.indices.len() as u8);
+
+            for index in &track.indices {
+                result.extend(index.offset.to_be_bytes());
+                result.push(index.number);
+                result.extend([0; 3]);
+            }
+        }
+
+        result
+    }
+}
+
+/// A track in a cue sheet.
+#[derive(Default, Debug, Clone, Eq, PartialEq)]
+pub struct CueTrack {
+    /// The offset of the track from the beginning of the audio, in samples.
+    pub offset: u64,
+    pub number: u8,
+    /// The ISRC of the track. Up to 12 ASCII characters can be written.
+    pub isrc: String,
+    pub is_audio: bool,
+    pub pre_emphasis: bool,
+    pub indices: Vec<CueIndex>,
+}
+
+/// An index point in a cue sheet track.
+#[derive(Default, Debug, Clone, Copy, Eq, PartialEq)]
+pub struct CueIndex {
+    /// The offset of the index from the beginning of the track, in samples.
+    pub offset: u64,
+    pub number: u8,
+}
+
+/// An attached picture.
+///
+/// This is analogous to the ID3v2 [`AttachedPictureFrame`](crate::id3v2::frames::AttachedPictureFrame).
+#[derive(Default, Debug, Clone, Eq, PartialEq)]
+pub struct PictureBlock {
+    pub pic_type: PictureType,
+    pub mime: String,
+    pub desc: String,
+    pub width: u32,
+    pub height: u32,
+    /// The color depth of the picture, in bits per pixel.
+    pub depth: u32,
+    /// The number of colors used in an indexed picture, or zero if the picture is not indexed.
+    pub colors: u32,
+    pub picture: Vec<u8>,
+}
+
+impl PictureBlock {
+    pub(crate) fn parse(stream: &mut BufStream) -> io::Result<Self> {
+        let pic_type = match u8::try_from(stream.read_be_u32()?) {
+            Ok(pic_type) => PictureType::parse(pic_type),
+            Err(_) => PictureType::Other,
+        };
+
+        let mime_len = stream.read_be_u32()? as usize;
+        let mime = string::read(Encoding::Latin1, &mut stream.slice_stream(mime_len)?);
+
+        let desc_len = stream.read_be_u32()? as usize;
+        let desc = string::read(Encoding::Utf8, &mut stream.slice_stream(desc_len)?);
+
+        let width = stream.read_be_u32()?;
+        let height = stream.read_be_u32()?;
+        let depth = stream.read_be_u32()?;
+        let colors = stream.read_be_u32()?;
+
+        let picture_len = stream.read_be_u32()? as usize;
+        let picture = stream.slice(picture_len)?.to_vec();
+
+        Ok(Self {
+            pic_type,
+            mime,
+            desc,
+            width,
+            height,
+            depth,
+            colors,
+            picture,
+        })
+    }
+
+    pub(crate) fn render(&self) -> Vec<u8> {
+        let mut result = Vec::new();
+
+        let mime = string::render(Encoding::Latin1, &self.mime);
+        let desc = string::render(Encoding::Utf8, &self.desc);
+
+        result.extend((self.pic_type as u32).to_be_bytes());
+        result.extend((mime.len() as u32).to_be_bytes());
+        result.extend(mime);
+        result.extend((desc.len() as u32).to_be_bytes());
+        result.extend(desc);
+        result.extend(self.width.to_be_bytes());
+        result.extend(self.height.to_be_bytes());
+        result.extend(self.depth.to_be_bytes());
+        result.extend(self.colors.to_be_bytes());
+        result.extend((self.picture.len() as u32).to_be_bytes());
+        result.extend(&self.picture);
+
+        result
+    }
+}
+
+impl Display for PictureBlock {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write![f, "{} ", self.mime]?;
+
+        if !self.desc.is_empty() {
+            write![f, "\"{}\" ", self.desc]?;
+        }
+
+        write![f, "[{:?}]", self.pic_type]
+    }
+}
+
+/// A block that musikr does not recognize.
+#[derive(Default, Debug, Clone, Eq, PartialEq)]
+pub struct UnknownBlock {
+    /// The type of this block.
+    pub kind: u8,
+    pub data: Vec<u8>,
+}
+
+fn read_u24(stream: &mut BufStream) -> io::Result<u32> {
+    let raw = stream.read_array::<3>()?;
+    Ok(u32::from_be_bytes([0, raw[0], raw[1], raw[2]]))
+}
+
+fn read_ascii(stream: &mut BufStream, size: usize) -> io::Result<String> {
+    Ok(string::read_terminated(
+        Encoding::Latin1,
+        &mut stream.slice_stream(size)?,
+    ))
+}
+
+fn render_ascii(string: &str, size: usize) -> Vec<u8> {
+    let mut result = string::render(Encoding::Latin1, string);
+    result.resize(size, 0);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STREAMINFO_DATA: &[u8] = b"\x10\x00\x10\x00\
+                                     \x00\x00\x0E\x00\x31\x9F\
+                                     \x0A\xC4\x42\xF0\x00\x1D\xF6\xAF\
+                                     \x16\x16\x16\x16\x16\x16\x16\x16\x16\x16\x16\x16\x16\x16\x16\x16";
+
+    const SEEKTABLE_DATA: &[u8] = b"\x00\x00\x00\x00\x00\x00\x00\x00\
+                                    \x00\x00\x00\x00\x00\x00\x00\x00\
+                                    \x10\x00\
+                                    \xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\
+                                    \x00\x00\x00\x00\x00\x00\x00\x00\
+                                    \x00\x00";
+
+    const PICTURE_DATA: &[u8] = b"\x00\x00\x00\x03\
+                                  \x00\x00\x00\x09image/png\
+                                  \x00\x00\x00\x0BDescription\
+                                  \x00\x00\x00\x10\x00\x00\x00\x10\
+                                  \x00\x00\x00\x18\x00\x00\x00\x00\
+                                  \x00\x00\x00\x04\
+                                  \x16\x16\x16\x16";
+
+    #[test]
+    fn parse_streaminfo() {
+        let info = StreamInfo::parse(&mut BufStream::new(STREAMINFO_DATA)).unwrap();
+
+        assert_eq!(info.min_block_size, 4096);
+        assert_eq!(info.max_block_size, 4096);
+        assert_eq!(info.min_frame_size, 14);
+        assert_eq!(info.max_frame_size, 12703);
+        assert_eq!(info.sample_rate, 44100);
+        assert_eq!(info.channels, 2);
+        assert_eq!(info.bits_per_sample, 16);
+        assert_eq!(info.total_samples, 1963695);
+        assert_eq!(info.md5, [0x16; 16]);
+    }
+
+    #[test]
+    fn render_streaminfo() {
+        let info = StreamInfo {
+            min_block_size: 4096,
+            max_block_size: 4096,
+            min_frame_size: 14,
+            max_frame_size: 12703,
+            sample_rate: 44100,
+            channels: 2,
+            bits_per_sample: 16,
+            total_samples: 1963695,
+            md5: [0x16; 16],
+        };
+
+        assert_eq!(info.render(), STREAMINFO_DATA);
+    }
+
+    #[test]
+    fn parse_seektable() {
+        let table = SeekTableBlock::parse(&mut BufStream::new(SEEKTABLE_DATA)).unwrap();
+
+        assert_eq!(table.points.len(), 2);
+        assert_eq!(table.points[0].samples, 4096);
+        assert_eq!(table.points[1].sample, u64::MAX);
+        assert_eq!(table.render(), SEEKTABLE_DATA);
+    }
+
+    #[test]
+    fn parse_picture() {
+        let picture = PictureBlock::parse(&mut BufStream::new(PICTURE_DATA)).unwrap();
+
+        assert_eq!(picture.pic_type, PictureType::FrontCover);
+        assert_eq!(picture.mime, "image/png");
+        assert_eq!(picture.desc, "Description");
+        assert_eq!(picture.width, 16);
+        assert_eq!(picture.height, 16);
+        assert_eq!(picture.depth, 24);
+        assert_eq!(picture.colors, 0);
+        assert_eq!(picture.picture, b"\x16\x16\x16\x16");
+        assert_eq!(picture.render(), PICTURE_DATA);
+    }
+
+    #[test]
+    fn render_cuesheet() {
+        let cuesheet = CueSheetBlock {
+            catalog: String::from("1234567890123"),
+            lead_in: 88200,
+            is_cd: true,
+            tracks: vec![
+                CueTrack {
+                    offset: 0,
+                    number: 1,
+                    isrc: String::from("USABC0400001"),
+                    is_audio: true,
+                    pre_emphasis: false,
+                    indices: vec![CueIndex {
+                        offset: 0,
+                        number: 1,
+                    }],
+                },
+                CueTrack {
+                    offset: 1963695,
+                    number: 170,
+                    ..Default::default()
+                },
+            ],
+        };
+
+        let data = cuesheet.render();
+
+        assert_eq!(data.len(), 396 + 36 + 12 + 36);
+        assert_eq!(
+            CueSheetBlock::parse(&mut BufStream::new(&data)).unwrap(),
+            cuesheet
+        );
+    }
+}
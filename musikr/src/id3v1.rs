@@ -0,0 +1,577 @@
+//! ID3v1 tag reading and writing.
+//!
+//! ID3v1 is the original metadata format for MP3 files. It is a fixed 128-byte block at the very
+//! end of a file, containing a title, artist, album, year, comment, and a genre index. Unlike
+//! ID3v2, there is no notion of frames, so a tag is exposed as a plain struct:
+//!
+//! ```
+//! # use std::error::Error;
+//! # use std::env;
+//! # use std::fs;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! #   let example_path = env::var("CARGO_MANIFEST_DIR").unwrap() + "/res/test/example.mp3";
+//! #   let output_path = env::temp_dir().join("musikr_id3v1_doctest.mp3");
+//! #   fs::copy(&example_path, &output_path)?;
+//! use musikr::id3v1::Tag;
+//! let mut tag = Tag::new();
+//! tag.title = String::from("Archangel");
+//! tag.track = Some(2);
+//! tag.save(&output_path)?;
+//! #   Ok(())
+//! # }
+//! ```
+//!
+//! Two common extensions to the format are also supported:
+//!
+//! - ID3v1.1, which takes the last two bytes of the comment to store a track number. This is
+//!   exposed as [`Tag.track`](Tag.track).
+//! - The "enhanced" `TAG+` block, which sits right before the ID3v1 tag and extends the title,
+//!   artist, and album fields by 60 bytes each, alongside some additional information. This is
+//!   exposed as [`Tag.extended`](Tag.extended).
+//!
+//! All text in an ID3v1 tag is Latin1. Any characters that cannot be expressed in Latin1 will
+//! be written as "?", and any text that is too long for a field will be truncated.
+
+mod genres;
+
+pub use genres::GENRES;
+
+use crate::core::io::BufStream;
+use crate::core::string::{self, Encoding};
+
+use log::info;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const ID: &[u8] = b"TAG";
+const EXT_ID: &[u8] = b"TAG+";
+
+const TAG_SIZE: usize = 128;
+const EXT_SIZE: usize = 227;
+
+/// An ID3v1 tag.
+///
+/// A tag can be created programmatically, or it can be opened from a file. All fields
+/// are exposed directly. More information can be found in the [module documentation](self).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Tag {
+    /// The title. Up to 30 bytes can be written, or 90 bytes if an enhanced block is written.
+    pub title: String,
+    /// The artist. Up to 30 bytes can be written, or 90 bytes if an enhanced block is written.
+    pub artist: String,
+    /// The album. Up to 30 bytes can be written, or 90 bytes if an enhanced block is written.
+    pub album: String,
+    /// The year. Up to 4 bytes can be written.
+    pub year: String,
+    /// The comment. Up to 30 bytes can be written, or 28 bytes if a track number is present.
+    pub comment: String,
+    /// The ID3v1.1 track number. A track number of zero is equivalent to no track number.
+    pub track: Option<u8>,
+    /// The index of the genre in [`GENRES`](GENRES). `255` means that no genre is set.
+    pub genre: u8,
+    /// The optional information from an enhanced `TAG+` block.
+    pub extended: Option<ExtendedInfo>,
+}
+
+impl Tag {
+    /// Creates an empty tag.
+    pub fn new() -> Self {
+        Self {
+            title: String::new(),
+            artist: String::new(),
+            album: String::new(),
+            year: String::new(),
+            comment: String::new(),
+            track: None,
+            genre: 255,
+            extended: None,
+        }
+    }
+
+    /// Attempts to open and parse a tag in `path`.
+    ///
+    /// If an enhanced `TAG+` block precedes the tag, it will be parsed as well.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be opened or does not contain a tag, then an error will be returned.
+    pub fn open<P: AsRef<Path>>(path: P) -> ParseResult<Self> {
+        let mut file = File::open(path)?;
+
+        let size = trailer_size(&mut file)?;
+
+        if size == 0 {
+            return Err(ParseError::NotFound);
+        }
+
+        let mut data = vec![0; size as usize];
+        file.seek(SeekFrom::End(-(size as i64)))?;
+        file.read_exact(&mut data)?;
+
+        Self::parse(&data)
+    }
+
+    pub(crate) fn parse(data: &[u8]) -> ParseResult<Self> {
+        // The data will either be a lone tag or an enhanced block followed by a tag.
+        let (ext, raw) = data.split_at(data.len() - TAG_SIZE);
+
+        if &raw[0..3] != ID {
+            return Err(ParseError::NotFound);
+        }
+
+        let mut stream = BufStream::new(&raw[3..]);
+        let mut ext_stream = BufStream::new(ext);
+
+        let has_ext = ext.len() == EXT_SIZE && ext_stream.read_array::<4>()? == EXT_ID;
+
+        // The enhanced block simply continues the title, artist, and album fields, so we
+        // join the raw field data before decoding it.
+        let mut read_long_field = |stream: &mut BufStream| -> io::Result<String> {
+            let mut field = stream.slice(30)?.to_vec();
+
+            if has_ext {
+                field.extend(ext_stream.slice(60)?);
+            }
+
+            Ok(decode_field(&field))
+        };
+
+        let title = read_long_field(&mut stream)?;
+        let artist = read_long_field(&mut stream)?;
+        let album = read_long_field(&mut stream)?;
+        let year = decode_field(stream.slice(4)?);
+
+        // ID3v1.1 stores the track number in the last byte of the comment, using the
+        // byte before it as a NUL separator.
+        let comment_raw = stream.read_array::<30>()?;
+
+        let (comment, track) = match (comment_raw[28], comment_raw[29]) {
+            (0, track) if track != 0 => (decode_field(&comment_raw[..28]), Some(track)),
+            _ => (decode_field(&comment_raw), None),
+        };
+
+        let genre = stream.read_u8()?;
+
+        let extended = if has_ext {
+            Some(ExtendedInfo {
+                speed: Speed::parse(ext_stream.read_u8()?),
+                genre: decode_field(ext_stream.slice(30)?),
+                start_time: decode_field(ext_stream.slice(6)?),
+                end_time: decode_field(ext_stream.slice(6)?),
+            })
+        } else {
+            None
+        };
+
+        Ok(Self {
+            title,
+            artist,
+            album,
+            year,
+            comment,
+            track,
+            genre,
+            extended,
+        })
+    }
+
+    /// Returns the name of this tag's genre, or `None` if the genre index is not
+    /// present in [`GENRES`](GENRES).
+    pub fn genre_name(&self) -> Option<&'static str> {
+        GENRES.get(usize::from(self.genre)).copied()
+    }
+
+    /// Returns whether this tag has no information.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::new()
+    }
+
+    /// Saves the tag to `path`.
+    ///
+    /// Any tag already present at the end of the file will be replaced. If not, the tag is
+    /// appended to the file. An enhanced `TAG+` block will be written if [`Tag.extended`](Tag.extended)
+    /// is present or if the title, artist, or album do not fit in a normal tag. If the tag is
+    /// empty, then the tag will be stripped from the file instead.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be opened or written to, an error will be returned.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> SaveResult<()> {
+        if self.is_empty() {
+            info!("tag is empty, deleting tag instead");
+            return Self::strip(path);
+        }
+
+        write_trailer(path, &self.render())
+    }
+
+    /// Removes any tag from the end of the file in `path`.
+    ///
+    /// Nothing will be done if there is no tag present.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be opened or written to, an error will be returned.
+    pub fn strip<P: AsRef<Path>>(path: P) -> SaveResult<()> {
+        write_trailer(path, &[])
+    }
+
+    pub(crate) fn render(&self) -> Vec<u8> {
+        let title = string::render(Encoding::Latin1, &self.title);
+        let artist = string::render(Encoding::Latin1, &self.artist);
+        let album = string::render(Encoding::Latin1, &self.album);
+
+        let needs_ext =
+            self.extended.is_some() || title.len() > 30 || artist.len() > 30 || album.len() > 30;
+
+        let mut result = Vec::with_capacity(TAG_SIZE + EXT_SIZE);
+
+        if needs_ext {
+            let ext = self.extended.clone().unwrap_or_default();
+
+            result.extend(EXT_ID);
+            result.extend(render_field(title.get(30..).unwrap_or_default(), 60));
+            result.extend(render_field(artist.get(30..).unwrap_or_default(), 60));
+            result.extend(render_field(album.get(30..).unwrap_or_default(), 60));
+            result.push(ext.speed as u8);
+            result.extend(render_str(&ext.genre, 30));
+            result.extend(render_str(&ext.start_time, 6));
+            result.extend(render_str(&ext.end_time, 6));
+        }
+
+        result.extend(ID);
+        result.extend(render_field(&title, 30));
+        result.extend(render_field(&artist, 30));
+        result.extend(render_field(&album, 30));
+        result.extend(render_str(&self.year, 4));
+
+        match self.track {
+            Some(track) if track != 0 => {
+                result.extend(render_str(&self.comment, 28));
+                result.push(0);
+                result.push(track);
+            }
+            _ => result.extend(render_str(&self.comment, 30)),
+        }
+
+        result.push(self.genre);
+
+        result
+    }
+}
+
+impl Default for Tag {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The information contained in an enhanced `TAG+` block.
+///
+/// The extended title, artist, and album data in this block are merged into the fields of
+/// [`Tag`](Tag), so only the information unique to this block is exposed here.
+#[derive(Default, Debug, Clone, Eq, PartialEq)]
+pub struct ExtendedInfo {
+    /// The speed of the audio.
+    pub speed: Speed,
+    /// A free-form genre. Up to 30 bytes can be written.
+    pub genre: String,
+    /// The start of the music, in the format `mmm:ss`.
+    pub start_time: String,
+    /// The end of the music, in the format `mmm:ss`.
+    pub end_time: String,
+}
+
+byte_enum! {
+    /// The speed of the audio, as defined by an enhanced `TAG+` block.
+    #[derive(Default)]
+    pub enum Speed {
+        /// No speed is set.
+        #[default]
+        Unset = 0x00,
+        Slow = 0x01,
+        Medium = 0x02,
+        Fast = 0x03,
+        Hardcore = 0x04,
+    };
+    Speed::Unset
+}
+
+/// Returns the size of the ID3v1 tag and enhanced block at the end of `src`, or zero if there
+/// is no tag.
+pub(crate) fn trailer_size<R: Read + Seek>(src: &mut R) -> io::Result<u64> {
+    let len = src.seek(SeekFrom::End(0))?;
+
+    if len < TAG_SIZE as u64 {
+        return Ok(0);
+    }
+
+    let mut id = [0; 4];
+
+    src.seek(SeekFrom::End(-(TAG_SIZE as i64)))?;
+    src.read_exact(&mut id[..3])?;
+
+    if &id[..3] != ID {
+        return Ok(0);
+    }
+
+    if len < (TAG_SIZE + EXT_SIZE) as u64 {
+        return Ok(TAG_SIZE as u64);
+    }
+
+    src.seek(SeekFrom::End(-((TAG_SIZE + EXT_SIZE) as i64)))?;
+    src.read_exact(&mut id)?;
+
+    if id == EXT_ID {
+        Ok((TAG_SIZE + EXT_SIZE) as u64)
+    } else {
+        Ok(TAG_SIZE as u64)
+    }
+}
+
+fn write_trailer<P: AsRef<Path>>(path: P, data: &[u8]) -> SaveResult<()> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+
+    // Replace any pre-existing trailer with our new data, truncating the file if
+    // the new data is smaller.
+    let old_size = trailer_size(&mut file)?;
+    let start = file.seek(SeekFrom::End(0))? - old_size;
+
+    file.seek(SeekFrom::Start(start))?;
+    file.write_all(data)?;
+    file.set_len(start + data.len() as u64)?;
+    file.flush()?;
+
+    Ok(())
+}
+
+fn decode_field(data: &[u8]) -> String {
+    // Fields are usually NUL-padded, but some taggers will pad them with spaces instead.
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    let string = string::read(Encoding::Latin1, &mut BufStream::new(&data[..end]));
+
+    String::from(string.trim_end_matches(' '))
+}
+
+fn render_str(string: &str, size: usize) -> Vec<u8> {
+    render_field(&string::render(Encoding::Latin1, string), size)
+}
+
+fn render_field(data: &[u8], size: usize) -> Vec<u8> {
+    let mut field = data[..usize::min(data.len(), size)].to_vec();
+    field.resize(size, 0);
+    field
+}
+
+/// The result given after a parsing operation.
+pub type ParseResult<T> = Result<T, ParseError>;
+
+/// The error type returned when parsing ID3v1 tags.
+#[derive(Debug)]
+pub enum ParseError {
+    /// Generic IO errors. This means that a problem occurred while opening the file
+    /// for a tag.
+    IoError(io::Error),
+    /// The tag was not found in the given file.
+    NotFound,
+}
+
+impl From<io::Error> for ParseError {
+    fn from(other: io::Error) -> Self {
+        ParseError::IoError(other)
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(err) => err.fmt(f),
+            Self::NotFound => write![f, "not found"],
+        }
+    }
+}
+
+impl error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::IoError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// The result given after a save operation.
+pub type SaveResult<T> = Result<T, SaveError>;
+
+/// The error type returned when saving ID3v1 tags.
+#[derive(Debug)]
+pub enum SaveError {
+    /// Generic IO errors. This means that a problem occurred while writing the tag to a file.
+    IoError(io::Error),
+}
+
+impl From<io::Error> for SaveError {
+    fn from(other: io::Error) -> Self {
+        SaveError::IoError(other)
+    }
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(err) => err.fmt(f),
+        }
+    }
+}
+
+impl error::Error for SaveError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::IoError(err) => Some(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    const V10_DATA: &[u8] = b"TAG\
+                              Title\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\
+                              Artist\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\
+                              Album                         \
+                              2004\
+                              A comment that fills the field\
+                              \x11";
+
+    const V11_DATA: &[u8] = b"TAG\
+                              Title\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\
+                              Artist\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\
+                              Album\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\
+                              2004\
+                              Comment\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\x03\
+                              \xFF";
+
+    #[test]
+    fn parse_v10() {
+        let tag = Tag::parse(V10_DATA).unwrap();
+
+        assert_eq!(tag.title, "Title");
+        assert_eq!(tag.artist, "Artist");
+        assert_eq!(tag.album, "Album");
+        assert_eq!(tag.year, "2004");
+        assert_eq!(tag.comment, "A comment that fills the field");
+        assert_eq!(tag.track, None);
+        assert_eq!(tag.genre, 0x11);
+        assert_eq!(tag.genre_name(), Some("Rock"));
+        assert_eq!(tag.extended, None);
+    }
+
+    #[test]
+    fn parse_v11() {
+        let tag = Tag::parse(V11_DATA).unwrap();
+
+        assert_eq!(tag.title, "Title");
+        assert_eq!(tag.artist, "Artist");
+        assert_eq!(tag.album, "Album");
+        assert_eq!(tag.year, "2004");
+        assert_eq!(tag.comment, "Comment");
+        assert_eq!(tag.track, Some(3));
+        assert_eq!(tag.genre, 0xFF);
+        assert_eq!(tag.genre_name(), None);
+    }
+
+    #[test]
+    fn render_v11() {
+        let mut tag = Tag::new();
+        tag.title = String::from("Title");
+        tag.artist = String::from("Artist");
+        tag.album = String::from("Album");
+        tag.year = String::from("2004");
+        tag.comment = String::from("Comment");
+        tag.track = Some(3);
+
+        assert_eq!(tag.render(), V11_DATA);
+    }
+
+    #[test]
+    fn render_enhanced() {
+        let mut tag = Tag::new();
+        tag.title = "A".repeat(50);
+        tag.artist = String::from("Artist");
+        tag.genre = 0;
+        tag.extended = Some(ExtendedInfo {
+            speed: Speed::Fast,
+            genre: String::from("Delta Blues"),
+            start_time: String::from("000:05"),
+            end_time: String::from("003:50"),
+        });
+
+        let data = tag.render();
+        assert_eq!(data.len(), EXT_SIZE + TAG_SIZE);
+        assert_eq!(&data[0..4], b"TAG+");
+        assert_eq!(&data[EXT_SIZE..EXT_SIZE + 3], b"TAG");
+
+        assert_eq!(Tag::parse(&data).unwrap(), tag);
+    }
+
+    #[test]
+    fn genre_list() {
+        assert_eq!(GENRES.len(), 192);
+        assert_eq!(GENRES[79], "Hard Rock");
+        assert_eq!(GENRES[191], "Psybient");
+    }
+
+    #[test]
+    fn save_and_strip() {
+        let path = env::var("CARGO_MANIFEST_DIR").unwrap() + "/res/test/example.mp3";
+        let out = env::temp_dir().join("musikr_id3v1_out.mp3");
+        fs::copy(&path, &out).unwrap();
+
+        let len = fs::metadata(&out).unwrap().len();
+        assert!(matches!(Tag::open(&out), Err(ParseError::NotFound)));
+
+        let mut tag = Tag::new();
+        tag.title = String::from("Title");
+        tag.track = Some(3);
+        tag.save(&out).unwrap();
+
+        assert_eq!(fs::metadata(&out).unwrap().len(), len + 128);
+        assert_eq!(Tag::open(&out).unwrap(), tag);
+
+        // Growing into an enhanced tag should replace the old tag, not append to it.
+        tag.album = "B".repeat(80);
+        tag.save(&out).unwrap();
+
+        assert_eq!(fs::metadata(&out).unwrap().len(), len + 355);
+        assert_eq!(Tag::open(&out).unwrap().album, tag.album);
+
+        Tag::strip(&out).unwrap();
+
+        assert_eq!(fs::metadata(&out).unwrap().len(), len);
+        assert!(matches!(Tag::open(&out), Err(ParseError::NotFound)));
+    }
+
+    #[test]
+    fn save_missing_file() {
+        let path = env::temp_dir().join("musikr_id3v1_missing.mp3");
+        let _ = fs::remove_file(&path);
+
+        let mut tag = Tag::new();
+        tag.title = String::from("Title");
+
+        for result in [tag.save(&path), Tag::strip(&path)] {
+            assert!(matches!(
+                result,
+                Err(SaveError::IoError(err)) if err.kind() == io::ErrorKind::NotFound
+            ));
+        }
+
+        assert!(!path.exists());
+    }
+}
@@ -33,13 +33,14 @@
 //! An ID3v2 tag is composed of the following components:
 //!
 //! - A header that contains the version, tag size, and flags. Only the version and tag size
-//! are exposed in the [`Tag`](Tag) type.
+//!   are exposed in the [`Tag`](Tag) type.
 //! - An optional extended header that signifies information about the tag when it was
-//! written. This is exposed with the [`ExtendedHeader`](tag::ExtendedHeader) type.
+//!   written. This is exposed with the [`ExtendedHeader`](tag::ExtendedHeader) type.
 //! - A body made up of "frames", which are the actual tags of the media. This is exposed 
-//! with [`FrameMap`](collections::FrameMap) for known frames, and [`UnknownFrames`](`collections::UnknownFrames`)
-//! for unknown frames.
-//! - On ID3v2.4, a footer might also be present. Musikr does not parse this.
+//!   with [`FrameMap`](collections::FrameMap) for known frames, and [`UnknownFrames`](`collections::UnknownFrames`)
+//!   for unknown frames.
+//! - On ID3v2.4, a footer might also be present. This is a copy of the header that allows
+//!   a tag to be appended to the end of a file, and can be enabled with [`Tag::set_footer`](Tag::set_footer).
 //!
 //! ## Frames
 //!
@@ -74,9 +75,9 @@
 //!
 //! This is because ID3v2 frames are differentiated in two ways:
 //! - A conventional Frame ID, which is the 4 character sequence in the beginning of each key.
-//! This differentiates the frame implementation.
+//!   This differentiates the frame implementation.
 //! - A "key", which is a reflection of what makes the frame "unique" from other frames of
-//! the same type. More information can be found in [`Frame::key`](crate::id3v2::frames::Frame::key).
+//!   the same type. More information can be found in [`Frame::key`](crate::id3v2::frames::Frame::key).
 //!
 //! All indexing methods in [`FrameMap`](collections::FrameMap) rely on the frame key. To access
 //! all frames that match a given Frame ID,  [`FrameMap::get_all`](collections::FrameMap::get_all)
@@ -121,6 +122,9 @@
 //! be added with [`FrameParser`](frames::FrameParser) and [`Tag::open_with_parser`](Tag::open_with_parser).
 //! More information can be found in the [`frames`](frames) module.
 //!
+//! Tags do not have to be in a file. [`Tag::read_from`](Tag::read_from) parses a tag from any
+//! reader, and [`Tag::from_bytes`](Tag::from_bytes) parses a tag from an in-memory buffer.
+//!
 //! # Tag versioning
 //!
 //! The `id3v2` module is designed with ID3v2.3 and ID3v2.4 in mind. Any ID3v2.2 tags are automatically converted
@@ -145,20 +149,26 @@ mod compat;
 #[macro_use]
 mod macros;
 pub mod frames;
+mod restrict;
 mod syncdata;
 pub mod tag;
 
-use crate::core::io::{write_replaced, BufStream};
+use crate::ape;
+use crate::core::io::{
+    write_replaced_in, write_spliced_with, BufStream, RestrictionMode, SaveOptions, Splice,
+};
 use collections::{FrameMap, UnknownFrames};
-use frames::{DefaultFrameParser, FrameParser, ParsedFrame};
+use frames::{DefaultFrameParser, FrameDecryptor, FrameParser, ParsedFrame, SeekFrame};
 use tag::{ExtendedHeader, SaveVersion, TagHeader, Version};
 
 use log::{error, info, warn};
 use std::error;
 use std::fmt::{self, Display, Formatter};
 use std::fs::File;
-use std::io::{self, Read};
+use std::io::{self, BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
 use std::path::Path;
+use std::sync::Arc;
 
 /// An ID3v2 tag.
 ///
@@ -177,6 +187,8 @@ pub struct Tag {
     pub frames: FrameMap,
     /// A collection of unknown frames encountered during parsing.
     pub unknown_frames: UnknownFrames,
+    decryptor: Option<Arc<dyn FrameDecryptor>>,
+    crc_mismatch: bool,
 }
 
 impl Tag {
@@ -195,6 +207,8 @@ impl Tag {
             extended_header: None,
             frames: FrameMap::new(),
             unknown_frames: UnknownFrames::new(Version::from(version), Vec::new()),
+            decryptor: None,
+            crc_mismatch: false,
         }
     }
 
@@ -204,6 +218,14 @@ impl Tag {
     /// strict mode enabled. If a frame is malformed, then the parsing process will
     /// stop at that point and return the tag.
     ///
+    /// If the tag has a [`SeekFrame`](frames::SeekFrame), the tag it points to will be parsed
+    /// and merged into this tag. If the later tag is marked as an update with
+    /// [`ExtendedHeader::is_update`](tag::ExtendedHeader::is_update), its frames will replace
+    /// the frames of the earlier tag.
+    ///
+    /// If the tag has a CRC-32, it is checked against the tag data but will not cause an
+    /// error. Use [`Tag::verify_crc`](Tag::verify_crc) to find if it matched.
+    ///
     /// # Errors
     ///
     /// If the file cannot be opened, does not contain a tag, or if the tag is malformed, 
@@ -229,11 +251,110 @@ impl Tag {
         path: P,
         parser: &impl FrameParser,
     ) -> ParseResult<Self> {
-        let mut file = File::open(path)?;
+        let mut file = BufReader::new(File::open(path)?);
+
+        let mut tag = match Self::read_from_with_parser(&mut file, parser) {
+            Err(ParseError::NotFound) => {
+                // ID3v2.4 tags can also be appended to the end of a file, which can only be
+                // found with their footer.
+                match find_appended(&mut file)? {
+                    Some(Range { start, .. }) => {
+                        info!("found appended tag at {}", start);
+                        file.seek(SeekFrom::Start(start))?;
+                        Self::read_from_with_parser(&mut file, parser)?
+                    }
+                    None => return Err(ParseError::NotFound),
+                }
+            }
+            result => result?,
+        };
+
+        tag.follow_seek(&mut file, parser)?;
+
+        Ok(tag)
+    }
+
+    /// Follows the `SEEK` frames in this tag, merging every tag that they point to. `src` must
+    /// be positioned at the end of this tag.
+    fn follow_seek<R: Read + Seek>(
+        &mut self,
+        src: &mut R,
+        parser: &impl FrameParser,
+    ) -> io::Result<()> {
+        // The SEEK frame is removed once followed, as it will no longer be valid if the
+        // merged tag is saved.
+        while let Some(frame) = self.frames.remove("SEEK") {
+            let offset = match frame.downcast::<SeekFrame>() {
+                Some(seek) => seek.offset,
+                None => break,
+            };
+
+            let next = src.stream_position()? + u64::from(offset);
+            src.seek(SeekFrom::Start(next))?;
+
+            match Self::read_from_with_parser(src, parser) {
+                Ok(tag) => {
+                    info!("merging tag found at {}", next);
+                    self.merge(tag)
+                }
+                Err(err) => {
+                    warn!("could not parse tag pointed to by seek frame: {}", err);
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
 
+    /// Merges the frames of a tag found later in the file into this tag.
+    ///
+    /// If the later tag is marked as an update, its frames replace any frames with the same
+    /// key. Otherwise, they are added like with [`FrameMap::add`](FrameMap::add).
+    fn merge(&mut self, other: Tag) {
+        let is_update = matches!(&other.extended_header, Some(ext) if ext.is_update);
+
+        self.frames.merge(other.frames, is_update);
+        self.crc_mismatch |= other.crc_mismatch;
+
+        if other.unknown_frames.version() == self.unknown_frames.version() {
+            let mut unknowns = self.unknown_frames.frames().to_vec();
+            unknowns.extend(other.unknown_frames.frames().iter().cloned());
+            self.unknown_frames = UnknownFrames::new(self.unknown_frames.version(), unknowns);
+        }
+    }
+
+    /// Attempts to parse a tag that starts at the current position of `src`.
+    ///
+    /// This is useful for tags that are not at the start of a file, or that are not in a
+    /// file at all. Only the tag will be read from `src`, leaving it positioned at the end
+    /// of the tag. [`DefaultFrameParser`](DefaultFrameParser) will be used, as in
+    /// [`Tag::open`](Tag::open).
+    ///
+    /// # Errors
+    ///
+    /// If `src` cannot be read, does not start with a tag, or if the tag is malformed, an
+    /// error will be returned with a general reason for why. Specific information about
+    /// parsing errors will be logged.
+    pub fn read_from<R: Read>(src: &mut R) -> ParseResult<Self> {
+        Self::read_from_with_parser(src, &DefaultFrameParser::default())
+    }
+
+    /// Attempts to parse a tag that starts at the current position of `src` with a
+    /// [`FrameParser`](FrameParser).
+    ///
+    /// # Errors
+    ///
+    /// If `src` cannot be read, does not start with a tag, or if the tag is malformed, an
+    /// error will be returned with a general reason for why. Specific information about
+    /// parsing errors will be logged.
+    pub fn read_from_with_parser<R: Read>(
+        src: &mut R,
+        parser: &impl FrameParser,
+    ) -> ParseResult<Self> {
         // Read and parse the possible ID3v2 header
         let mut header_raw = [0; 10];
-        file.read_exact(&mut header_raw)?;
+        src.read_exact(&mut header_raw)?;
 
         let mut header = TagHeader::parse(header_raw).map_err(|err| match err {
             ParseError::MalformedData => ParseError::NotFound,
@@ -241,9 +362,29 @@ impl Tag {
         })?;
 
         // Then get the full tag data. If the size is invalid, then we will just truncate it.
-        let mut tag_data = vec![0; header.size() as usize];
-        let read = file.read(&mut tag_data)?;
-        tag_data.truncate(read);
+        let mut tag_data = Vec::new();
+        src.take(u64::from(header.size()))
+            .read_to_end(&mut tag_data)?;
+
+        if header.flags().footer {
+            let mut footer_raw = [0; 10];
+
+            match src.read_exact(&mut footer_raw) {
+                Ok(_) => {
+                    let footer = TagHeader::parse_footer(footer_raw)?;
+
+                    if !header.matches_footer(&footer) {
+                        error!("footer does not match the tag header");
+                        return Err(ParseError::MalformedData);
+                    }
+                }
+                Err(err) if err.kind() == ErrorKind::UnexpectedEof => {
+                    info!("resetting footer flag on truncated tag");
+                    header.flags_mut().footer = false
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
 
         let mut stream = BufStream::new(&tag_data);
 
@@ -267,13 +408,36 @@ impl Tag {
             }
         }
 
+        // Check the CRC-32 of the tag data if present. ID3v2.3 only covers the frames, while
+        // ID3v2.4 covers everything after the extended header, including the padding.
+        let mut crc_mismatch = false;
+
+        if let Some(ext) = &extended_header {
+            if let Some(crc) = ext.crc32 {
+                let data = &tag_data[stream.pos()..];
+
+                let data = match header.version() {
+                    Version::V24 => data,
+                    _ => {
+                        let padding = ext.padding_size.unwrap_or_default() as usize;
+                        &data[..data.len().saturating_sub(padding)]
+                    }
+                };
+
+                if tag::crc32(data) != crc {
+                    warn!("CRC-32 does not match the tag data");
+                    crc_mismatch = true;
+                }
+            }
+        }
+
         // Now try parsing our frames.
         let mut frames = FrameMap::new();
         let mut unknowns = Vec::new();
 
         while let Ok(parsed) = frames::parse(&header, &mut stream, parser) {
             match parsed {
-                ParsedFrame::Frame(frame) => frames.add_boxed(frame),
+                ParsedFrame::Frame(frame, flags) => frames.add_parsed(&header, frame, flags),
                 ParsedFrame::Unknown(unknown) => {
                     info!("found unknown frame {}", unknown.id_str());
                     unknowns.push(unknown)
@@ -296,9 +460,36 @@ impl Tag {
             extended_header,
             frames,
             unknown_frames,
+            decryptor: None,
+            crc_mismatch,
         })
     }
 
+    /// Attempts to parse a tag from the start of `data`.
+    ///
+    /// Any data after the tag is ignored. [`DefaultFrameParser`](DefaultFrameParser) will be
+    /// used, as in [`Tag::open`](Tag::open).
+    ///
+    /// # Errors
+    ///
+    /// If `data` does not start with a tag, or if the tag is malformed, an error will be
+    /// returned with a general reason for why. Specific information about parsing errors
+    /// will be logged.
+    pub fn from_bytes(data: &[u8]) -> ParseResult<Self> {
+        Self::from_bytes_with_parser(data, &DefaultFrameParser::default())
+    }
+
+    /// Attempts to parse a tag from the start of `data` with a [`FrameParser`](FrameParser).
+    ///
+    /// # Errors
+    ///
+    /// If `data` does not start with a tag, or if the tag is malformed, an error will be
+    /// returned with a general reason for why. Specific information about parsing errors
+    /// will be logged.
+    pub fn from_bytes_with_parser(data: &[u8], parser: &impl FrameParser) -> ParseResult<Self> {
+        Self::read_from_with_parser(&mut &data[..], parser)
+    }
+
     /// Returns the version of this tag.
     ///
     /// While ID3v2.2 tags are converted to ID3v2.3, the version will still be
@@ -311,13 +502,123 @@ impl Tag {
     /// Returns the total size of this tag, in bytes.
     ///
     /// The size includes the extended header, the tag body [e.g all frames], and
-    /// the footer. This value is only updated when the tag is read or saved, so it
-    /// may not be accurate to the current contents of a tag. In a freshly created
-    /// tag, this value will be `0`.
+    /// the padding, but not the header or footer. This value is only updated when the
+    /// tag is read or saved, so it may not be accurate to the current contents of a tag.
+    /// In a freshly created tag, this value will be `0`.
     pub fn size(&self) -> u32 {
         self.header.size()
     }
 
+    /// Returns whether this tag has a footer.
+    ///
+    /// Footers are only present in ID3v2.4 tags.
+    pub fn has_footer(&self) -> bool {
+        self.header.flags().footer
+    }
+
+    /// Sets whether this tag will be written with a footer.
+    ///
+    /// A footer is a copy of the tag header at the end of the tag, which allows the tag to be
+    /// found when it's appended to the end of a file. Footers are only written on ID3v2.4 tags,
+    /// and a tag with a footer will not have any padding.
+    pub fn set_footer(&mut self, footer: bool) {
+        self.header.flags_mut().footer = footer
+    }
+
+    /// Returns whether this tag is unsynchronized.
+    pub fn is_unsync(&self) -> bool {
+        self.header.flags().unsync
+    }
+
+    /// Sets whether this tag will be written with unsynchronization.
+    ///
+    /// Unsynchronization inserts a `0x00` byte after any `0xFF` byte that could be mistaken
+    /// for an MPEG sync signal, which is only needed by legacy hardware and software that
+    /// are not aware of ID3v2. On ID3v2.3, the entire tag body is unsynchronized, while on
+    /// ID3v2.4, every frame is unsynchronized individually. Tags that were unsynchronized
+    /// when read will stay unsynchronized unless this is disabled.
+    pub fn set_unsync(&mut self, unsync: bool) {
+        self.header.flags_mut().unsync = unsync
+    }
+
+    /// Sets whether a CRC-32 of the tag data will be written to the extended header.
+    ///
+    /// The CRC-32 is computed when the tag is saved. Enabling this will create an extended
+    /// header if one is not present.
+    pub fn set_crc(&mut self, crc: bool) {
+        match &mut self.extended_header {
+            Some(ext) => ext.crc32 = crc.then_some(0),
+            None if crc => {
+                self.extended_header = Some(ExtendedHeader {
+                    crc32: Some(0),
+                    ..Default::default()
+                })
+            }
+            None => {}
+        }
+    }
+
+    /// Checks the CRC-32 in the extended header against the tag data that was read.
+    ///
+    /// Tags without a CRC-32 will always pass. This only reflects the data that was read
+    /// when the tag was parsed, and will always pass after the tag is saved.
+    ///
+    /// # Errors
+    ///
+    /// If the CRC-32 did not match the tag data, [`ParseError::CrcMismatch`](ParseError::CrcMismatch)
+    /// will be returned.
+    pub fn verify_crc(&self) -> ParseResult<()> {
+        match self.crc_mismatch {
+            true => Err(ParseError::CrcMismatch),
+            false => Ok(()),
+        }
+    }
+
+    /// Decrypts any encrypted frames in this tag with `decryptor`.
+    ///
+    /// Encrypted frames are kept in [`Tag::unknown_frames`](Tag::unknown_frames) when parsed,
+    /// as the [`EncryptionMethodFrame`](crate::id3v2::frames::EncryptionMethodFrame) that
+    /// registers their method may come after them. Frames that can be decrypted are parsed
+    /// with [`DefaultFrameParser`](DefaultFrameParser) and moved into [`Tag::frames`](Tag::frames),
+    /// with their method kept in their [`FrameFlags`](crate::id3v2::frames::FrameFlags).
+    /// `decryptor` is kept so that these frames can be encrypted again when the tag is saved.
+    pub fn decrypt<D: FrameDecryptor + 'static>(&mut self, decryptor: D) {
+        self.decrypt_with_parser(decryptor, &DefaultFrameParser::default())
+    }
+
+    /// Decrypts any encrypted frames in this tag with `decryptor`, parsing them with a
+    /// [`FrameParser`](FrameParser).
+    ///
+    /// More information can be found in [`Tag::decrypt`](Tag::decrypt).
+    pub fn decrypt_with_parser<D: FrameDecryptor + 'static>(
+        &mut self,
+        decryptor: D,
+        parser: &impl FrameParser,
+    ) {
+        if self.unknown_frames.version() == self.version() {
+            let mut decrypted = Vec::new();
+            let mut unknowns = Vec::new();
+
+            for unknown in self.unknown_frames.frames() {
+                match frames::decrypt(&self.header, unknown, &self.frames, &decryptor, parser) {
+                    Some(ParsedFrame::Frame(frame, flags)) => decrypted.push((frame, flags)),
+                    Some(ParsedFrame::Dropped) => {}
+                    // Frames that are still unknown after being decrypted are kept encrypted,
+                    // as there is no way to encrypt them again otherwise.
+                    _ => unknowns.push(unknown.clone()),
+                }
+            }
+
+            for (frame, flags) in decrypted {
+                self.frames.add_parsed(&self.header, frame, flags)
+            }
+
+            self.unknown_frames = UnknownFrames::new(self.version(), unknowns);
+        }
+
+        self.decryptor = Some(Arc::new(decryptor));
+    }
+
     /// Update the tag to the specified version.
     ///
     /// **Update operations are inherently destructive.** Frames will be renamed, merged,
@@ -328,7 +629,7 @@ impl Tag {
     /// ```text
     /// EQU2 -> Dropped (no sane conversion)
     /// RVA2 -> Dropped (no sane conversion)
-    /// ASPI -> Dropped (no analogue)
+    /// ASPI -> Dropped (no analogue, cannot be regenerated)
     /// SEEK -> Dropped (no analogue)
     /// SIGN -> Dropped (no analogue)
     /// TDEN -> Dropped (no analogue)
@@ -350,6 +651,9 @@ impl Tag {
     ///          TYER   TDAT    TIME
     /// ```
     ///
+    /// Dropping `ASPI` logs a warning, as the index can only be rebuilt by parsing the MPEG
+    /// audio, which musikr does not do. Upgrading the tag to ID3v2.4 again will not restore it.
+    ///
     /// # ID3v2.4 Conversions
     /// ```text
     /// EQUA -> Dropped (no sane conversion)
@@ -391,17 +695,238 @@ impl Tag {
     ///
     /// All known frames will be written, while unknown frames will be written only if [`Tag::version`](Tag::version)
     /// is equal to [`UnknownFrames::version`](crate::id3v2::collections::UnknownFrames::version).
-    /// No unsynchronization, compression, or similar manipulation is done on the tag body, and
-    /// all flags will be zeroed.
+    /// Unsynchronization is only done if enabled with [`Tag::set_unsync`](Tag::set_unsync). The
+    /// [`FrameFlags`](crate::id3v2::frames::FrameFlags) of each frame are written alongside it,
+    /// and unknown frames that should be discarded when the tag is altered are dropped.
+    ///
+    /// If a frame marked as read-only was modified, then the tag will not be written. This can be
+    /// overridden with [`SaveOptions::force`](crate::core::SaveOptions::force). Frames marked as
+    /// encrypted are only written if they can be encrypted with the decryptor given to
+    /// [`Tag::decrypt`](Tag::decrypt).
+    ///
+    /// The [`Restrictions`](tag::Restrictions) of an ID3v2.4 tag are not checked unless enabled
+    /// with [`SaveOptions::restrictions`](crate::core::SaveOptions::restrictions).
     ///
     /// The tag will be written to the file regardless of if a previous tag is present. If the tag
     /// is written to a file that may not support ID3v2, this may render the file inoperable.
+    /// The tag is always written to the start of the file. If the file has no tag at the start
+    /// but has a tag appended to the end of it, such as a tag read by [`Tag::open`](Tag::open),
+    /// then the appended tag is removed so that the file does not keep a stale copy of it.
     /// If the written tag is smaller than a pre-existing tag, at most 1% of the file size will be
-    /// used for padding. If the tag is larger, then 1 KiB of padding will be applied.
+    /// used for padding. If the tag is larger, then 1 KiB of padding will be applied. Other
+    /// amounts of padding can be used with [`Tag::save_with`](Tag::save_with) and
+    /// [`PaddingStrategy`](crate::core::PaddingStrategy).
     ///
     /// If the tag creation or writing process fails, then an error with a general reason will
     /// be returned.  Specific information about saving errors will be logged.
     pub fn save<P: AsRef<Path>>(&mut self, path: P) -> SaveResult<()> {
+        self.save_with(path, &SaveOptions::default())
+    }
+
+    /// Saves the tag to `path` with the given [`SaveOptions`](crate::core::SaveOptions).
+    ///
+    /// This behaves like [`Tag::save`](Tag::save), except that the file can be written
+    /// atomically, ensuring that the original file is left intact if the process is
+    /// interrupted or the disk is full.
+    ///
+    /// If the tag creation or writing process fails, then an error with a general reason will
+    /// be returned.  Specific information about saving errors will be logged.
+    pub fn save_with<P: AsRef<Path>>(&mut self, path: P, options: &SaveOptions) -> SaveResult<()> {
+        self.save_with_progress(path, options, |_, _| {})
+    }
+
+    /// Saves the tag to `path` with the given [`SaveOptions`](crate::core::SaveOptions),
+    /// reporting progress to `progress`.
+    ///
+    /// If the audio data has to be moved, it will be moved in fixed-size blocks so that the file
+    /// never has to be loaded into memory. After each block, `progress` is called with the
+    /// number of bytes moved so far and the total number of bytes to move.
+    ///
+    /// If the tag creation or writing process fails, then an error with a general reason will
+    /// be returned.  Specific information about saving errors will be logged.
+    pub fn save_with_progress<P: AsRef<Path>, F: FnMut(u64, u64)>(
+        &mut self,
+        path: P,
+        options: &SaveOptions,
+        mut progress: F,
+    ) -> SaveResult<()> {
+        // Check if theres an existing tag in this file or not. If there is, keep track of its
+        // size so that we can replace it with this tag.
+        let mut len = 0;
+        let mut old_end = 0;
+        let mut appended = None;
+
+        if let Ok(mut file) = File::open(&path) {
+            len = file.metadata()?.len();
+            old_end = existing_end(&mut file)?;
+
+            // If the file only has a tag appended to the end of it, then that tag would become
+            // stale once this tag is written to the start, so it has to be removed.
+            if old_end == 0 {
+                appended = find_appended(&mut file)?;
+            }
+        }
+
+        // Find a sensible padding length. We make all tag sizes here u64 so that we don't accidentally
+        // overflow while doing this.
+        let tag_data = self.render_with(options, |tag_size| {
+            options.padding.padding(tag_size + 10, old_end, len)
+        })?;
+
+        if tag_data.is_empty() {
+            info!("tag is empty, deleting tag instead");
+        }
+
+        let mut splices = vec![Splice::new(0, old_end, &tag_data)];
+
+        if let Some(range) = appended {
+            info!("removing appended tag at {}", range.start);
+            splices.push(Splice::new(range.start, range.end, &[]));
+        }
+
+        write_spliced_with(path, &splices, options, &mut progress)?;
+
+        Ok(())
+    }
+
+    /// Writes the tag to the start of `dst`.
+    ///
+    /// This behaves like [`Tag::save`](Tag::save), except that `dst` can be any stream, such as
+    /// a [`Cursor`](std::io::Cursor) or a file that is already open. Since a stream cannot be
+    /// truncated, a tag that is smaller than a pre-existing tag will be padded to take up the
+    /// same space, and an empty tag will be replaced with a tag that only contains padding.
+    /// A tag with a footer that has to be padded is written without its footer, but will
+    /// otherwise be left with it.
+    ///
+    /// # Errors
+    ///
+    /// If the tag creation or writing process fails, then an error with a general reason will
+    /// be returned. Specific information about saving errors will be logged.
+    pub fn write_to<W: Read + Write + Seek>(&mut self, dst: &mut W) -> SaveResult<()> {
+        self.check_read_only()?;
+
+        let old_end = existing_end(dst)?;
+
+        // If the tag is smaller, fill the remaining space. Otherwise, use 1KiB.
+        let mut tag_data = self
+            .render_padded(|tag_size| u64::checked_sub(old_end, tag_size + 10).unwrap_or(1024))?;
+
+        // A tag with a footer cannot have padding, so the footer must be left out if the tag
+        // has to fill up the space of a larger tag. The tag itself still keeps its footer.
+        if self.has_footer() && !tag_data.is_empty() && (tag_data.len() as u64) < old_end {
+            warn!("cannot pad a tag with a footer, writing it without one");
+            self.set_footer(false);
+
+            let result = self
+                .render_padded(|tag_size| u64::checked_sub(old_end, tag_size + 10).unwrap_or(1024));
+
+            self.set_footer(true);
+            tag_data = result?;
+        }
+
+        if tag_data.is_empty() && old_end > 0 {
+            info!("tag is empty, replacing tag with padding");
+
+            let mut header = self.header.clone();
+            *header.size_mut() = (old_end - 10) as u32;
+            header.flags_mut().extended = false;
+            header.flags_mut().footer = false;
+
+            tag_data = header.render().to_vec();
+            tag_data.resize(old_end as usize, 0);
+        }
+
+        write_replaced_in(dst, &tag_data, old_end, &mut |_, _| {})?;
+
+        Ok(())
+    }
+
+    /// Renders the complete tag, including the header, extended header, frames, and 1 KiB
+    /// of padding.
+    ///
+    /// Like [`Tag::save`](Tag::save), the tag will be updated to the version it will be
+    /// written as. If the tag has no frames, then nothing will be rendered.
+    ///
+    /// # Errors
+    ///
+    /// If the tag is too large to be rendered, or if a frame marked as encrypted cannot be
+    /// encrypted, an error will be returned.
+    pub fn render(&mut self) -> SaveResult<Vec<u8>> {
+        self.render_padded(|_| 1024)
+    }
+
+    /// Renders the tag for saving with `options`.
+    ///
+    /// Unless [`SaveOptions::force`](crate::core::SaveOptions::force) is set, the tag is not
+    /// rendered if a read-only frame was modified. Restrictions are applied as configured by
+    /// [`SaveOptions::restrictions`](crate::core::SaveOptions::restrictions), and padding is
+    /// limited so that it can't push the tag over the restricted size.
+    pub(crate) fn render_with<F: FnOnce(u64) -> u64>(
+        &mut self,
+        options: &SaveOptions,
+        padding: F,
+    ) -> SaveResult<Vec<u8>> {
+        if !options.force {
+            self.check_read_only()?;
+        }
+
+        // Restrictions only exist in ID3v2.4 tags.
+        let restrictions = match (&self.extended_header, options.restrictions) {
+            (_, RestrictionMode::Ignore) => None,
+            (Some(ext), _) if self.version() == Version::V24 => ext.restrictions,
+            _ => None,
+        };
+
+        let max_size = match restrictions {
+            Some(restrictions) => {
+                restrict::apply(
+                    &mut self.frames,
+                    self.unknown_frames.frames().len(),
+                    restrictions,
+                    options.restrictions,
+                )?;
+
+                restrict::max_tag_size(restrictions.tag_size).1
+            }
+            None => u64::MAX,
+        };
+
+        let tag_data = self.render_padded(|tag_size| {
+            padding(tag_size).min(max_size.saturating_sub(tag_size + 10))
+        })?;
+
+        if tag_data.len() as u64 > max_size {
+            error!(
+                "tag is larger than its restricted size of {} bytes",
+                max_size
+            );
+            return Err(SaveError::Restricted);
+        }
+
+        Ok(tag_data)
+    }
+
+    /// Ensures that no read-only frames have been modified.
+    fn check_read_only(&self) -> SaveResult<()> {
+        let modified = self.frames.modified_read_only(&self.header);
+
+        if modified.is_empty() {
+            return Ok(());
+        }
+
+        for key in modified {
+            error!("read-only frame {} was modified", key);
+        }
+
+        Err(SaveError::ReadOnly)
+    }
+
+    /// Renders the full tag, with the amount of padding being decided by `padding` from the
+    /// size of the tag body. If the tag has no frames, nothing is rendered.
+    pub(crate) fn render_padded<F: FnOnce(u64) -> u64>(
+        &mut self,
+        padding: F,
+    ) -> SaveResult<Vec<u8>> {
         // Before saving, ensure that our tag has been fully upgraded. ID3v2.2 tags always
         // become ID3v2.3 tags, as it has been obsoleted.
         match self.header.version() {
@@ -410,22 +935,25 @@ impl Tag {
         };
 
         // Reset all the flags that we don't really have a way to expose or support.
+        let version = self.header.version();
         let flags = self.header.flags_mut();
-        flags.unsync = false; // Modern software is aware of ID3v2, making this obsolete
         flags.extended = self.extended_header.is_some(); // Supported
         flags.experimental = false; // This has no use defined by the spec
-        flags.footer = false; // May be exposed in the future
+        flags.footer = flags.footer && version == Version::V24; // Only supported on ID3v2.4
+        let footer = flags.footer;
 
-        // Render the extended header first, if it's present.
-        let mut tag_data = match &self.extended_header {
-            Some(ext) => ext.render(self.header.version()),
-            None => Vec::new(),
+        // The extended header depends on the rest of the tag, so only find its size for now.
+        let ext_len = match &self.extended_header {
+            Some(ext) => ext.render(version).len(),
+            None => 0,
         };
 
-        // Keep track of the body length here so we can tell if we actually wrote frames.
-        let start_len = tag_data.len();
+        let mut tag_data = Vec::new();
 
-        tag_data.extend(self.frames.render(&self.header));
+        tag_data.extend(
+            self.frames
+                .render(&self.header, self.decryptor.as_deref())?,
+        );
 
         // While we could theoretically upgrade unknown frames, its better that we don't
         // since they could be metaframes and since the flags would also have to be changed.
@@ -437,61 +965,75 @@ impl Tag {
             warn!("dropping {} unknown frames", self.unknown_frames.version())
         }
 
-        // Check if theres an existing header in this file or not.
-        // If there is, keep track of its size so that we can replace it with this tag.
-        let mut len = 0;
-        let mut old_size = 0;
-
-        if let Ok(mut file) = File::open(&path) {
-            len = file.metadata()?.len();
+        // Make sure our tag isn't empty. If it is, then nothing should be written.
+        if tag_data.is_empty() {
+            *self.header.size_mut() = 0;
+            return Ok(Vec::new());
+        }
 
-            let mut header_raw = [0; 10];
+        // ID3v2.3 unsynchronization applies to the entire tag body, unlike ID3v2.4 where
+        // it has already been done on each frame.
+        let unsync = version == Version::V23 && self.header.flags().unsync;
 
-            if file.read(&mut header_raw).is_ok() {
-                if let Ok(header) = TagHeader::parse(header_raw) {
-                    info!("found previously written tag, will be overwritten");
+        let body_size = match unsync {
+            true => ext_len + syncdata::encode(&tag_data).len(),
+            false => ext_len + tag_data.len(),
+        } as u64;
 
-                    old_size = header.size() as u64
-                }
-            };
-        }
+        // Tags with footers cannot have padding.
+        let padding_size = match footer {
+            true => 0,
+            false => padding(body_size),
+        };
 
-        // Make sure our tag isn't empty. If it is, then we will just delete the tag.
-        if tag_data.len() > start_len {
-            // Find a sensible padding length. We make all tag sizes here u64 so that we don't accidentally
-            // overflow while doing this.
-            let tag_size = tag_data.len() as u64;
+        // Now that the padding is known, the extended header can be rendered. The CRC-32 in
+        // ID3v2.3 only covers the frames, while ID3v2.4 also covers the padding.
+        if let Some(ext) = &mut self.extended_header {
+            if version == Version::V23 {
+                ext.padding_size = Some(padding_size as u32);
+            }
 
-            let padding_size = match u64::checked_sub(old_size, tag_size) {
-                Some(delta) => u64::min(delta, len / 100), // Tag is smaller, use the remaining space or 1% of the file size
-                None => 1024,                              // Tag is larger, use 1KiB.
-            };
+            if ext.crc32.is_some() {
+                let crc = match version {
+                    Version::V24 => {
+                        let mut data = tag_data.clone();
+                        data.resize(tag_data.len() + padding_size as usize, 0);
+                        tag::crc32(&data)
+                    }
+                    _ => tag::crc32(&tag_data),
+                };
+
+                ext.crc32 = Some(crc);
+            }
 
-            let tag_size = tag_size + padding_size;
+            tag_data.splice(0..0, ext.render(version));
+        }
 
-            // Tag sizes are syncsafe, so tags can never be more than 256mb. This also ensures that we won't overflow the
-            // u32 when we cast it.
-            if tag_size > 256_000_000 {
-                error!("tag was larger than 256mb");
-                return Err(SaveError::TooLarge);
-            }
+        if unsync {
+            tag_data = syncdata::encode(&tag_data);
+        }
 
-            *self.header.size_mut() = tag_size as u32;
+        let tag_size = tag_data.len() as u64 + padding_size;
 
-            // Finalize our tag, adding the padding and prepending the header.
-            tag_data.resize(tag_size as usize, 0);
-            tag_data.splice(0..0, self.header.render());
+        // Tag sizes are syncsafe, so tags can never be more than 256mb. This also ensures that we won't overflow the
+        // u32 when we cast it.
+        if tag_size > 256_000_000 {
+            error!("tag was larger than 256mb");
+            return Err(SaveError::TooLarge);
+        }
 
-            write_replaced(path, &tag_data, old_size + 10)?;
-        } else {
-            info!("tag is empty, deleting tag instead");
+        self.crc_mismatch = false;
+        *self.header.size_mut() = tag_size as u32;
 
-            *self.header.size_mut() = 0;
+        // Finalize our tag, adding the padding, prepending the header, and appending the footer.
+        tag_data.resize(tag_size as usize, 0);
+        tag_data.splice(0..0, self.header.render());
 
-            write_replaced(&path, &[], old_size + 10)?;
+        if footer {
+            tag_data.extend(self.header.render_footer());
         }
 
-        Ok(())
+        Ok(tag_data)
     }
 }
 
@@ -501,6 +1043,54 @@ impl Default for Tag {
     }
 }
 
+/// Returns the end of the tag at the start of `src`, or 0 if there is no tag.
+fn existing_end<R: Read + Seek>(src: &mut R) -> io::Result<u64> {
+    let mut header_raw = [0; 10];
+    src.seek(SeekFrom::Start(0))?;
+
+    match src.read_exact(&mut header_raw) {
+        Ok(_) => {}
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(0),
+        Err(err) => return Err(err),
+    }
+
+    match TagHeader::parse(header_raw) {
+        Ok(header) => {
+            info!("found previously written tag, will be overwritten");
+
+            let footer_size = if header.flags().footer { 10 } else { 0 };
+            Ok(u64::from(header.size()) + 10 + footer_size)
+        }
+        Err(_) => Ok(0),
+    }
+}
+
+/// Finds a tag appended to the end of `src` by scanning backwards for its footer, skipping
+/// any APE or ID3v1 tags after it. The range of the tag, from its header to the end of its
+/// footer, is returned.
+fn find_appended<R: Read + Seek>(src: &mut R) -> io::Result<Option<Range<u64>>> {
+    let end = ape::trailer_start(src)?;
+
+    if end < 20 {
+        return Ok(None);
+    }
+
+    let mut footer_raw = [0; 10];
+    src.seek(SeekFrom::Start(end - 10))?;
+    src.read_exact(&mut footer_raw)?;
+
+    if &footer_raw[0..3] != b"3DI" {
+        return Ok(None);
+    }
+
+    match TagHeader::parse_footer(footer_raw) {
+        Ok(footer) => Ok(end
+            .checked_sub(u64::from(footer.size()) + 20)
+            .map(|start| start..end)),
+        Err(_) => Ok(None),
+    }
+}
+
 /// The result given after a parsing operation.
 pub type ParseResult<T> = Result<T, ParseError>;
 
@@ -516,6 +1106,8 @@ pub enum ParseError {
     Unsupported,
     /// The tag was not found in the given file.
     NotFound,
+    /// The CRC-32 of the tag did not match the tag data.
+    CrcMismatch,
 }
 
 impl From<io::Error> for ParseError {
@@ -531,6 +1123,7 @@ impl Display for ParseError {
             Self::MalformedData => write![f, "malformed data"],
             Self::Unsupported => write![f, "unsupported"],
             Self::NotFound => write![f, "not found"],
+            Self::CrcMismatch => write![f, "crc-32 does not match"],
         }
     }
 }
@@ -556,6 +1149,12 @@ pub enum SaveError {
     IoError(io::Error),
     /// The tag [or an element in the tag] was too large to be written.
     TooLarge,
+    /// A frame marked as read-only was modified.
+    ReadOnly,
+    /// A frame marked as encrypted could not be encrypted.
+    EncryptionFailed,
+    /// The tag did not comply with the restrictions in its extended header.
+    Restricted,
 }
 
 impl From<io::Error> for SaveError {
@@ -569,6 +1168,9 @@ impl Display for SaveError {
         match self {
             Self::IoError(err) => err.fmt(f),
             Self::TooLarge => write![f, "tag is too large to be saved"],
+            Self::ReadOnly => write![f, "read-only frame was modified"],
+            Self::EncryptionFailed => write![f, "frame could not be encrypted"],
+            Self::Restricted => write![f, "tag does not comply with its restrictions"],
         }
     }
 }
@@ -588,8 +1190,18 @@ impl error::Error for SaveError {
 mod tests {
     use super::*;
     use crate::core::string::Encoding;
-    use crate::id3v2::frames::CommentsFrame;
+    use crate::core::PaddingStrategy;
+    use crate::id3v2::frames::{
+        AttachedPictureFrame, CommentsFrame, CreditsFrame, EncryptionMethodFrame, FrameId,
+        GeneralObjectFrame, GroupIdFrame, SeekFrame, SignatureFrame, SignatureVerifier, TextFrame,
+    };
+    use crate::id3v2::tag::{
+        ImageEncodingRestriction, ImageSizeRestriction, Restrictions, TagSizeRestriction,
+        TextEncodingRestriction, TextSizeRestriction,
+    };
     use std::env;
+    use std::io::Cursor;
+    use std::time::Duration;
 
     #[test]
     fn read_id3v22() {
@@ -610,6 +1222,759 @@ mod tests {
         id3v22_ensure(&tag, Version::V23);
     }
 
+    #[test]
+    fn read_from_bytes() {
+        let path = env::var("CARGO_MANIFEST_DIR").unwrap() + "/res/test/v22.mp3";
+        let data = std::fs::read(&path).unwrap();
+
+        let tag = Tag::from_bytes(&data).unwrap();
+        id3v22_ensure(&tag, Version::V22);
+
+        // Reading from a stream should leave it at the end of the tag.
+        let mut src = &data[..];
+        let tag = Tag::read_from(&mut src).unwrap();
+        assert_eq!(src.len(), data.len() - tag.size() as usize - 10);
+
+        assert!(matches!(
+            Tag::from_bytes(b"fLaC"),
+            Err(ParseError::IoError(_))
+        ));
+        assert!(matches!(
+            Tag::from_bytes(&[0; 10]),
+            Err(ParseError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn write_to_stream() {
+        let mut dst = Cursor::new(b"\xFF\xFBaudio".to_vec());
+
+        let mut frame = TextFrame::new(FrameId::new(b"TIT2"));
+        frame.text = vec![String::from("Title")];
+
+        let mut tag = Tag::new();
+        tag.frames.insert(frame);
+        tag.write_to(&mut dst).unwrap();
+
+        let data = dst.get_ref().clone();
+        assert_eq!(data.len(), tag.render().unwrap().len() + 7);
+        assert!(data.ends_with(b"\xFF\xFBaudio"));
+        assert_eq!(
+            Tag::from_bytes(&data).unwrap().frames["TIT2"].to_string(),
+            "Title"
+        );
+
+        // An empty tag cannot shrink the stream, so it should become padding.
+        Tag::new().write_to(&mut dst).unwrap();
+
+        assert_eq!(dst.get_ref().len(), data.len());
+        assert!(dst.get_ref().ends_with(b"\xFF\xFBaudio"));
+        assert!(Tag::from_bytes(dst.get_ref()).unwrap().frames.is_empty());
+
+        // A tag with a footer cannot be padded, so the footer is only left out of the stream.
+        let mut frame = TextFrame::new(FrameId::new(b"TIT2"));
+        frame.text = vec![String::from("Ti")];
+
+        let mut tag = Tag::new();
+        tag.frames.insert(frame);
+        tag.set_footer(true);
+        tag.write_to(&mut dst).unwrap();
+
+        assert!(tag.has_footer());
+        assert_eq!(dst.get_ref().len(), data.len());
+        assert!(!Tag::from_bytes(dst.get_ref()).unwrap().has_footer());
+    }
+
+    #[test]
+    fn save_untagged() {
+        let path = env::temp_dir().join("musikr_id3v2_untagged.mp3");
+        std::fs::write(&path, b"\xFF\xFBaudio data").unwrap();
+
+        let mut frame = TextFrame::new(FrameId::new(b"TIT2"));
+        frame.text = vec![String::from("Title")];
+
+        let mut tag = Tag::new();
+        tag.frames.insert(frame);
+        tag.save(&path).unwrap();
+
+        let data = std::fs::read(&path).unwrap();
+        assert_eq!(data.len(), tag.size() as usize + 10 + 12);
+        assert!(data.ends_with(b"\xFF\xFBaudio data"));
+
+        // Removing the tag should leave only the audio data.
+        Tag::new().save(&path).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"\xFF\xFBaudio data");
+    }
+
+    #[test]
+    fn save_atomic() {
+        let path = env::temp_dir().join("musikr_id3v2_atomic.mp3");
+        std::fs::write(&path, b"\xFF\xFBaudio data").unwrap();
+
+        let mtime = std::fs::metadata(&path).unwrap().modified().unwrap() - Duration::from_secs(60);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+
+        let mut frame = TextFrame::new(FrameId::new(b"TIT2"));
+        frame.text = vec![String::from("Title")];
+
+        let mut tag = Tag::new();
+        tag.frames.insert(frame);
+
+        let options = SaveOptions {
+            preserve_mtime: true,
+            ..SaveOptions::atomic()
+        };
+
+        tag.save_with(&path, &options).unwrap();
+
+        let data = std::fs::read(&path).unwrap();
+        assert!(data.ends_with(b"\xFF\xFBaudio data"));
+        assert_eq!(
+            Tag::from_bytes(&data).unwrap().frames["TIT2"].to_string(),
+            "Title"
+        );
+        assert_eq!(std::fs::metadata(&path).unwrap().modified().unwrap(), mtime);
+
+        // No temporary files should be left behind.
+        let temp_files = std::fs::read_dir(env::temp_dir())
+            .unwrap()
+            .filter(|entry| {
+                let name = entry.as_ref().unwrap().file_name();
+                name.to_string_lossy()
+                    .starts_with(".musikr_id3v2_atomic.mp3")
+            })
+            .count();
+
+        assert_eq!(temp_files, 0);
+    }
+
+    #[test]
+    fn save_large() {
+        let path = env::temp_dir().join("musikr_id3v2_large.mp3");
+        let audio: Vec<u8> = (0..200_000).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&path, &audio).unwrap();
+
+        let mut frame = TextFrame::new(FrameId::new(b"TIT2"));
+        frame.text = vec![String::from("Title")];
+
+        let mut tag = Tag::new();
+        tag.frames.insert(frame);
+
+        // The audio data should be moved in multiple blocks.
+        let mut calls = 0;
+        let mut last = (0, 0);
+
+        tag.save_with_progress(&path, &SaveOptions::default(), |moved, total| {
+            calls += 1;
+            last = (moved, total)
+        })
+        .unwrap();
+
+        assert!(calls > 1);
+        assert_eq!(last, (audio.len() as u64, audio.len() as u64));
+
+        let data = std::fs::read(&path).unwrap();
+        assert_eq!(&data[tag.size() as usize + 10..], &audio[..]);
+
+        // Removing the tag should move the data back.
+        Tag::new().save(&path).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), audio);
+    }
+
+    #[test]
+    fn save_padding() {
+        let path = env::temp_dir().join("musikr_id3v2_padding.mp3");
+        std::fs::write(&path, b"\xFF\xFBaudio data").unwrap();
+
+        let mut frame = TextFrame::new(FrameId::new(b"TIT2"));
+        frame.text = vec![String::from("Title")];
+
+        let mut tag = Tag::new();
+        tag.frames.insert(frame);
+
+        let save = |tag: &mut Tag, padding| {
+            let options = SaveOptions {
+                padding,
+                ..SaveOptions::default()
+            };
+
+            tag.save_with(&path, &options).unwrap();
+            tag.size() as u64 + 10
+        };
+
+        let minimal = save(&mut tag, PaddingStrategy::Minimal);
+        assert_eq!(minimal, 10 + 10 + 6);
+
+        assert_eq!(save(&mut tag, PaddingStrategy::Block(4096)), 4096);
+
+        // The existing space should be reused if the tag and its padding fits in it.
+        assert_eq!(save(&mut tag, PaddingStrategy::Fixed(100)), 4096);
+        assert_eq!(save(&mut tag, PaddingStrategy::ReuseOnly), 4096);
+        assert_eq!(save(&mut tag, PaddingStrategy::Fixed(5000)), minimal + 5000);
+        assert_eq!(save(&mut tag, PaddingStrategy::Minimal), minimal);
+        assert_eq!(save(&mut tag, PaddingStrategy::Fixed(100)), minimal + 100);
+
+        let data = std::fs::read(&path).unwrap();
+        assert_eq!(data.len() as u64, minimal + 100 + 12);
+        assert!(data.ends_with(b"\xFF\xFBaudio data"));
+    }
+
+    #[test]
+    #[cfg(feature = "id3v2_compression")]
+    fn render_compressed() {
+        for version in [SaveVersion::V23, SaveVersion::V24] {
+            let frame = GeneralObjectFrame {
+                encoding: Encoding::Latin1,
+                desc: String::from("Data"),
+                data: vec![0x16; 4096],
+                ..Default::default()
+            };
+
+            let mut tag = Tag::with_version(version);
+            tag.frames.insert(frame);
+            tag.frames.flags_mut("GEOB:Data").unwrap().compressed = true;
+
+            let data = tag.render_padded(|_| 0).unwrap();
+            assert!(data.len() < 4096);
+
+            // ID3v2.3 only sets the compression flag, while ID3v2.4 also sets the data
+            // length indicator flag.
+            match version {
+                SaveVersion::V23 => assert_eq!(&data[18..20], b"\x00\x80"),
+                SaveVersion::V24 => assert_eq!(&data[18..20], b"\x00\x09"),
+            }
+
+            let new_tag = Tag::from_bytes(&data).unwrap();
+            let geob = new_tag.frames["GEOB:Data"]
+                .downcast::<GeneralObjectFrame>()
+                .unwrap();
+
+            assert_eq!(geob.data, vec![0x16; 4096]);
+        }
+    }
+
+    #[test]
+    fn render_unsync() {
+        for version in [SaveVersion::V23, SaveVersion::V24] {
+            let frame = GeneralObjectFrame {
+                encoding: Encoding::Latin1,
+                desc: String::from("Data"),
+                data: b"\xFF\xE0\x16\xFF\x00\xFF\xFF\xFB\xFF".to_vec(),
+                ..Default::default()
+            };
+
+            let mut tag = Tag::with_version(version);
+            tag.frames.insert(frame);
+            tag.set_unsync(true);
+
+            if version == SaveVersion::V24 {
+                tag.frames.flags_mut("GEOB:Data").unwrap().compressed = true;
+            }
+
+            let data = tag.render_padded(|_| 0).unwrap();
+            assert_eq!(data[5] & 0x80, 0x80);
+
+            // There should be no false syncs anywhere in the tag body.
+            for pair in data[10..].windows(2) {
+                assert!(!(pair[0] == 0xFF && pair[1] >= 0xE0));
+            }
+
+            let new_tag = Tag::from_bytes(&data).unwrap();
+            let geob = new_tag.frames["GEOB:Data"]
+                .downcast::<GeneralObjectFrame>()
+                .unwrap();
+
+            assert!(new_tag.is_unsync());
+            assert_eq!(geob.data, b"\xFF\xE0\x16\xFF\x00\xFF\xFF\xFB\xFF");
+        }
+    }
+
+    #[test]
+    fn render_crc() {
+        for version in [SaveVersion::V23, SaveVersion::V24] {
+            let mut frame = TextFrame::new(FrameId::new(b"TIT2"));
+            frame.encoding = Encoding::Latin1;
+            frame.text = vec![String::from("Title")];
+
+            let mut tag = Tag::with_version(version);
+            tag.frames.insert(frame);
+            tag.set_crc(true);
+
+            let mut data = tag.render_padded(|_| 16).unwrap();
+            let new_tag = Tag::from_bytes(&data).unwrap();
+
+            assert!(new_tag.extended_header.as_ref().unwrap().crc32.is_some());
+            assert!(matches!(new_tag.verify_crc(), Ok(())));
+
+            // Corrupt the title, which is just before the padding.
+            let pos = data.len() - 17;
+            data[pos] = b'!';
+
+            let new_tag = Tag::from_bytes(&data).unwrap();
+            assert_eq!(new_tag.frames["TIT2"].to_string(), "Titl!");
+            assert!(matches!(new_tag.verify_crc(), Err(ParseError::CrcMismatch)));
+        }
+    }
+
+    #[test]
+    fn save_read_only() {
+        let data = b"ID3\x03\x00\x00\x00\x00\x00\x10\
+                     TIT2\x00\x00\x00\x06\x20\x00\x00Title";
+
+        let mut tag = Tag::from_bytes(data).unwrap();
+        assert!(tag.frames.flags("TIT2").unwrap().read_only);
+
+        // Unmodified read-only frames can be written with their flags.
+        let mut cursor = Cursor::new(data.to_vec());
+        tag.write_to(&mut cursor).unwrap();
+        assert_eq!(&cursor.get_ref()[10..26], &data[10..]);
+
+        tag.frames["TIT2"].downcast_mut::<TextFrame>().unwrap().text = vec![String::from("New")];
+        assert!(matches!(
+            tag.write_to(&mut Cursor::new(data.to_vec())),
+            Err(SaveError::ReadOnly)
+        ));
+
+        let path = env::temp_dir().join("musikr_id3v2_read_only.mp3");
+        std::fs::write(&path, data).unwrap();
+
+        assert!(matches!(tag.save(&path), Err(SaveError::ReadOnly)));
+
+        let options = SaveOptions {
+            force: true,
+            ..SaveOptions::default()
+        };
+
+        tag.save_with(&path, &options).unwrap();
+
+        let new_tag = Tag::open(&path).unwrap();
+        assert_eq!(new_tag.frames["TIT2"].to_string(), "New");
+        assert!(new_tag.frames.flags("TIT2").unwrap().read_only);
+    }
+
+    #[test]
+    fn save_restricted() {
+        let mut title = TextFrame::new(FrameId::new(b"TIT2"));
+        title.encoding = Encoding::Utf16;
+        title.text = vec![String::from(
+            "A title that is longer than thirty characters",
+        )];
+
+        let gif = AttachedPictureFrame {
+            mime: String::from("image/gif"),
+            desc: String::from("GIF"),
+            picture: b"GIF89a".to_vec(),
+            ..Default::default()
+        };
+
+        let png = AttachedPictureFrame {
+            encoding: Encoding::Latin1,
+            mime: String::from("image/png"),
+            desc: String::from("PNG"),
+            picture: b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0DIHDR\x00\x00\x00\x20\x00\x00\x00\x20"
+                .to_vec(),
+            ..Default::default()
+        };
+
+        let comment = CommentsFrame {
+            encoding: Encoding::Utf16Le,
+            text: String::from("Comment"),
+            ..Default::default()
+        };
+
+        let mut tag = Tag::with_version(SaveVersion::V24);
+        tag.frames.insert(title);
+        tag.frames.insert(comment);
+        tag.frames.insert(gif);
+        tag.frames.insert(png);
+
+        tag.extended_header = Some(ExtendedHeader {
+            restrictions: Some(Restrictions {
+                tag_size: TagSizeRestriction::Max32Frames4Kb,
+                text_encoding: TextEncodingRestriction::Latin1OrUtf8,
+                text_size: TextSizeRestriction::LessThan30Chars,
+                image_encoding: ImageEncodingRestriction::OnlyPngOrJpeg,
+                image_size: ImageSizeRestriction::LessThan64x64,
+            }),
+            ..Default::default()
+        });
+
+        let path = env::temp_dir().join("musikr_id3v2_restricted.mp3");
+        std::fs::write(&path, [0x16; 16384]).unwrap();
+
+        // Restrictions are not checked by default.
+        let mut unchecked = tag.clone();
+        unchecked.save(&path).unwrap();
+
+        let options = SaveOptions {
+            restrictions: RestrictionMode::Check,
+            ..SaveOptions::default()
+        };
+
+        assert!(matches!(
+            tag.save_with(&path, &options),
+            Err(SaveError::Restricted)
+        ));
+
+        let options = SaveOptions {
+            restrictions: RestrictionMode::Enforce,
+            padding: PaddingStrategy::Fixed(8192),
+            ..SaveOptions::default()
+        };
+
+        tag.save_with(&path, &options).unwrap();
+
+        let new_tag = Tag::open(&path).unwrap();
+        let title = new_tag.frames["TIT2"].downcast::<TextFrame>().unwrap();
+
+        assert_eq!(title.encoding, Encoding::Utf8);
+        assert_eq!(title.text, ["A title that is longer than th"]);
+
+        let comment = new_tag.frames["COMM::xxx"]
+            .downcast::<CommentsFrame>()
+            .unwrap();
+        assert_eq!(comment.encoding, Encoding::Utf8);
+        assert!(!new_tag.frames.contains_key("APIC:GIF"));
+        assert!(new_tag.frames.contains_key("APIC:PNG"));
+        assert!(new_tag.size() <= 4096);
+    }
+
+    #[test]
+    fn read_groups() {
+        let data = b"ID3\x04\x00\x00\x00\x00\x00\x2F\
+                     GRID\x00\x00\x00\x0A\x00\x00example\0\x80\x16\
+                     TIT2\x00\x00\x00\x07\x00\x40\x80\x00Title\
+                     TALB\x00\x00\x00\x06\x00\x00\x00Album";
+
+        let mut tag = Tag::from_bytes(data).unwrap();
+
+        let grid = tag.frames.group_id(0x80).unwrap();
+        assert_eq!(grid.owner, "example");
+        assert_eq!(grid.data, b"\x16");
+
+        let group = tag.frames.get_group(0x80);
+        assert_eq!(group.len(), 1);
+        assert_eq!(group[0].id(), b"TIT2");
+
+        // The group should survive being edited.
+        tag.frames["TIT2"].downcast_mut::<TextFrame>().unwrap().text = vec![String::from("New")];
+
+        let new_tag = Tag::from_bytes(&tag.render().unwrap()).unwrap();
+        assert_eq!(new_tag.frames.get_group(0x80)[0].to_string(), "New");
+        assert!(new_tag.frames.get_group(0x81).is_empty());
+    }
+
+    #[derive(Debug)]
+    struct XorDecryptor;
+
+    impl FrameDecryptor for XorDecryptor {
+        fn decrypt(&self, method: &EncryptionMethodFrame, data: &[u8]) -> Option<Vec<u8>> {
+            let key = *method.data.first()?;
+            Some(data.iter().map(|byte| byte ^ key).collect())
+        }
+
+        fn encrypt(&self, method: &EncryptionMethodFrame, data: &[u8]) -> Option<Vec<u8>> {
+            self.decrypt(method, data)
+        }
+    }
+
+    #[test]
+    fn decrypt_frames() {
+        decrypt_round_trip(false);
+    }
+
+    #[test]
+    #[cfg(feature = "id3v2_compression")]
+    fn decrypt_compressed_frames() {
+        decrypt_round_trip(true);
+    }
+
+    fn decrypt_round_trip(compressed: bool) {
+        for version in [SaveVersion::V23, SaveVersion::V24] {
+            let mut tag = Tag::with_version(version);
+
+            tag.frames.insert(EncryptionMethodFrame {
+                owner: String::from("example"),
+                method: 0x81,
+                data: vec![0x16],
+            });
+
+            let mut frame = TextFrame::new(FrameId::new(b"TIT2"));
+            frame.encoding = Encoding::Latin1;
+            frame.text = vec![String::from("Title")];
+            tag.frames.insert(frame);
+
+            let flags = tag.frames.flags_mut("TIT2").unwrap();
+            flags.encryption = Some(0x81);
+            flags.group = Some(0x80);
+            flags.compressed = compressed;
+
+            // Encrypted frames cannot be written without a decryptor.
+            assert!(matches!(tag.render(), Err(SaveError::EncryptionFailed)));
+
+            tag.decrypt(XorDecryptor);
+            let data = tag.render().unwrap();
+
+            // Encrypted frames are kept exactly as they were until they are decrypted.
+            let mut new_tag = Tag::from_bytes(&data).unwrap();
+            assert!(!new_tag.frames.contains_key("TIT2"));
+            assert_eq!(new_tag.unknown_frames.frames()[0].id(), b"TIT2");
+            assert_eq!(new_tag.clone().render().unwrap(), data);
+
+            new_tag.decrypt(XorDecryptor);
+            assert!(new_tag.unknown_frames.frames().is_empty());
+            assert_eq!(new_tag.frames["TIT2"].to_string(), "Title");
+
+            let flags = new_tag.frames.flags("TIT2").unwrap();
+            assert_eq!(flags.encryption, Some(0x81));
+            assert_eq!(flags.group, Some(0x80));
+            assert_eq!(flags.compressed, compressed);
+
+            assert_eq!(new_tag.render().unwrap(), data);
+        }
+    }
+
+    /// Signs groups with a CRC-32 of the data, keyed with the group registration data.
+    struct KeyedVerifier;
+
+    impl KeyedVerifier {
+        fn sign(group: &GroupIdFrame, data: &[u8]) -> Vec<u8> {
+            let keyed = [group.data.as_slice(), data].concat();
+            crate::id3v2::tag::crc32(&keyed).to_be_bytes().to_vec()
+        }
+    }
+
+    impl SignatureVerifier for KeyedVerifier {
+        fn verify(&self, sign: &SignatureFrame, group: Option<&GroupIdFrame>, data: &[u8]) -> bool {
+            group.is_some_and(|group| Self::sign(group, data) == sign.signature)
+        }
+    }
+
+    #[test]
+    fn verify_signed_group() {
+        let grid = GroupIdFrame {
+            owner: String::from("example"),
+            symbol: 0x80,
+            data: b"key".to_vec(),
+        };
+
+        let mut tag = Tag::with_version(SaveVersion::V24);
+
+        let mut title = TextFrame::new(FrameId::new(b"TIT2"));
+        title.text = vec![String::from("Title")];
+        tag.frames.insert(title);
+
+        let mut tipl = CreditsFrame::new(FrameId::new(b"TIPL"));
+        tipl.people
+            .insert(String::from("Producer"), String::from("Person"));
+        tag.frames.insert(tipl);
+        tag.frames.flags_mut("TIPL").unwrap().group = Some(0x80);
+
+        tag.frames.insert(SignatureFrame {
+            symbol: 0x80,
+            signature: KeyedVerifier::sign(&grid, &tag.frames.signed_data(0x80).unwrap()),
+        });
+
+        tag.frames.insert(grid);
+
+        // Only frames in the group are signed.
+        let mut new_tag = Tag::from_bytes(&tag.render().unwrap()).unwrap();
+        new_tag.frames["TIT2"]
+            .downcast_mut::<TextFrame>()
+            .unwrap()
+            .text = vec![String::from("New")];
+
+        assert!(new_tag.frames.verify_group(0x80, &KeyedVerifier));
+        assert!(!new_tag.frames.verify_group(0x81, &KeyedVerifier));
+
+        let tipl = new_tag.frames["TIPL"]
+            .downcast_mut::<CreditsFrame>()
+            .unwrap();
+        tipl.people
+            .insert(String::from("Producer"), String::from("Someone else"));
+
+        assert!(!new_tag.frames.verify_group(0x80, &KeyedVerifier));
+
+        // Encrypted frames cannot be rendered without an encryptor, so they cannot be verified.
+        new_tag.frames.flags_mut("TIPL").unwrap().encryption = Some(0x81);
+
+        assert!(matches!(
+            new_tag.frames.signed_data(0x80),
+            Err(SaveError::EncryptionFailed)
+        ));
+        assert!(!new_tag.frames.verify_group(0x80, &KeyedVerifier));
+    }
+
+    #[test]
+    fn render_footer() {
+        let mut frame = TextFrame::new(FrameId::new(b"TIT2"));
+        frame.text = vec![String::from("Title")];
+
+        let mut tag = Tag::new();
+        tag.frames.insert(frame);
+        tag.set_footer(true);
+
+        // Tags with footers should not be padded.
+        let data = tag.render().unwrap();
+        assert_eq!(data.len(), 10 + tag.size() as usize + 10);
+        assert_eq!(&data[data.len() - 10..data.len() - 7], b"3DI");
+        assert_eq!(data[..10][3..], data[data.len() - 10..][3..]);
+
+        let new_tag = Tag::from_bytes(&data).unwrap();
+        assert!(new_tag.has_footer());
+        assert_eq!(new_tag.frames["TIT2"].to_string(), "Title");
+
+        // A footer that does not match the header is malformed.
+        let mut bad = data.clone();
+        let len = bad.len();
+        bad[len - 1] ^= 1;
+        assert!(matches!(
+            Tag::from_bytes(&bad),
+            Err(ParseError::MalformedData)
+        ));
+
+        // ID3v2.3 tags cannot have footers.
+        let mut tag = Tag::with_version(SaveVersion::V23);
+        tag.frames.insert(TextFrame::new(FrameId::new(b"TIT2")));
+        tag.set_footer(true);
+        tag.render().unwrap();
+        assert!(!tag.has_footer());
+    }
+
+    #[test]
+    fn read_appended() {
+        let mut frame = TextFrame::new(FrameId::new(b"TIT2"));
+        frame.text = vec![String::from("Title")];
+
+        let mut tag = Tag::new();
+        tag.frames.insert(frame);
+        tag.set_footer(true);
+
+        let mut data = b"\xFF\xFBaudio data".to_vec();
+        data.extend(tag.render().unwrap());
+
+        // The tag should be found before an ID3v1 tag.
+        data.extend(b"TAG");
+        data.resize(data.len() + 125, 0);
+
+        let path = env::temp_dir().join("musikr_id3v2_appended.mp3");
+        std::fs::write(&path, &data).unwrap();
+
+        let tag = Tag::open(&path).unwrap();
+        assert!(tag.has_footer());
+        assert_eq!(tag.frames["TIT2"].to_string(), "Title");
+
+        // Without a footer, the tag cannot be found.
+        std::fs::write(&path, b"\xFF\xFBaudio data").unwrap();
+        assert!(matches!(Tag::open(&path), Err(ParseError::NotFound)));
+    }
+
+    #[test]
+    fn save_appended() {
+        let mut frame = TextFrame::new(FrameId::new(b"TIT2"));
+        frame.text = vec![String::from("Title")];
+
+        let mut tag = Tag::new();
+        tag.frames.insert(frame);
+        tag.set_footer(true);
+
+        let mut data = b"\xFF\xFBaudio data".to_vec();
+        data.extend(tag.render().unwrap());
+        data.extend(b"TAG");
+        data.resize(data.len() + 125, 0);
+
+        let path = env::temp_dir().join("musikr_id3v2_save_appended.mp3");
+        std::fs::write(&path, &data).unwrap();
+
+        // The appended tag is replaced by the tag at the start of the file.
+        let mut tag = Tag::open(&path).unwrap();
+        tag.frames["TIT2"].downcast_mut::<TextFrame>().unwrap().text = vec![String::from("New")];
+        tag.save(&path).unwrap();
+
+        let data = std::fs::read(&path).unwrap();
+        let end = 10 + tag.size() as usize + 10;
+
+        assert!(data.starts_with(b"ID3"));
+        assert_eq!(&data[end..end + 12], b"\xFF\xFBaudio data");
+        assert_eq!(&data[end + 12..end + 15], b"TAG");
+        assert_eq!(data.len(), end + 12 + 128);
+
+        let new_tag = Tag::open(&path).unwrap();
+        assert_eq!(new_tag.frames["TIT2"].to_string(), "New");
+    }
+
+    #[test]
+    fn save_footer() {
+        let path = env::temp_dir().join("musikr_id3v2_footer.mp3");
+        std::fs::write(&path, b"\xFF\xFBaudio data").unwrap();
+
+        let mut frame = TextFrame::new(FrameId::new(b"TIT2"));
+        frame.text = vec![String::from("Title")];
+
+        let mut tag = Tag::new();
+        tag.frames.insert(frame);
+        tag.set_footer(true);
+        tag.save(&path).unwrap();
+
+        // Saving again should replace the whole tag, including the footer.
+        let mut tag = Tag::open(&path).unwrap();
+        tag.save(&path).unwrap();
+
+        let data = std::fs::read(&path).unwrap();
+        assert_eq!(data.len(), 10 + tag.size() as usize + 10 + 12);
+        assert!(data.ends_with(b"\xFF\xFBaudio data"));
+    }
+
+    #[test]
+    fn read_seek() {
+        let path = env::temp_dir().join("musikr_id3v2_seek.mp3");
+
+        let make_tag = |seek: Option<u32>, is_update: bool| {
+            let mut tag = Tag::new();
+
+            if let Some(offset) = seek {
+                tag.frames.insert(text_frame! { b"TIT2", ["First"] });
+                tag.frames.insert(text_frame! { b"TPE1", ["Artist"] });
+                tag.frames.insert(SeekFrame { offset });
+            } else {
+                tag.frames.insert(text_frame! { b"TIT2", ["Second"] });
+                tag.frames.insert(text_frame! { b"TALB", ["Album"] });
+            }
+
+            if is_update {
+                tag.extended_header = Some(ExtendedHeader {
+                    is_update: true,
+                    ..Default::default()
+                });
+            }
+
+            tag.render().unwrap()
+        };
+
+        for is_update in [true, false] {
+            let mut data = make_tag(Some(5), false);
+            data.extend(b"audio");
+            data.extend(make_tag(None, is_update));
+            std::fs::write(&path, &data).unwrap();
+
+            let tag = Tag::open(&path).unwrap();
+            assert_eq!(tag.frames["TPE1"].to_string(), "Artist");
+            assert_eq!(tag.frames["TALB"].to_string(), "Album");
+            assert!(!tag.frames.contains_key("SEEK"));
+
+            if is_update {
+                assert_eq!(tag.frames["TIT2"].to_string(), "Second");
+            } else {
+                assert_eq!(tag.frames["TIT2"].to_string(), "First, Second");
+            }
+        }
+    }
+
     fn id3v22_ensure(tag: &Tag, version: Version) {
         assert_eq!(tag.version(), version);
         assert_eq!(tag.frames["TIT2"].to_string(), "cosmic american");
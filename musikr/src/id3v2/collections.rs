@@ -1,7 +1,11 @@
 //! Frame collection and management.
 
-use crate::id3v2::frames::{self, CreditsFrame, Frame, TextFrame, UnknownFrame, UserTextFrame};
+use crate::id3v2::frames::{
+    self, CreditsFrame, EncryptionMethodFrame, Frame, FrameDecryptor, FrameFlags, GroupIdFrame,
+    SignatureFrame, SignatureVerifier, TextFrame, UnknownFrame, UserTextFrame,
+};
 use crate::id3v2::tag::{TagHeader, Version};
+use crate::id3v2::{SaveError, SaveResult};
 use log::{info, warn};
 use std::cmp::Ordering;
 use std::collections::btree_map::{
@@ -49,6 +53,8 @@ use std::ops::{Deref, DerefMut, Index, IndexMut};
 #[derive(Debug, Clone, Default)]
 pub struct FrameMap {
     map: BTreeMap<String, Box<dyn Frame>>,
+    flags: BTreeMap<String, FrameFlags>,
+    read_only: BTreeMap<String, Vec<u8>>,
 }
 
 impl FrameMap {
@@ -177,6 +183,95 @@ impl FrameMap {
         keys.iter().map(|key| self.remove(key).unwrap()).collect()
     }
 
+    /// Returns a list of references to all frames that belong to the group with the
+    /// specified symbol.
+    pub fn get_group(&self, symbol: u8) -> Vec<&dyn Frame> {
+        self.iter()
+            .filter(|(key, _)| self.flags(key).unwrap().group == Some(symbol))
+            .map(|(_, frame)| frame)
+            .collect()
+    }
+
+    /// Returns the [`GroupIdFrame`](crate::id3v2::frames::GroupIdFrame) that registers
+    /// the specified group symbol, if present.
+    pub fn group_id(&self, symbol: u8) -> Option<&GroupIdFrame> {
+        self.get_all(b"GRID")
+            .into_iter()
+            .filter_map(|frame| frame.downcast::<GroupIdFrame>())
+            .find(|grid| grid.symbol == symbol)
+    }
+
+    /// Returns the data signed by the [`SignatureFrame`](crate::id3v2::frames::SignatureFrame)
+    /// of the group with the specified symbol.
+    ///
+    /// This is every frame in the group, excluding signatures, rendered as an ID3v2.4 frame
+    /// in the order of their keys.
+    ///
+    /// # Errors
+    ///
+    /// If a frame in the group cannot be rendered, such as a frame marked as encrypted, an
+    /// error will be returned.
+    pub fn signed_data(&self, symbol: u8) -> SaveResult<Vec<u8>> {
+        let header = TagHeader::with_version(Version::V24);
+        let mut data = Vec::new();
+
+        for (key, frame) in self.iter() {
+            let flags = self.flags(key).unwrap();
+
+            if flags.group != Some(symbol) || frame.id() == b"SIGN" {
+                continue;
+            }
+
+            data.extend(frames::render(&header, frame, &flags, None)?);
+        }
+
+        Ok(data)
+    }
+
+    /// Verifies the group with the specified symbol against its
+    /// [`SignatureFrame`](crate::id3v2::frames::SignatureFrame)s with `verifier`.
+    ///
+    /// Returns `false` if the group is not signed, if the signed data cannot be rendered, or if
+    /// any signature does not match.
+    pub fn verify_group(&self, symbol: u8, verifier: &dyn SignatureVerifier) -> bool {
+        let signatures: Vec<&SignatureFrame> = self
+            .get_all(b"SIGN")
+            .into_iter()
+            .filter_map(|frame| frame.downcast::<SignatureFrame>())
+            .filter(|sign| sign.symbol == symbol)
+            .collect();
+
+        if signatures.is_empty() {
+            warn!("group {:#x} is not signed", symbol);
+            return false;
+        }
+
+        let data = match self.signed_data(symbol) {
+            Ok(data) => data,
+            Err(err) => {
+                warn!(
+                    "could not render group {:#x} for verification: {}",
+                    symbol, err
+                );
+                return false;
+            }
+        };
+        let group = self.group_id(symbol);
+
+        signatures
+            .into_iter()
+            .all(|sign| verifier.verify(sign, group, &data))
+    }
+
+    /// Returns the [`EncryptionMethodFrame`](crate::id3v2::frames::EncryptionMethodFrame) that
+    /// registers the specified method symbol, if present.
+    pub fn encryption_method(&self, method: u8) -> Option<&EncryptionMethodFrame> {
+        self.get_all(b"ENCR")
+            .into_iter()
+            .filter_map(|frame| frame.downcast::<EncryptionMethodFrame>())
+            .find(|encr| encr.method == method)
+    }
+
     /// Returns true if the map contains a value for the specified key.
     pub fn contains_key(&self, key: &str) -> bool {
         self.map.contains_key(key)
@@ -210,7 +305,102 @@ impl FrameMap {
     where
         F: FnMut(&String, &mut dyn Frame) -> bool,
     {
-        self.map.retain(|k, v| keep(k, v.deref_mut()))
+        self.map.retain(|k, v| keep(k, v.deref_mut()));
+
+        let map = &self.map;
+        self.flags.retain(|key, _| map.contains_key(key));
+        self.read_only.retain(|key, _| map.contains_key(key));
+    }
+
+    /// Removes a key from the map, returning the value at the key if the key
+    /// was previously in the map.
+    pub fn remove(&mut self, key: &str) -> Option<Box<dyn Frame>> {
+        self.flags.remove(key);
+        self.read_only.remove(key);
+        self.map.remove(key)
+    }
+
+    /// Clears the map, removing all elements.
+    pub fn clear(&mut self) {
+        self.flags.clear();
+        self.read_only.clear();
+        self.map.clear()
+    }
+
+    /// Returns the [`FrameFlags`](crate::id3v2::frames::FrameFlags) of the frame
+    /// corresponding to the key.
+    pub fn flags(&self, key: &str) -> Option<FrameFlags> {
+        if !self.map.contains_key(key) {
+            return None;
+        }
+
+        Some(self.flags.get(key).copied().unwrap_or_default())
+    }
+
+    /// Returns a mutable reference to the [`FrameFlags`](crate::id3v2::frames::FrameFlags)
+    /// of the frame corresponding to the key.
+    ///
+    /// Flags are kept until the frame is removed, even if it's replaced with
+    /// [`insert`](FrameMap::insert).
+    pub fn flags_mut(&mut self, key: &str) -> Option<&mut FrameFlags> {
+        if !self.map.contains_key(key) {
+            return None;
+        }
+
+        Some(self.flags.entry(key.to_string()).or_default())
+    }
+
+    /// Adds a parsed frame with its flags. If the frame is read-only, its original contents
+    /// are kept so that modifications can be detected.
+    pub(crate) fn add_parsed(
+        &mut self,
+        header: &TagHeader,
+        frame: Box<dyn Frame>,
+        flags: FrameFlags,
+    ) {
+        let key = frame.key();
+
+        if !self.map.contains_key(&key) {
+            if flags.read_only {
+                self.read_only.insert(key.clone(), frame.render(header));
+            }
+
+            if flags != FrameFlags::default() {
+                self.flags.insert(key, flags);
+            }
+        }
+
+        self.add_boxed(frame)
+    }
+
+    /// Moves all frames and their flags from `other` into this map. Frames that are
+    /// already present will be replaced if `replace` is true.
+    pub(crate) fn merge(&mut self, mut other: FrameMap, replace: bool) {
+        for (key, frame) in other.map {
+            if self.map.contains_key(&key) && !replace {
+                self.add_boxed(frame);
+                continue;
+            }
+
+            self.flags.remove(&key);
+            self.read_only.remove(&key);
+            self.flags.extend(other.flags.remove_entry(&key));
+            self.read_only.extend(other.read_only.remove_entry(&key));
+            self.insert_boxed(frame);
+        }
+    }
+
+    /// Returns the keys of all read-only frames that have been modified since they
+    /// were parsed.
+    pub(crate) fn modified_read_only(&self, header: &TagHeader) -> Vec<&str> {
+        self.read_only
+            .iter()
+            .filter(|(key, orig)| {
+                self.flags(key).is_some_and(|flags| flags.read_only)
+                    && self.map[key.as_str()].render(header) != **orig
+            })
+            .map(|(key, _)| key.as_str())
+            .collect()
     }
 
     /// Returns a reference to the inner [`BTreeMap`](std::collections::BTreeMap) for this instance.
@@ -218,17 +408,30 @@ impl FrameMap {
         &self.map
     }
 
-    pub(crate) fn render(&self, header: &TagHeader) -> impl Iterator<Item = u8> + '_ {
+    pub(crate) fn render<'a>(
+        &'a self,
+        header: &TagHeader,
+        decryptor: Option<&dyn FrameDecryptor>,
+    ) -> SaveResult<impl Iterator<Item = u8> + 'a> {
         const PRIORITY: &[&[u8; 4]] = &[
             b"TIT2", b"TPE1", b"TALB", b"TRCK", b"TPOS", b"TDRC", b"TCON",
         ];
 
         let mut frame_pairs: Vec<(&dyn Frame, Vec<u8>)> = Vec::new();
 
-        for frame in self.values() {
+        for (key, frame) in self.iter() {
             if !frame.is_empty() {
-                match frames::render(header, frame.deref()) {
+                let flags = self.flags(key).unwrap();
+
+                let encryptor = flags
+                    .encryption
+                    .and_then(|method| Some((decryptor?, self.encryption_method(method)?)));
+
+                // Frames that should be encrypted must never be written in plaintext, so
+                // failing to encrypt one fails the entire render.
+                match frames::render(header, frame, &flags, encryptor) {
                     Ok(data) => frame_pairs.push((frame, data)),
+                    Err(SaveError::EncryptionFailed) => return Err(SaveError::EncryptionFailed),
                     Err(_) => warn!("could not render frame {}", frame.key()),
                 }
             } else {
@@ -259,17 +462,15 @@ impl FrameMap {
             }
         });
 
-        frame_pairs
+        Ok(frame_pairs
             .into_iter()
-            .flat_map(|(_, data)| data.into_iter())
+            .flat_map(|(_, data)| data.into_iter()))
     }
 
     delegate::delegate! {
         to self.map {
-            /// Clears the map, removing all elements.
-            pub fn clear(&mut self);
             /// Gets an iterator over the keys of the map, in sorted order.
-            pub fn keys(&self) -> Keys<String, Box<dyn Frame>>;
+            pub fn keys(&self) -> Keys<'_, String, Box<dyn Frame>>;
             /// Returns the number of elements in the map.
             pub fn len(&self) -> usize;
             /// Returns true if the map contains no elements.
@@ -282,9 +483,6 @@ impl FrameMap {
             /// The map cannot be used after calling this. The iterator element type is
             /// the frame instances.
             pub fn into_values(self) -> IntoValues<String, Box<dyn Frame>>;
-            /// Removes a key from the map, returning the value at the key if the key
-            /// was previously in the map.
-            pub fn remove(&mut self, key: &str) -> Option<Box<dyn Frame>>;
         }
     }
 }
@@ -338,7 +536,11 @@ impl Extend<(String, Box<dyn Frame>)> for FrameMap {
 
 impl From<BTreeMap<String, Box<dyn Frame>>> for FrameMap {
     fn from(other: BTreeMap<String, Box<dyn Frame>>) -> Self {
-        Self { map: other }
+        Self {
+            map: other,
+            flags: BTreeMap::new(),
+            read_only: BTreeMap::new(),
+        }
     }
 }
 
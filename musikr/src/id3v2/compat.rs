@@ -2,7 +2,7 @@ use crate::id3v2::frames::{
     self, ChapterFrame, CreditsFrame, Frame, FrameId, TableOfContentsFrame, TextFrame,
 };
 use crate::id3v2::{FrameMap, ParseError, ParseResult};
-use log::info;
+use log::{info, warn};
 use std::str::Chars;
 
 static V2_V3_CONV: &[(&[u8; 3], &[u8; 4])] = &[
@@ -157,7 +157,12 @@ pub fn to_v3(frames: &mut FrameMap) {
 
     // Drop the remaining frames with no analogue.
     frames.retain(|_, frame| {
-        if V3_UNSUPPORTED.contains(&frame.id().as_ref()) {
+        if frame.id() == b"ASPI" {
+            // Unlike the other frames, the index describes the audio, and regenerating it
+            // requires parsing the MPEG audio, which musikr does not do.
+            warn!("dropping ASPI, which cannot be regenerated if the tag is upgraded again");
+            false
+        } else if V3_UNSUPPORTED.contains(&frame.id().as_ref()) {
             info!("dropping ID3v2.3-incompatible frame {}", frame.id());
             false
         } else {
@@ -11,7 +11,7 @@
 //!
 //! - Trait objects make it easy to implement new frame specifications, even outside of musikr.
 //! - There are numerous frames, each with their own distinct structure. This would make a large
-//! enum extremely cumbersome to use.
+//!   enum extremely cumbersome to use.
 //!
 //! Musikr attempts to alleviate the shortcomings of trait objects by implementing
 //! methods to downcast frames to a concrete type. For example, a `dyn Frame` instance
@@ -153,25 +153,35 @@ pub mod chapters;
 mod encoding;
 pub mod events;
 pub mod file;
+pub mod link;
 pub mod lyrics;
 pub mod owner;
+pub mod registration;
+pub mod seek;
+pub mod signature;
 pub mod stats;
 pub mod text;
 pub mod url;
 
 pub use audio::v23::{EqualizationFrame, RelativeVolumeFrame};
 pub use audio::v24::{EqualizationFrame2, RelativeVolumeFrame2};
+pub use audio::{AudioEncryptionFrame, BufferSizeFrame, ReverbFrame};
 pub use bin::{FileIdFrame, MusicCdIdFrame, PodcastFrame, PrivateFrame};
 pub use chapters::{ChapterFrame, TableOfContentsFrame};
-pub use events::{EventTimingCodesFrame, SyncedTempoCodesFrame};
+pub use events::{EventTimingCodesFrame, PositionSyncFrame, SyncedTempoCodesFrame};
 pub use file::{AttachedPictureFrame, GeneralObjectFrame};
+pub use link::LinkFrame;
 pub use lyrics::{SyncedLyricsFrame, UnsyncLyricsFrame};
 pub use owner::{CommercialFrame, OwnershipFrame, TermsOfUseFrame};
+pub use registration::{EncryptionMethodFrame, GroupIdFrame};
+pub use seek::{MpegLookupFrame, MpegReference, SeekFrame, SeekPointIndexFrame};
+pub use signature::SignatureFrame;
 pub use stats::{PlayCounterFrame, PopularimeterFrame};
 pub use text::{CommentsFrame, CreditsFrame, TextFrame, UserTextFrame};
 pub use url::{UrlFrame, UserUrlFrame};
 
 use crate::core::io::BufStream;
+use crate::id3v2::collections::FrameMap;
 use crate::id3v2::tag::{TagHeader, Version};
 use crate::id3v2::{compat, syncdata, ParseError, ParseResult, SaveError, SaveResult};
 
@@ -196,13 +206,13 @@ pub trait Frame: Display + Debug + AsAny + DynClone {
     ///
     /// # Custom Frame Considerations
     /// - Certain Frame ID namespaces are reserved. For example, all frames beginning with `T` are
-    /// reserved for text frames, while all frames beginning with `W` are reserved for URL frames.
-    /// Keep this in mind depending on the frame you are intending to create.
+    ///   reserved for text frames, while all frames beginning with `W` are reserved for URL frames.
+    ///   Keep this in mind depending on the frame you are intending to create.
     /// - Generally, all Frame IDs beginning with `X`, `Y`, and `Z` are free for anyone to use, while
-    /// the remaining Frame IDs are reserved by the specification. This is reccomended, but not required
-    /// by musikr.
+    ///   the remaining Frame IDs are reserved by the specification. This is reccomended, but not required
+    ///   by musikr.
     /// - Do not use a Frame ID already used by the standard. This may result in errors in musikr or other
-    /// programs.
+    ///   programs.
     fn id(&self) -> FrameId;
 
     /// Returns the unique key of this frame.
@@ -397,6 +407,65 @@ impl UnknownFrame {
     }
 }
 
+/// The flags of a frame in a [`FrameMap`](crate::id3v2::collections::FrameMap).
+///
+/// These are parsed from the frame header and written back when the tag is saved. Flags
+/// that only describe how the frame data is encoded, such as unsynchronization or data
+/// length indicators, are handled automatically. ID3v2.2 frames have no flags, so they
+/// will always have the default flags.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameFlags {
+    /// Whether this frame should be discarded by software that does not know it when the
+    /// tag is altered.
+    pub tag_alter_discard: bool,
+    /// Whether this frame should be discarded by software that does not know it when the
+    /// audio data is altered.
+    pub file_alter_discard: bool,
+    /// Whether this frame is intended to be read-only. [`Tag::save`](crate::id3v2::Tag::save)
+    /// will refuse to write read-only frames that were modified unless forced.
+    pub read_only: bool,
+    /// The group symbol of this frame, if it belongs to a group. The group is registered
+    /// by a [`GroupIdFrame`](GroupIdFrame) with the same symbol.
+    pub group: Option<u8>,
+    /// Whether this frame will be compressed with zlib when written. Frames will be
+    /// written uncompressed if the `id3v2_compression` feature is disabled.
+    pub compressed: bool,
+    /// The method symbol this frame is encrypted with, if it's encrypted. The method is
+    /// registered by an [`EncryptionMethodFrame`](EncryptionMethodFrame) with the same symbol.
+    pub encryption: Option<u8>,
+}
+
+impl FrameFlags {
+    fn parse(flags: u16, version: Version) -> Self {
+        let status = match version {
+            Version::V24 => flags << 1,
+            _ => flags,
+        };
+
+        Self {
+            tag_alter_discard: status & 0x8000 != 0,
+            file_alter_discard: status & 0x4000 != 0,
+            read_only: status & 0x2000 != 0,
+            group: None,
+            compressed: false,
+            encryption: None,
+        }
+    }
+
+    fn render_status(&self, version: Version) -> u16 {
+        let mut status = 0;
+        status |= u16::from(self.tag_alter_discard) * 0x8000;
+        status |= u16::from(self.file_alter_discard) * 0x4000;
+        status |= u16::from(self.read_only) * 0x2000;
+
+        // ID3v2.4 moved the status flags down by one bit.
+        match version {
+            Version::V24 => status >> 1,
+            _ => status,
+        }
+    }
+}
+
 
 /// A representation of an ID3v2.3 or ID3v2.4 Frame ID.
 ///
@@ -457,7 +526,7 @@ impl FrameId {
     pub(crate) fn validate(frame_id: &[u8]) -> bool {
         for ch in frame_id {
             // Valid frame IDs can only contain uppercase ASCII chars and numbers.
-            if !(b'A'..=b'Z').contains(ch) && !(b'0'..=b'9').contains(ch) {
+            if !ch.is_ascii_uppercase() && !ch.is_ascii_digit() {
                 return false;
             }
         }
@@ -494,7 +563,7 @@ impl FromStr for FrameId {
         let mut id = [0; 4];
 
         for (i, ch) in s.chars().enumerate() {
-            if !('A'..='Z').contains(&ch) && !('0'..='9').contains(&ch) {
+            if !ch.is_ascii_uppercase() && !ch.is_ascii_digit() {
                 return Err(FrameIdError(()));
             }
 
@@ -542,6 +611,38 @@ pub trait FrameParser {
     ) -> ParseResult<FrameResult<'a>>;
 }
 
+/// Decrypts and encrypts frame data.
+///
+/// Encrypted frames are marked with a method symbol that is registered by an
+/// [`EncryptionMethodFrame`](EncryptionMethodFrame). Since encryption is vendor-specific,
+/// musikr does not implement any methods, and instead hands the frame data to a
+/// `FrameDecryptor` alongside the registration of the method. A `FrameDecryptor` is used with
+/// [`Tag::decrypt`](crate::id3v2::Tag::decrypt), which will also re-encrypt the frames when
+/// the tag is saved.
+pub trait FrameDecryptor: Debug {
+    /// Decrypts `data` with the method registered by `method`, returning `None` if the data
+    /// could not be decrypted.
+    fn decrypt(&self, method: &EncryptionMethodFrame, data: &[u8]) -> Option<Vec<u8>>;
+
+    /// Encrypts `data` with the method registered by `method`, returning `None` if the data
+    /// could not be encrypted.
+    fn encrypt(&self, method: &EncryptionMethodFrame, data: &[u8]) -> Option<Vec<u8>>;
+}
+
+/// Verifies the signatures of frame groups.
+///
+/// A [`SignatureFrame`](SignatureFrame) signs all frames in the group with its symbol, which
+/// is usually registered by a [`GroupIdFrame`](GroupIdFrame). Like encryption, signing is
+/// vendor-specific, so musikr does not implement any methods. A `SignatureVerifier` is used
+/// with [`FrameMap::verify_group`](crate::id3v2::collections::FrameMap::verify_group).
+pub trait SignatureVerifier {
+    /// Returns whether `signature` is a valid signature of `data` for the group registered by
+    /// `group`. `data` is the same as
+    /// [`FrameMap::signed_data`](crate::id3v2::collections::FrameMap::signed_data).
+    fn verify(&self, signature: &SignatureFrame, group: Option<&GroupIdFrame>, data: &[u8])
+        -> bool;
+}
+
 /// Frame data that has not been fully parsed.
 ///
 /// The data in this enum has already had the header parsed and the frame body fully decoded,
@@ -684,11 +785,11 @@ impl DefaultFrameParser {
             // Equalization 2 [Frames 4.12]
             b"EQU2" => frame!(EqualizationFrame2::parse(&mut stream)?),
             // Signature Frame [Frames 4.28]
-            // b"SIGN" => todo!(),
+            b"SIGN" => frame!(SignatureFrame::parse(&mut stream)?),
             // Seek frame [Frames 4.27]
-            // b"SEEK" => todo!(),
+            b"SEEK" => frame!(SeekFrame::parse(&mut stream)?),
             // Audio seek point index [Frames 4.30]
-            // b"ASPI" => todo!(),
+            b"ASPI" => frame!(SeekPointIndexFrame::parse(&mut stream)?),
             // Not version-specific, go down to general frames
             _ => self.match_frame(tag_header, frame_id, stream)?,
         };
@@ -718,7 +819,7 @@ impl DefaultFrameParser {
             // Event timing codes [Frames 4.5]
             b"ETCO" => frame!(EventTimingCodesFrame::parse(&mut stream)?),
             // MPEG Lookup Codes [Frames 4.6]
-            // b"MLLT" => todo!(),
+            b"MLLT" => frame!(MpegLookupFrame::parse(&mut stream)?),
             // Synchronized tempo codes [Frames 4.7]
             b"SYTC" => frame!(SyncedTempoCodesFrame::parse(&mut stream)?),
             // Unsynchronized Lyrics [Frames 4.8]
@@ -729,7 +830,7 @@ impl DefaultFrameParser {
             b"COMM" => frame!(CommentsFrame::parse(&mut stream)?),
             // (Frames 4.11 & 4.12 are Version-Specific)
             // Reverb [Frames 4.13]
-            // b"RVRB" => todo!(),
+            b"RVRB" => frame!(ReverbFrame::parse(&mut stream)?),
             // Attached Picture [Frames 4.14]
             b"APIC" => frame!(AttachedPictureFrame::parse(&mut stream)?),
             // General Encapsulated Object [Frames 4.15]
@@ -739,13 +840,13 @@ impl DefaultFrameParser {
             // Popularimeter [Frames 4.17]
             b"POPM" => frame!(PopularimeterFrame::parse(&mut stream)?),
             // Relative buffer size [Frames 4.18]
-            // b"RBUF" => todo!(),
+            b"RBUF" => frame!(BufferSizeFrame::parse(&mut stream)?),
             // Audio Encryption [Frames 4.19]
-            // b"AENC" => todo!(),
+            b"AENC" => frame!(AudioEncryptionFrame::parse(&mut stream)?),
             // Linked Information [Frames 4.20]
-            // b"LINK" => todo!(),
+            b"LINK" => frame!(LinkFrame::parse(tag_header, &mut stream)?),
             // Position synchronization frame [Frames 4.21]
-            // b"POSS" => todo!(),
+            b"POSS" => frame!(PositionSyncFrame::parse(&mut stream)?),
             // Terms of use frame [Frames 4.22]
             b"USER" => frame!(TermsOfUseFrame::parse(&mut stream)?),
             // Ownership frame [Frames 4.23]
@@ -753,9 +854,9 @@ impl DefaultFrameParser {
             // Commercial frame [Frames 4.24]
             b"COMR" => frame!(CommercialFrame::parse(&mut stream)?),
             // Encryption Registration [Frames 4.25]
-            // b"ENCR" => todo!(),
+            b"ENCR" => frame!(EncryptionMethodFrame::parse(&mut stream)?),
             // Group Identification [Frames 4.26]
-            // b"GRID" => todo!(),
+            b"GRID" => frame!(GroupIdFrame::parse(&mut stream)?),
             // Private Frame [Frames 4.27]
             b"PRIV" => frame!(PrivateFrame::parse(&mut stream)?),
             // (Frames 4.28 -> 4.30 are version-specific)
@@ -782,16 +883,20 @@ impl Default for DefaultFrameParser {
 // Internal analogue to FrameResult that returns unknown frames.
 #[derive(Debug)]
 pub(crate) enum ParsedFrame {
-    Frame(Box<dyn Frame>),
+    Frame(Box<dyn Frame>, FrameFlags),
     Unknown(UnknownFrame),
     Dropped,
 }
 
-impl From<FrameResult<'_>> for ParsedFrame {
-    fn from(other: FrameResult) -> ParsedFrame {
-        match other {
-            FrameResult::Frame(frame) => Self::Frame(frame),
-            FrameResult::Unknown(data) => Self::Unknown(UnknownFrame::new(data, 0)),
+impl ParsedFrame {
+    fn new(result: FrameResult, flags: FrameFlags, raw_flags: u16) -> Self {
+        match result {
+            FrameResult::Frame(frame) => Self::Frame(frame, flags),
+            // The frame data has already been decoded at this point, so only the status
+            // flags are kept for unknown frames.
+            FrameResult::Unknown(data) => {
+                Self::Unknown(UnknownFrame::new(data, raw_flags & 0xFF00))
+            }
             FrameResult::Dropped => ParsedFrame::Dropped,
         }
     }
@@ -831,8 +936,10 @@ fn parse_frame_v2(
     // Luckily for us, we don't need to do any decoding magic for ID3v2.2 frames.
     let stream = stream.slice_stream(size)?;
 
-    Ok(ParsedFrame::from(
+    Ok(ParsedFrame::new(
         parser.parse(tag_header, FrameData::Legacy(frame_id, stream))?,
+        FrameFlags::default(),
+        0,
     ))
 }
 
@@ -870,8 +977,10 @@ fn parse_frame_v3(
                 let mut v2_id = [0; 3];
                 v2_id.copy_from_slice(&id_bytes[0..3]);
 
-                return Ok(ParsedFrame::from(
+                return Ok(ParsedFrame::new(
                     parser.parse(tag_header, FrameData::Legacy(v2_id, stream))?,
+                    FrameFlags::default(),
+                    0,
                 ));
             }
 
@@ -879,26 +988,35 @@ fn parse_frame_v3(
         }
     };
 
-    // Encryption. This really can't be implemented since:
-    // A. Encryption is vendor-specific
-    // B. Even if we *were* to add a Fn-pointer for the end-user handle these frames, the end-user
-    // still doesn't know what kind of encryption the frame might have since the corresponding ENCR
-    // frame might have not even been parsed yet.
+    // Encryption. This can't be handled here since encryption is vendor-specific, and the
+    // corresponding ENCR frame might have not even been parsed yet. Instead, encrypted frames
+    // are kept as unknown frames until they are decrypted with Tag::decrypt, once all ENCR
+    // frames are known.
     //
     // The way encryption is designed in ID3v2.3 and ID3v2.4 is absolutely busted, and honestly it
     // would be so much better if a metaframe like ID3v2.2's CRM was used instead. Oh well.
     if flags & 0x40 != 0 {
-        warn!("encryption is not supported");
+        info!("found encrypted frame {}", frame_id);
         return Ok(ParsedFrame::Unknown(UnknownFrame::new(
             FrameData::Normal(frame_id, stream),
             flags,
         )));
     }
 
+    let mut frame_flags = FrameFlags::parse(flags, Version::V23);
+
     // Frame-specific compression. This flag also adds a data length indicator that we will skip.
+    // The group symbol comes after it, but before the compressed data.
     if flags & 0x80 != 0 {
         stream.skip(4)?;
+    }
+
+    // Frame grouping.
+    if flags & 0x20 != 0 && stream.len() >= 4 {
+        frame_flags.group = Some(stream.read_u8()?);
+    }
 
+    if flags & 0x80 != 0 {
         decoded = match inflate_frame(&mut stream) {
             Ok(stream) => stream,
             Err(_) => {
@@ -910,15 +1028,13 @@ fn parse_frame_v3(
         };
 
         stream = BufStream::new(&decoded);
+        frame_flags.compressed = true;
     }
 
-    // Frame grouping. Pretty much nobody uses this, so its ignored.
-    if flags & 0x20 != 0 && stream.len() >= 4 {
-        stream.skip(1)?;
-    }
-
-    return Ok(ParsedFrame::from(
+    return Ok(ParsedFrame::new(
         parser.parse(tag_header, FrameData::Normal(frame_id, stream))?,
+        frame_flags,
+        flags,
     ));
 }
 
@@ -981,20 +1097,24 @@ fn parse_frame_v4(
         stream = BufStream::new(&decoded);
     }
 
-    // Frame grouping. Pretty much nobody uses this, so its ignored.
-    if flags & 0x40 != 0 {
-        stream.skip(1)?;
-    }
-
-    // Encryption is unimplemented, see parse_frame_v3 for more information.
+    // Encrypted frames are decrypted later, see parse_frame_v3 for more information. This is
+    // done before anything else is read so that the frame keeps all of its auxiliary data,
+    // such as the group symbol.
     if flags & 0x4 != 0 {
-        warn!("encryption is not supported");
+        info!("found encrypted frame {}", frame_id);
         return Ok(ParsedFrame::Unknown(UnknownFrame::new(
             FrameData::Normal(frame_id, stream),
             flags,
         )));
     }
 
+    let mut frame_flags = FrameFlags::parse(flags, Version::V24);
+
+    // Frame grouping.
+    if flags & 0x40 != 0 {
+        frame_flags.group = Some(stream.read_u8()?);
+    }
+
     // Data length indicator. Some taggers may not flip the data length indicator when
     // compression is enabled, so it's treated as implicitly enabling it.
     // The spec is also vague about whether the length location is affected by the new flag
@@ -1017,13 +1137,104 @@ fn parse_frame_v4(
         };
 
         stream = BufStream::new(&decoded);
+        frame_flags.compressed = true;
     }
 
-    return Ok(ParsedFrame::from(
+    return Ok(ParsedFrame::new(
         parser.parse(tag_header, FrameData::Normal(frame_id, stream))?,
+        frame_flags,
+        flags,
     ));
 }
 
+/// Attempts to decrypt an encrypted unknown frame with the method registered in `methods`.
+pub(crate) fn decrypt(
+    tag_header: &TagHeader,
+    frame: &UnknownFrame,
+    methods: &FrameMap,
+    decryptor: &dyn FrameDecryptor,
+    parser: &impl FrameParser,
+) -> Option<ParsedFrame> {
+    let version = tag_header.version();
+    let flags = frame.flags();
+
+    let (encrypted, compressed) = match version {
+        Version::V24 => (flags & 0x4 != 0, flags & 0x8 != 0),
+        Version::V23 => (flags & 0x40 != 0, flags & 0x80 != 0),
+        Version::V22 => return None,
+    };
+
+    if !encrypted {
+        return None;
+    }
+
+    let frame_id = FrameId::try_new(&frame.id().try_into().ok()?).ok()?;
+    let mut stream = BufStream::new(frame.data());
+    let mut frame_flags = FrameFlags::parse(flags, version);
+
+    // The auxiliary data of the frame is in the order of the flags, which differs between versions.
+    let method = match version {
+        Version::V24 => {
+            if flags & 0x40 != 0 {
+                frame_flags.group = Some(stream.read_u8().ok()?);
+            }
+
+            let method = stream.read_u8().ok()?;
+
+            if flags & 0x1 != 0 || compressed {
+                stream.skip(4).ok()?;
+            }
+
+            method
+        }
+        _ => {
+            if compressed {
+                stream.skip(4).ok()?;
+            }
+
+            let method = stream.read_u8().ok()?;
+
+            if flags & 0x20 != 0 {
+                frame_flags.group = Some(stream.read_u8().ok()?);
+            }
+
+            method
+        }
+    };
+
+    let method_frame = match methods.encryption_method(method) {
+        Some(method_frame) => method_frame,
+        None => {
+            warn!("no encryption method registered for {:#x}", method);
+            return None;
+        }
+    };
+
+    let mut decoded = match decryptor.decrypt(method_frame, stream.take_rest()) {
+        Some(decoded) => decoded,
+        None => {
+            warn!("could not decrypt frame {}", frame_id);
+            return None;
+        }
+    };
+
+    if compressed {
+        decoded = inflate_frame(&mut BufStream::new(&decoded)).ok()?;
+        frame_flags.compressed = true;
+    }
+
+    frame_flags.encryption = Some(method);
+
+    let result = parser
+        .parse(
+            tag_header,
+            FrameData::Normal(frame_id, BufStream::new(&decoded)),
+        )
+        .ok()?;
+
+    Some(ParsedFrame::new(result, frame_flags, 0))
+}
+
 cfg_if::cfg_if! {
     if #[cfg(feature = "id3v2_compression")] {
         fn inflate_frame(src: &mut BufStream) -> ParseResult<Vec<u8>> {
@@ -1032,33 +1243,132 @@ cfg_if::cfg_if! {
                 ParseError::MalformedData
             })
         }
+
+        fn deflate_frame(src: &[u8]) -> Option<Vec<u8>> {
+            Some(miniz_oxide::deflate::compress_to_vec_zlib(src, 6))
+        }
     } else {
         fn inflate_frame(src: &mut BufStream) -> ParseResult<Vec<u8>> {
             warn!("decompression is not enabled");
             Err(ParseError::Unsupported)
         }
+
+        fn deflate_frame(_: &[u8]) -> Option<Vec<u8>> {
+            warn!("compression is not enabled");
+            None
+        }
     }
 }
 
-pub(crate) fn render(tag_header: &TagHeader, frame: &dyn Frame) -> SaveResult<Vec<u8>> {
+pub(crate) fn render(
+    tag_header: &TagHeader,
+    frame: &dyn Frame,
+    frame_flags: &FrameFlags,
+    encryptor: Option<(&dyn FrameDecryptor, &EncryptionMethodFrame)>,
+) -> SaveResult<Vec<u8>> {
     assert_ne!(tag_header.version(), Version::V22);
 
     // We need to render the frame backwards, starting from the frame and then making the
     // header from the size of that data.
 
     // Render the frame here, as we will need its size.
-    let frame_data = frame.render(tag_header);
+    let mut frame_data = frame.render(tag_header);
+    let mut flags = frame_flags.render_status(tag_header.version());
+    let size = frame_data.len() as u32;
+
+    // Frame-specific compression. Both versions prepend the decompressed size to the
+    // compressed data, but ID3v2.4 calls it a data length indicator and makes it syncsafe.
+    let mut compressed = false;
+
+    if frame_flags.compressed {
+        match deflate_frame(&frame_data) {
+            Some(data) => {
+                frame_data = data;
+                compressed = true;
+            }
+            None => warn!("writing frame {} uncompressed", frame.key()),
+        }
+    }
+
+    // Frame-specific encryption. This is done after compression, and cannot be skipped since
+    // that would write frames that should be encrypted in plaintext.
+    if let Some(method) = frame_flags.encryption {
+        match encryptor.and_then(|(encryptor, method)| encryptor.encrypt(method, &frame_data)) {
+            Some(data) => frame_data = data,
+            None => {
+                error!(
+                    "could not encrypt frame {} with method {:#x}",
+                    frame.key(),
+                    method
+                );
+                return Err(SaveError::EncryptionFailed);
+            }
+        }
+    }
+
+    // The auxiliary data is written in the order of the flags, which differs between versions.
     let mut data: Vec<u8> = Vec::new();
 
-    // Render the header. Leave the flags zeroed, we don't care about them and likely
-    // never will.
-    data.extend(match tag_header.version() {
-        Version::V24 => render_v4_header(frame.id(), 0, frame_data.len())?,
-        Version::V23 => render_v3_header(frame.id(), 0, frame_data.len())?,
+    match tag_header.version() {
+        Version::V24 => {
+            if let Some(group) = frame_flags.group {
+                flags |= 0x0040;
+                data.push(group);
+            }
+
+            if let Some(method) = frame_flags.encryption {
+                flags |= 0x0004;
+                data.push(method);
+            }
+
+            // ID3v2.4 unsynchronization is done per-frame, and is also marked on every frame.
+            // We write a data length indicator here as well, as the spec recommends it.
+            let unsync = tag_header.flags().unsync;
+
+            if compressed {
+                flags |= 0x0008;
+            }
+
+            if compressed || unsync || frame_flags.encryption.is_some() {
+                flags |= 0x0001;
+                data.extend(syncdata::from_u28(size));
+            }
+
+            data.extend(frame_data);
+
+            if unsync {
+                data = syncdata::encode(&data);
+                flags |= 0x0002;
+            }
+        }
+        Version::V23 => {
+            if compressed {
+                flags |= 0x0080;
+                data.extend(size.to_be_bytes());
+            }
+
+            if let Some(method) = frame_flags.encryption {
+                flags |= 0x0040;
+                data.push(method);
+            }
+
+            if let Some(group) = frame_flags.group {
+                flags |= 0x0020;
+                data.push(group);
+            }
+
+            data.extend(frame_data);
+        }
         Version::V22 => unreachable!(),
-    });
+    }
+
+    let header = match tag_header.version() {
+        Version::V24 => render_v4_header(frame.id(), flags, data.len())?,
+        Version::V23 => render_v3_header(frame.id(), flags, data.len())?,
+        Version::V22 => unreachable!(),
+    };
 
-    data.extend(frame_data);
+    data.splice(0..0, header);
 
     Ok(data)
 }
@@ -1072,22 +1382,38 @@ pub(crate) fn render_unknown(tag_header: &TagHeader, frame: &UnknownFrame) -> Ve
         return Vec::new();
     }
 
+    // Writing the tag alters it, so unknown frames that should be discarded on tag alteration
+    // are dropped.
+    if FrameFlags::parse(frame.flags(), tag_header.version()).tag_alter_discard {
+        info!("discarding unknown frame {}", frame.id_str());
+        return Vec::new();
+    }
+
     let frame_id = FrameId::new(&frame.id().try_into().unwrap());
 
     let mut data: Vec<u8> = Vec::new();
 
     // UnknownFrame instances are immutable, so we can assume that they will render with no issues.
-    // We also re-render the unknown frame flags as well, exlcuding the ID3v2.4 unsync flag, as we don't
-    // resynchronize frames.
-    data.extend(match tag_header.version() {
+    // We also re-render the unknown frame flags as well. The ID3v2.4 unsync flag is only kept if
+    // the tag is being unsynchronized, as unknown frames are stored decoded.
+    match tag_header.version() {
+        Version::V24 if tag_header.flags().unsync => {
+            let frame_data = syncdata::encode(frame.data());
+            let flags = frame.flags() | 0x0002;
+            data.extend(render_v4_header(frame_id, flags, frame_data.len()).unwrap());
+            data.extend(frame_data);
+        }
         Version::V24 => {
-            render_v4_header(frame_id, frame.flags() & 0xFFFD, frame.data().len()).unwrap()
+            let flags = frame.flags() & 0xFFFD;
+            data.extend(render_v4_header(frame_id, flags, frame.data().len()).unwrap());
+            data.extend(frame.data());
+        }
+        Version::V23 => {
+            data.extend(render_v3_header(frame_id, frame.flags(), frame.data().len()).unwrap());
+            data.extend(frame.data());
         }
-        Version::V23 => render_v3_header(frame_id, frame.flags(), frame.data().len()).unwrap(),
         Version::V22 => unreachable!(),
-    });
-
-    data.extend(frame.data());
+    };
 
     data
 }
@@ -1110,7 +1436,7 @@ fn render_v3_header(frame_id: FrameId, flags: u16, size: usize) -> SaveResult<[u
     data[4..8].copy_from_slice(&size.to_be_bytes());
 
     // Render flags.
-    data[8] = (flags & 0xFF00) as u8;
+    data[8] = (flags >> 8) as u8;
     data[9] = (flags & 0x00FF) as u8;
 
     Ok(data)
@@ -1131,7 +1457,7 @@ fn render_v4_header(frame_id: FrameId, flags: u16, size: usize) -> SaveResult<[u
     data[4..8].copy_from_slice(&syncdata::from_u28(size as u32));
 
     // Render flags.
-    data[8] = (flags & 0xFF00) as u8;
+    data[8] = (flags >> 8) as u8;
     data[9] = (flags & 0x00FF) as u8;
 
     Ok(data)
@@ -1182,10 +1508,16 @@ mod tests {
         )
         .unwrap();
 
-        if let ParsedFrame::Frame(frame) = frame {
+        if let ParsedFrame::Frame(frame, _) = frame {
             assert_eq!(frame.id(), b"TIT2");
             assert_eq!(
-                render(&TagHeader::with_version(Version::V23), frame.deref()).unwrap(),
+                render(
+                    &TagHeader::with_version(Version::V23),
+                    frame.deref(),
+                    &FrameFlags::default(),
+                    None
+                )
+                .unwrap(),
                 DATA_V3
             );
         } else {
@@ -1202,10 +1534,16 @@ mod tests {
         )
         .unwrap();
 
-        if let ParsedFrame::Frame(frame) = frame {
+        if let ParsedFrame::Frame(frame, _) = frame {
             assert_eq!(frame.id(), b"TIT2");
             assert_eq!(
-                render(&TagHeader::with_version(Version::V24), frame.deref()).unwrap(),
+                render(
+                    &TagHeader::with_version(Version::V24),
+                    frame.deref(),
+                    &FrameFlags::default(),
+                    None
+                )
+                .unwrap(),
                 DATA_V4
             );
         } else {
@@ -1222,10 +1560,16 @@ mod tests {
         )
         .unwrap();
 
-        if let ParsedFrame::Frame(frame) = frame {
+        if let ParsedFrame::Frame(frame, _) = frame {
             assert_eq!(frame.id(), b"TIT2");
             assert_eq!(
-                render(&TagHeader::with_version(Version::V23), frame.deref()).unwrap(),
+                render(
+                    &TagHeader::with_version(Version::V23),
+                    frame.deref(),
+                    &FrameFlags::default(),
+                    None
+                )
+                .unwrap(),
                 DATA_V3
             );
         } else {
@@ -1233,6 +1577,57 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_frame_flags() {
+        let data_v3 = b"TIT2\x00\x00\x00\x0A\xA0\x20\x16\x00Unspoken";
+        let data_v4 = b"TIT2\x00\x00\x00\x0A\x50\x40\x16\x00Unspoken";
+
+        for (version, data) in [(Version::V23, data_v3), (Version::V24, data_v4)] {
+            let tag_header = TagHeader::with_version(version);
+            let frame = parse(
+                &tag_header,
+                &mut BufStream::new(data),
+                &DefaultFrameParser { strict: true },
+            )
+            .unwrap();
+
+            if let ParsedFrame::Frame(frame, flags) = frame {
+                assert_eq!(frame.to_string(), "Unspoken");
+                assert!(flags.tag_alter_discard);
+                assert!(!flags.file_alter_discard);
+                assert!(flags.read_only);
+                assert_eq!(flags.group, Some(0x16));
+                assert!(!flags.compressed);
+
+                assert_eq!(
+                    render(&tag_header, frame.deref(), &flags, None).unwrap(),
+                    data
+                );
+            } else {
+                panic!("frame was not parsed");
+            }
+        }
+    }
+
+    #[test]
+    fn discard_unknown_on_alter() {
+        let data = b"ABCD\x00\x00\x00\x04\x40\x00\x16\x16\x16\x16";
+
+        let frame = parse(
+            &TagHeader::with_version(Version::V24),
+            &mut BufStream::new(data),
+            &DefaultFrameParser { strict: true },
+        )
+        .unwrap();
+
+        if let ParsedFrame::Unknown(unknown) = frame {
+            assert_eq!(unknown.flags(), 0x4000);
+            assert!(render_unknown(&TagHeader::with_version(Version::V24), &unknown).is_empty());
+        } else {
+            panic!("frame is not unknown")
+        }
+    }
+
     #[test]
     fn parse_unknown_v2() {
         let data = b"ABC\x00\x00\x04\x16\x16\x16\x16";
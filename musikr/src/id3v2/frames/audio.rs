@@ -7,3 +7,335 @@
 
 pub mod v23;
 pub mod v24;
+
+use crate::core::io::BufStream;
+use crate::core::string::{self, Encoding};
+use crate::id3v2::frames::{Frame, FrameId};
+use crate::id3v2::{ParseResult, TagHeader};
+use std::fmt::{self, Display, Formatter};
+
+/// A frame that describes how the audio stream is encrypted.
+///
+/// The part of the audio from `preview_start` that lasts for `preview_length` frames is left
+/// unencrypted. The `data` is specific to the owner of the encryption method.
+#[derive(Default, Debug, Clone)]
+pub struct AudioEncryptionFrame {
+    pub owner: String,
+    pub preview_start: u16,
+    pub preview_length: u16,
+    pub data: Vec<u8>,
+}
+
+impl AudioEncryptionFrame {
+    pub(crate) fn parse(stream: &mut BufStream) -> ParseResult<Self> {
+        let owner = string::read_terminated(Encoding::Latin1, stream);
+        let preview_start = stream.read_be_u16()?;
+        let preview_length = stream.read_be_u16()?;
+        let data = stream.take_rest().to_vec();
+
+        Ok(Self {
+            owner,
+            preview_start,
+            preview_length,
+            data,
+        })
+    }
+}
+
+impl Frame for AudioEncryptionFrame {
+    fn id(&self) -> FrameId {
+        FrameId::new(b"AENC")
+    }
+
+    fn key(&self) -> String {
+        format!["AENC:{}", self.owner]
+    }
+
+    fn is_empty(&self) -> bool {
+        self.owner.is_empty()
+    }
+
+    fn render(&self, _: &TagHeader) -> Vec<u8> {
+        let mut result = Vec::new();
+
+        result.extend(string::render_terminated(Encoding::Latin1, &self.owner));
+        result.extend(self.preview_start.to_be_bytes());
+        result.extend(self.preview_length.to_be_bytes());
+        result.extend(self.data.iter());
+
+        result
+    }
+}
+
+impl Display for AudioEncryptionFrame {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write![f, "{}", self.owner]
+    }
+}
+
+/// A frame that describes the reverb of the audio.
+///
+/// `left` and `right` are the delay between bounces in milliseconds for each channel, while
+/// `bounces_left` and `bounces_right` are the number of bounces, with `0xFF` being an infinite
+/// reverb. Each `feedback_*` field is how much of the first channel is fed back into the second
+/// channel on each bounce, and each `premix_*` field is how much of the first channel is mixed
+/// into the second channel before the reverb is applied. Both range from `0x00` for none of
+/// the sound to `0xFF` for all of it.
+#[derive(Default, Debug, Clone)]
+pub struct ReverbFrame {
+    pub left: u16,
+    pub right: u16,
+    pub bounces_left: u8,
+    pub bounces_right: u8,
+    pub feedback_left_left: u8,
+    pub feedback_left_right: u8,
+    pub feedback_right_right: u8,
+    pub feedback_right_left: u8,
+    pub premix_left_right: u8,
+    pub premix_right_left: u8,
+}
+
+impl ReverbFrame {
+    pub(crate) fn parse(stream: &mut BufStream) -> ParseResult<Self> {
+        Ok(Self {
+            left: stream.read_be_u16()?,
+            right: stream.read_be_u16()?,
+            bounces_left: stream.read_u8()?,
+            bounces_right: stream.read_u8()?,
+            feedback_left_left: stream.read_u8()?,
+            feedback_left_right: stream.read_u8()?,
+            feedback_right_right: stream.read_u8()?,
+            feedback_right_left: stream.read_u8()?,
+            premix_left_right: stream.read_u8()?,
+            premix_right_left: stream.read_u8()?,
+        })
+    }
+}
+
+impl Frame for ReverbFrame {
+    fn id(&self) -> FrameId {
+        FrameId::new(b"RVRB")
+    }
+
+    fn key(&self) -> String {
+        String::from("RVRB")
+    }
+
+    fn is_empty(&self) -> bool {
+        false
+    }
+
+    fn render(&self, _: &TagHeader) -> Vec<u8> {
+        let mut result = Vec::new();
+
+        result.extend(self.left.to_be_bytes());
+        result.extend(self.right.to_be_bytes());
+        result.extend([
+            self.bounces_left,
+            self.bounces_right,
+            self.feedback_left_left,
+            self.feedback_left_right,
+            self.feedback_right_right,
+            self.feedback_right_left,
+            self.premix_left_right,
+            self.premix_right_left,
+        ]);
+
+        result
+    }
+}
+
+impl Display for ReverbFrame {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write![f, "{}ms, {}ms", self.left, self.right]
+    }
+}
+
+/// A frame that recommends the size of the buffer used to play the audio.
+///
+/// `size` is the buffer size in bytes, which can be at most 24 bits. If `embedded` is set,
+/// then tags up to `size` bytes large may be embedded in the audio stream. `next_tag` is the
+/// offset from the end of this tag to the start of the next tag, if there is one.
+#[derive(Default, Debug, Clone)]
+pub struct BufferSizeFrame {
+    pub size: u32,
+    pub embedded: bool,
+    pub next_tag: Option<u32>,
+}
+
+impl BufferSizeFrame {
+    pub(crate) fn parse(stream: &mut BufStream) -> ParseResult<Self> {
+        let [a, b, c] = stream.read_array()?;
+        let size = u32::from_be_bytes([0, a, b, c]);
+        let embedded = stream.read_u8()? & 0x1 != 0;
+
+        // The offset to the next tag is optional.
+        let next_tag = stream.read_be_u32().ok();
+
+        Ok(Self {
+            size,
+            embedded,
+            next_tag,
+        })
+    }
+}
+
+impl Frame for BufferSizeFrame {
+    fn id(&self) -> FrameId {
+        FrameId::new(b"RBUF")
+    }
+
+    fn key(&self) -> String {
+        String::from("RBUF")
+    }
+
+    fn is_empty(&self) -> bool {
+        false
+    }
+
+    fn render(&self, _: &TagHeader) -> Vec<u8> {
+        let mut result = self.size.to_be_bytes()[1..].to_vec();
+
+        result.push(u8::from(self.embedded));
+
+        if let Some(next_tag) = self.next_tag {
+            result.extend(next_tag.to_be_bytes());
+        }
+
+        result
+    }
+}
+
+impl Display for BufferSizeFrame {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write![f, "{}", self.size]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id3v2::tag::Version;
+
+    const AENC_DATA: &[u8] = b"AENC\x00\x00\x00\x10\x00\x00\
+                               example\0\
+                               \x00\x10\
+                               \x01\x00\
+                               \x16\x16\x16\x16";
+
+    const RVRB_DATA: &[u8] = b"RVRB\x00\x00\x00\x0C\x00\x00\
+                               \x01\x02\x03\x04\
+                               \x05\x06\
+                               \x07\x08\x09\x0A\
+                               \x0B\x0C";
+
+    const RVRB_DATA_V2: &[u8] = b"REV\x00\x00\x0C\
+                                  \x01\x02\x03\x04\
+                                  \x05\x06\
+                                  \x07\x08\x09\x0A\
+                                  \x0B\x0C";
+
+    const RBUF_DATA: &[u8] = b"RBUF\x00\x00\x00\x08\x00\x00\
+                               \x00\x10\x00\
+                               \x01\
+                               \x00\x00\x16\x16";
+
+    const RBUF_DATA_V2: &[u8] = b"BUF\x00\x00\x04\
+                                  \x00\x10\x00\
+                                  \x00";
+
+    #[test]
+    fn parse_aenc() {
+        make_frame!(AudioEncryptionFrame, AENC_DATA, frame);
+
+        assert_eq!(frame.owner, "example");
+        assert_eq!(frame.preview_start, 0x10);
+        assert_eq!(frame.preview_length, 0x100);
+        assert_eq!(frame.data, b"\x16\x16\x16\x16");
+    }
+
+    #[test]
+    fn parse_rvrb() {
+        make_frame!(ReverbFrame, RVRB_DATA, frame);
+
+        assert_eq!(frame.left, 0x0102);
+        assert_eq!(frame.right, 0x0304);
+        assert_eq!(frame.bounces_left, 0x05);
+        assert_eq!(frame.bounces_right, 0x06);
+        assert_eq!(frame.feedback_left_left, 0x07);
+        assert_eq!(frame.feedback_left_right, 0x08);
+        assert_eq!(frame.feedback_right_right, 0x09);
+        assert_eq!(frame.feedback_right_left, 0x0A);
+        assert_eq!(frame.premix_left_right, 0x0B);
+        assert_eq!(frame.premix_right_left, 0x0C);
+    }
+
+    #[test]
+    fn parse_rvrb_v2() {
+        make_frame!(ReverbFrame, RVRB_DATA_V2, Version::V22, frame);
+
+        assert_eq!(frame.id(), b"RVRB");
+        assert_eq!(frame.left, 0x0102);
+        assert_eq!(frame.premix_right_left, 0x0C);
+    }
+
+    #[test]
+    fn parse_rbuf() {
+        make_frame!(BufferSizeFrame, RBUF_DATA, frame);
+
+        assert_eq!(frame.size, 0x1000);
+        assert!(frame.embedded);
+        assert_eq!(frame.next_tag, Some(0x1616));
+    }
+
+    #[test]
+    fn parse_rbuf_v2() {
+        make_frame!(BufferSizeFrame, RBUF_DATA_V2, Version::V22, frame);
+
+        assert_eq!(frame.id(), b"RBUF");
+        assert_eq!(frame.size, 0x1000);
+        assert!(!frame.embedded);
+        assert_eq!(frame.next_tag, None);
+    }
+
+    #[test]
+    fn render_aenc() {
+        let frame = AudioEncryptionFrame {
+            owner: String::from("example"),
+            preview_start: 0x10,
+            preview_length: 0x100,
+            data: b"\x16\x16\x16\x16".to_vec(),
+        };
+
+        assert_render!(frame, AENC_DATA);
+    }
+
+    #[test]
+    fn render_rvrb() {
+        let frame = ReverbFrame {
+            left: 0x0102,
+            right: 0x0304,
+            bounces_left: 0x05,
+            bounces_right: 0x06,
+            feedback_left_left: 0x07,
+            feedback_left_right: 0x08,
+            feedback_right_right: 0x09,
+            feedback_right_left: 0x0A,
+            premix_left_right: 0x0B,
+            premix_right_left: 0x0C,
+        };
+
+        assert_render!(frame, RVRB_DATA);
+    }
+
+    #[test]
+    fn render_rbuf() {
+        let frame = BufferSizeFrame {
+            size: 0x1000,
+            embedded: true,
+            next_tag: Some(0x1616),
+        };
+
+        assert_render!(frame, RBUF_DATA);
+    }
+}
@@ -43,7 +43,7 @@ impl RelativeVolumeFrame {
         // ideal, as it means that we can't upgrade to RVA2/EQU2 in a sane way, but its the only thing
         // we can do sadly.
 
-        let len = usize::min((usize::from(bits) + 7) / 8, 8);
+        let len = usize::min(usize::from(bits).div_ceil(8), 8);
 
         // Since the sign of an adjustment is separate from the actual data, we will use an enum instead
         // of a signed integer so that we don't lose information.
@@ -173,7 +173,7 @@ impl EqualizationFrame {
 
         // Begin parsing our adjustments.
         let mut adjustments = BTreeMap::new();
-        let len = usize::min((usize::from(bits) + 7) / 8, 8);
+        let len = usize::min(usize::from(bits).div_ceil(8), 8);
 
         while !stream.is_empty() {
             // EQUA frequencies are special in that the last bit is used as the
@@ -168,19 +168,15 @@ impl Display for EqualizationFrame2 {
 }
 
 byte_enum! {
+    #[derive(Default)]
     pub enum InterpolationMethod {
         Band = 0x00,
+        #[default]
         Linear = 0x01,
     };
     InterpolationMethod::Band
 }
 
-impl Default for InterpolationMethod {
-    fn default() -> Self {
-        InterpolationMethod::Linear
-    }
-}
-
 const MIN_16: f64 = i16::MIN as f64;
 const MAX_16: f64 = i16::MAX as f64;
 
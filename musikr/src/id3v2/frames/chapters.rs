@@ -64,7 +64,10 @@ impl Frame for ChapterFrame {
         result.extend(self.time.end_time.to_be_bytes());
         result.extend(self.time.start_offset.to_be_bytes());
         result.extend(self.time.end_offset.to_be_bytes());
-        result.extend(self.frames.render(tag_header));
+        match self.frames.render(tag_header, None) {
+            Ok(data) => result.extend(data),
+            Err(_) => warn!("dropping embedded frames of {}", self.key()),
+        }
 
         result
     }
@@ -213,7 +216,10 @@ impl Frame for TableOfContentsFrame {
             ))
         }
 
-        result.extend(self.frames.render(tag_header));
+        match self.frames.render(tag_header, None) {
+            Ok(data) => result.extend(data),
+            Err(_) => warn!("dropping embedded frames of {}", self.key()),
+        }
 
         result
     }
@@ -269,7 +275,7 @@ fn parse_embedded_frames(
 
     while let Ok(parsed) = frames::parse(tag_header, stream, handler) {
         match parsed {
-            ParsedFrame::Frame(frame) => frames.add_boxed(frame),
+            ParsedFrame::Frame(frame, flags) => frames.add_parsed(tag_header, frame, flags),
             ParsedFrame::Unknown(unknown) => {
                 // Drop unknown frames if they're encountered. This is mostly for simplicity, as this
                 // allows all members in a ChapterFrame/TableOfContentsFrame to be public and also
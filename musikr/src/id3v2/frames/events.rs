@@ -2,7 +2,8 @@
 
 use crate::core::io::BufStream;
 use crate::id3v2::frames::{Frame, FrameId};
-use crate::id3v2::{ParseResult, TagHeader};
+use crate::id3v2::{ParseError, ParseResult, TagHeader};
+use log::error;
 use std::cmp::{Eq, Ord, Ordering, PartialEq, PartialOrd};
 use std::fmt::{self, Display, Formatter};
 
@@ -98,8 +99,9 @@ impl Display for Event {
 }
 
 byte_enum! {
-    #[derive(Ord, PartialOrd)]
+    #[derive(Ord, PartialOrd, Default)]
     pub enum EventType {
+        #[default]
         Padding = 0x00,
         EndOfInitialSilence = 0x01,
         IntroStart = 0x02,
@@ -145,12 +147,6 @@ byte_enum! {
     EventType::Padding
 }
 
-impl Default for EventType {
-    fn default() -> Self {
-        EventType::Padding
-    }
-}
-
 #[derive(Clone, Debug)]
 pub struct SyncedTempoCodesFrame {
     format: TimestampFormat,
@@ -259,29 +255,87 @@ impl Tempo {
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub struct Bpm(pub u16);
 
+/// A frame that marks the position in the audio where the file starts when it's part of a
+/// larger stream, such as a broadcast.
+#[derive(Default, Debug, Clone)]
+pub struct PositionSyncFrame {
+    pub format: TimestampFormat,
+    pub position: u64,
+}
+
+impl PositionSyncFrame {
+    pub(crate) fn parse(stream: &mut BufStream) -> ParseResult<Self> {
+        let format = TimestampFormat::parse(stream.read_u8()?);
+
+        // The position can be any size, but we can only represent it if it fits in a u64.
+        let data = stream.take_rest();
+
+        if data.is_empty() || data.len() > 8 {
+            error!("position cannot be represented");
+            return Err(ParseError::MalformedData);
+        }
+
+        let mut position = [0; 8];
+        position[8 - data.len()..].copy_from_slice(data);
+
+        Ok(Self {
+            format,
+            position: u64::from_be_bytes(position),
+        })
+    }
+}
+
+impl Frame for PositionSyncFrame {
+    fn id(&self) -> FrameId {
+        FrameId::new(b"POSS")
+    }
+
+    fn key(&self) -> String {
+        String::from("POSS")
+    }
+
+    fn is_empty(&self) -> bool {
+        false
+    }
+
+    fn render(&self, _: &TagHeader) -> Vec<u8> {
+        let mut data = vec![self.format as u8];
+
+        // Positions are usually written as a u32, so only write a u64 if we have to.
+        match u32::try_from(self.position) {
+            Ok(position) => data.extend(position.to_be_bytes()),
+            Err(_) => data.extend(self.position.to_be_bytes()),
+        }
+
+        data
+    }
+}
+
+impl Display for PositionSyncFrame {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write![f, "{}", self.position]
+    }
+}
+
 byte_enum! {
     /// A representation of an ID3v2 timestamp format
     ///
     /// The timestamp format represents the units for any timestamps
     /// in an ID3v2 frame. For the best compatibility with programs,
     /// [`Millis`](TimestampFormat::Millis) should be used.
+    #[derive(Default)]
     pub enum TimestampFormat {
         /// No unit was specified.
         Other = 0x00,
         /// Timestamps are in MPEG Frames.
         MpegFrames = 0x01,
         /// Timestamps are in milliseconds.
+        #[default]
         Millis = 0x02,
     };
     TimestampFormat::Other
 }
 
-impl Default for TimestampFormat {
-    fn default() -> Self {
-        TimestampFormat::Millis
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -312,6 +366,10 @@ mod tests {
                                \xFF\xA0\
                                \x00\x00\x00\x00";
 
+    const POSS_DATA: &[u8] = b"POSS\x00\x00\x00\x05\x00\x00\
+                               \x02\
+                               \x00\x00\x16\x16";
+
     #[test]
     fn parse_etco() {
         make_frame!(EventTimingCodesFrame, ETCO_DATA, frame);
@@ -384,6 +442,24 @@ mod tests {
         assert_render!(frame, SYTC_DATA);        
     }
 
+    #[test]
+    fn parse_poss() {
+        make_frame!(PositionSyncFrame, POSS_DATA, frame);
+
+        assert_eq!(frame.format, TimestampFormat::Millis);
+        assert_eq!(frame.position, 0x1616);
+    }
+
+    #[test]
+    fn render_poss() {
+        let frame = PositionSyncFrame {
+            format: TimestampFormat::Millis,
+            position: 0x1616,
+        };
+
+        assert_render!(frame, POSS_DATA);
+    }
+
     #[test]
     fn parse_timestamp_format() {
         assert_eq!(TimestampFormat::parse(0), TimestampFormat::Other);
@@ -0,0 +1,289 @@
+//! Frames that link to information outside of the tag.
+
+use crate::core::io::BufStream;
+use crate::core::string::{self, Encoding};
+use crate::id3v2::frames::{Frame, FrameId};
+use crate::id3v2::tag::Version;
+use crate::id3v2::{compat, ParseError, ParseResult, Tag, TagHeader};
+use log::error;
+use std::fmt::{self, Display, Formatter};
+use std::path::Path;
+
+/// A frame that links to frames in a tag in another file.
+///
+/// `url` is the location of the file, and `frame_id` is the ID of the linked frames. The first
+/// string of `id_data` can narrow down which frames are linked, such as the description of a
+/// `TXXX` frame or the language and description of a `COMM` frame. The linked frames can be
+/// loaded with [`LinkFrame::resolve`](LinkFrame::resolve).
+#[derive(Debug, Clone)]
+pub struct LinkFrame {
+    pub frame_id: FrameId,
+    pub url: String,
+    pub id_data: Vec<String>,
+}
+
+impl LinkFrame {
+    /// Creates a new instance of this frame that links to `frame_id` at `url`.
+    pub fn new(frame_id: FrameId, url: &str) -> Self {
+        Self {
+            frame_id,
+            url: url.to_string(),
+            id_data: Vec::new(),
+        }
+    }
+
+    pub(crate) fn parse(tag_header: &TagHeader, stream: &mut BufStream) -> ParseResult<Self> {
+        let frame_id = match tag_header.version() {
+            // ID3v2.2 links use 3-byte frame IDs, which have to be upgraded just like the
+            // frames themselves.
+            Version::V22 => {
+                let id = stream.read_array()?;
+
+                match &id {
+                    b"PIC" => FrameId::new(b"APIC"),
+                    _ => compat::upgrade_v2_id(&id).map_err(|_| {
+                        error!(
+                            "linked frame {} has no analogue",
+                            String::from_utf8_lossy(&id)
+                        );
+                        ParseError::MalformedData
+                    })?,
+                }
+            }
+            _ => FrameId::try_new(&stream.read_array()?).map_err(|_| {
+                error!("linked frame id is not valid");
+                ParseError::MalformedData
+            })?,
+        };
+
+        let url = string::read_terminated(Encoding::Latin1, stream);
+        let mut id_data = Vec::new();
+
+        while !stream.is_empty() {
+            id_data.push(string::read_terminated(Encoding::Latin1, stream));
+        }
+
+        Ok(Self {
+            frame_id,
+            url,
+            id_data,
+        })
+    }
+
+    /// Loads the frames this frame links to, with the directory `dir` standing in for the
+    /// location of `url`.
+    ///
+    /// The tag is read from the file in `dir` that has the same name as the last segment of
+    /// `url`. All frames in that tag with `frame_id` are returned, unless `id_data` is present,
+    /// in which case only the frames it identifies are returned.
+    ///
+    /// # Errors
+    ///
+    /// If `url` has no file name, or if its file name is `.`, `..`, or contains a path
+    /// separator once percent-decoded, [`ParseError::NotFound`](ParseError::NotFound) is returned.
+    /// Otherwise, any error from reading the linked tag with [`Tag::open`](Tag::open) is returned.
+    pub fn resolve<P: AsRef<Path>>(&self, dir: P) -> ParseResult<Vec<Box<dyn Frame>>> {
+        let path = self.url.split(['?', '#']).next().unwrap_or_default();
+        let segment = path.rsplit('/').next().unwrap_or_default();
+
+        let name = match percent_decode(segment) {
+            Some(name) => name,
+            None => {
+                error!("link has a malformed file name");
+                return Err(ParseError::NotFound);
+            }
+        };
+
+        // The name must not be able to escape from dir.
+        if matches!(name.as_str(), "" | "." | "..") || name.contains(['/', '\\', '\0']) {
+            error!("link has no valid file name");
+            return Err(ParseError::NotFound);
+        }
+
+        let tag = Tag::open(dir.as_ref().join(name))?;
+        let key = self.linked_key();
+
+        let frames = tag
+            .frames
+            .get_all(&self.frame_id.inner())
+            .into_iter()
+            .filter(|frame| key.as_deref().is_none_or(|key| frame.key() == key))
+            .map(dyn_clone::clone_box)
+            .collect();
+
+        Ok(frames)
+    }
+
+    fn linked_key(&self) -> Option<String> {
+        let data = self.id_data.first().filter(|data| !data.is_empty())?;
+        let id = self.frame_id;
+
+        let key = match id.as_ref() {
+            // These frames are identified by their language directly followed by their
+            // description.
+            b"COMM" | b"SYLT" | b"USLT" => {
+                let split = data.char_indices().nth(3).map_or(data.len(), |(i, _)| i);
+                let (lang, desc) = data.split_at(split);
+                format!["{}:{}:{}", id, desc, lang]
+            }
+            _ => format!["{}:{}", id, data],
+        };
+
+        Some(key)
+    }
+}
+
+/// Decodes the percent-encoded bytes in a URL segment, returning `None` if an escape is
+/// malformed or the result is not valid UTF-8.
+fn percent_decode(segment: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(segment.len());
+    let mut iter = segment.bytes();
+
+    while let Some(byte) = iter.next() {
+        if byte != b'%' {
+            bytes.push(byte);
+            continue;
+        }
+
+        let high = char::from(iter.next()?).to_digit(16)?;
+        let low = char::from(iter.next()?).to_digit(16)?;
+        bytes.push((high * 16 + low) as u8);
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
+impl Frame for LinkFrame {
+    fn id(&self) -> FrameId {
+        FrameId::new(b"LINK")
+    }
+
+    fn key(&self) -> String {
+        format![
+            "LINK:{}:{}:{}",
+            self.frame_id,
+            self.url,
+            self.id_data.join(":")
+        ]
+    }
+
+    fn is_empty(&self) -> bool {
+        self.url.is_empty()
+    }
+
+    fn render(&self, _: &TagHeader) -> Vec<u8> {
+        let mut result = self.frame_id.inner().to_vec();
+
+        result.extend(string::render_terminated(Encoding::Latin1, &self.url));
+
+        for (i, data) in self.id_data.iter().enumerate() {
+            if i > 0 {
+                result.push(0);
+            }
+
+            result.extend(string::render(Encoding::Latin1, data));
+        }
+
+        result
+    }
+}
+
+impl Display for LinkFrame {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write![f, "{}", self.url]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id3v2::frames::{CommentsFrame, TextFrame};
+    use crate::id3v2::tag::SaveVersion;
+    use std::env;
+    use std::fs;
+
+    const LINK_DATA: &[u8] = b"LINK\x00\x00\x00\x25\x00\x00\
+                               COMM\
+                               https://example.com/a.mp3\0\
+                               engDesc";
+
+    const LINK_DATA_V2: &[u8] = b"LNK\x00\x00\x1D\
+                                  TT2\
+                                  https://example.com/a.mp3\0";
+
+    #[test]
+    fn parse_link() {
+        make_frame!(LinkFrame, LINK_DATA, frame);
+
+        assert_eq!(frame.frame_id, b"COMM");
+        assert_eq!(frame.url, "https://example.com/a.mp3");
+        assert_eq!(frame.id_data, ["engDesc"]);
+    }
+
+    #[test]
+    fn parse_link_v2() {
+        make_frame!(LinkFrame, LINK_DATA_V2, Version::V22, frame);
+
+        assert_eq!(frame.frame_id, b"TIT2");
+        assert_eq!(frame.url, "https://example.com/a.mp3");
+        assert!(frame.id_data.is_empty());
+    }
+
+    #[test]
+    fn render_link() {
+        let mut frame = LinkFrame::new(FrameId::new(b"COMM"), "https://example.com/a.mp3");
+        frame.id_data.push(String::from("engDesc"));
+
+        assert_render!(frame, LINK_DATA);
+    }
+
+    #[test]
+    fn resolve_link() {
+        let dir = env::temp_dir().join("musikr_link");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("linked.mp3"), [0x16; 16]).unwrap();
+
+        let mut title = TextFrame::new(FrameId::new(b"TIT2"));
+        title.text = vec![String::from("Title")];
+
+        let mut tag = Tag::with_version(SaveVersion::V24);
+        tag.frames.insert(title);
+
+        for desc in ["Desc", "Other"] {
+            tag.frames.insert(CommentsFrame {
+                desc: String::from(desc),
+                text: String::from(desc),
+                ..Default::default()
+            });
+        }
+
+        tag.save(dir.join("linked.mp3")).unwrap();
+
+        let mut link = LinkFrame::new(FrameId::new(b"COMM"), "https://example.com/linked.mp3");
+        assert_eq!(link.resolve(&dir).unwrap().len(), 2);
+
+        link.id_data.push(String::from("xxxDesc"));
+        let frames = link.resolve(&dir).unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].key(), "COMM:Desc:xxx");
+
+        let link = LinkFrame::new(FrameId::new(b"TIT2"), "https://example.com/linked.mp3?x=1");
+        assert_eq!(link.resolve(&dir).unwrap()[0].to_string(), "Title");
+
+        let link = LinkFrame::new(FrameId::new(b"TIT2"), "https://example.com/linked%2Emp3");
+        assert_eq!(link.resolve(&dir).unwrap()[0].to_string(), "Title");
+
+        for url in [
+            "https://example.com/",
+            "https://example.com/..",
+            "https://example.com/.",
+            "https://example.com/%2E%2E",
+            "https://example.com/..%2Flinked.mp3",
+            "https://example.com/..\\linked.mp3",
+            "https://example.com/linked%2",
+        ] {
+            let link = LinkFrame::new(FrameId::new(b"TIT2"), url);
+            assert!(matches!(link.resolve(&dir), Err(ParseError::NotFound)));
+        }
+    }
+}
@@ -182,8 +182,10 @@ impl Display for SyncedLyricsFrame {
 }
 
 byte_enum! {
+    #[derive(Default)]
     pub enum SyncedContentType {
         Other = 0x00,
+        #[default]
         Lyrics = 0x01,
         TextTranscription = 0x02,
         Movement = 0x03,
@@ -196,12 +198,6 @@ byte_enum! {
     SyncedContentType::Other
 }
 
-impl Default for SyncedContentType {
-    fn default() -> Self {
-        SyncedContentType::Lyrics
-    }
-}
-
 #[derive(Debug, Clone, Eq, PartialEq, Default)]
 pub struct SyncedText {
     pub text: String,
@@ -212,7 +212,9 @@ impl Display for CommercialFrame {
 }
 
 byte_enum! {
+    #[derive(Default)]
     pub enum ItemType {
+        #[default]
         Other = 0x00,
         StandardCd = 0x01,
         CompressedCd = 0x02,
@@ -226,12 +228,6 @@ byte_enum! {
     ItemType::Other
 }
 
-impl Default for ItemType {
-    fn default() -> Self {
-        Self::Other
-    }
-}
-
 #[derive(Eq, PartialEq, Ord, PartialOrd, Debug, Clone, Copy)]
 pub struct Date([u8; 8]);
 
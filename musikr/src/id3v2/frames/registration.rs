@@ -0,0 +1,188 @@
+//! Frames that register symbols used by other frames.
+
+use crate::core::io::BufStream;
+use crate::core::string::{self, Encoding};
+use crate::id3v2::frames::{Frame, FrameId};
+use crate::id3v2::{ParseResult, TagHeader};
+use log::warn;
+use std::fmt::{self, Display, Formatter};
+
+/// A frame that registers a group symbol.
+///
+/// Frames belong to a group if their [`FrameFlags::group`](crate::id3v2::frames::FrameFlags)
+/// is the same as the `symbol` of this frame. All frames in a group can be found with
+/// [`FrameMap::get_group`](crate::id3v2::collections::FrameMap::get_group).
+#[derive(Default, Debug, Clone)]
+pub struct GroupIdFrame {
+    pub owner: String,
+    pub symbol: u8,
+    pub data: Vec<u8>,
+}
+
+impl GroupIdFrame {
+    pub(crate) fn parse(stream: &mut BufStream) -> ParseResult<Self> {
+        let owner = string::read_terminated(Encoding::Latin1, stream);
+        let symbol = stream.read_u8()?;
+
+        // Symbols below 0x80 are reserved, but some taggers still use them.
+        if symbol < 0x80 {
+            warn!("group symbol {:#x} is reserved", symbol);
+        }
+
+        let data = stream.take_rest().to_vec();
+
+        Ok(Self {
+            owner,
+            symbol,
+            data,
+        })
+    }
+}
+
+impl Frame for GroupIdFrame {
+    fn id(&self) -> FrameId {
+        FrameId::new(b"GRID")
+    }
+
+    fn key(&self) -> String {
+        format!["GRID:{}", self.owner]
+    }
+
+    fn is_empty(&self) -> bool {
+        self.owner.is_empty()
+    }
+
+    fn render(&self, _: &TagHeader) -> Vec<u8> {
+        let mut result = Vec::new();
+
+        result.extend(string::render_terminated(Encoding::Latin1, &self.owner));
+        result.push(self.symbol);
+        result.extend(self.data.iter());
+
+        result
+    }
+}
+
+impl Display for GroupIdFrame {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write![f, "{}", self.owner]
+    }
+}
+
+/// A frame that registers an encryption method symbol.
+///
+/// Frames are encrypted with this method if their
+/// [`FrameFlags::encryption`](crate::id3v2::frames::FrameFlags) is the same as the `method`
+/// of this frame. The `data` is specific to the method, and is passed to a
+/// [`FrameDecryptor`](crate::id3v2::frames::FrameDecryptor) alongside the rest of this frame.
+#[derive(Default, Debug, Clone)]
+pub struct EncryptionMethodFrame {
+    pub owner: String,
+    pub method: u8,
+    pub data: Vec<u8>,
+}
+
+impl EncryptionMethodFrame {
+    pub(crate) fn parse(stream: &mut BufStream) -> ParseResult<Self> {
+        let owner = string::read_terminated(Encoding::Latin1, stream);
+        let method = stream.read_u8()?;
+
+        // Like group symbols, method symbols below 0x80 are reserved.
+        if method < 0x80 {
+            warn!("method symbol {:#x} is reserved", method);
+        }
+
+        let data = stream.take_rest().to_vec();
+
+        Ok(Self {
+            owner,
+            method,
+            data,
+        })
+    }
+}
+
+impl Frame for EncryptionMethodFrame {
+    fn id(&self) -> FrameId {
+        FrameId::new(b"ENCR")
+    }
+
+    fn key(&self) -> String {
+        format!["ENCR:{}", self.owner]
+    }
+
+    fn is_empty(&self) -> bool {
+        self.owner.is_empty()
+    }
+
+    fn render(&self, _: &TagHeader) -> Vec<u8> {
+        let mut result = Vec::new();
+
+        result.extend(string::render_terminated(Encoding::Latin1, &self.owner));
+        result.push(self.method);
+        result.extend(self.data.iter());
+
+        result
+    }
+}
+
+impl Display for EncryptionMethodFrame {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write![f, "{}", self.owner]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRID_DATA: &[u8] = b"GRID\x00\x00\x00\x1B\x00\x00\
+                               https://example.com\0\
+                               \x80\
+                               \x16\x16\x16\x16\x16\x16";
+
+    #[test]
+    fn parse_grid() {
+        make_frame!(GroupIdFrame, GRID_DATA, frame);
+
+        assert_eq!(frame.owner, "https://example.com");
+        assert_eq!(frame.symbol, 0x80);
+        assert_eq!(frame.data, b"\x16\x16\x16\x16\x16\x16");
+    }
+
+    #[test]
+    fn render_grid() {
+        let frame = GroupIdFrame {
+            owner: String::from("https://example.com"),
+            symbol: 0x80,
+            data: b"\x16\x16\x16\x16\x16\x16".to_vec(),
+        };
+
+        assert_render!(frame, GRID_DATA);
+    }
+
+    const ENCR_DATA: &[u8] = b"ENCR\x00\x00\x00\x0C\x00\x00\
+                               example\0\
+                               \x81\
+                               \x16\x16\x16";
+
+    #[test]
+    fn parse_encr() {
+        make_frame!(EncryptionMethodFrame, ENCR_DATA, frame);
+
+        assert_eq!(frame.owner, "example");
+        assert_eq!(frame.method, 0x81);
+        assert_eq!(frame.data, b"\x16\x16\x16");
+    }
+
+    #[test]
+    fn render_encr() {
+        let frame = EncryptionMethodFrame {
+            owner: String::from("example"),
+            method: 0x81,
+            data: b"\x16\x16\x16".to_vec(),
+        };
+
+        assert_render!(frame, ENCR_DATA);
+    }
+}
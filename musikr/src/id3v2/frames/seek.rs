@@ -0,0 +1,502 @@
+//! Frames for seeking within a file.
+
+use crate::core::io::BufStream;
+use crate::id3v2::frames::{Frame, FrameId};
+use crate::id3v2::{ParseError, ParseResult, TagHeader};
+use log::error;
+use std::fmt::{self, Display, Formatter};
+
+/// A frame that points to another tag later in the file.
+///
+/// The offset is the minimum amount of bytes from the end of this tag to the start
+/// of the next tag. [`Tag::open`](crate::id3v2::Tag::open) will follow this frame
+/// and merge the tag it points to.
+#[derive(Default, Debug, Clone)]
+pub struct SeekFrame {
+    pub offset: u32,
+}
+
+impl SeekFrame {
+    pub(crate) fn parse(stream: &mut BufStream) -> ParseResult<Self> {
+        let offset = stream.read_be_u32()?;
+
+        Ok(Self { offset })
+    }
+}
+
+impl Frame for SeekFrame {
+    fn id(&self) -> FrameId {
+        FrameId::new(b"SEEK")
+    }
+
+    fn key(&self) -> String {
+        String::from("SEEK")
+    }
+
+    fn is_empty(&self) -> bool {
+        false
+    }
+
+    fn render(&self, _: &TagHeader) -> Vec<u8> {
+        self.offset.to_be_bytes().to_vec()
+    }
+}
+
+impl Display for SeekFrame {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write![f, "{}", self.offset]
+    }
+}
+
+/// A frame that maps the MPEG frames of a file to byte offsets and times.
+///
+/// Each reference is `bytes` and `millis` after the previous one, plus a deviation that is
+/// stored in the amount of bits given by `bytes_bits` and `millis_bits`. The first reference
+/// is after the first MPEG frame. This is mostly useful for seeking in VBR files with
+/// [`MpegLookupFrame::seek`](MpegLookupFrame::seek).
+///
+/// `bytes` and `millis` are written as 24 bits, the deviations can be at most 32 bits, and
+/// `bytes_bits` and `millis_bits` must add up to a multiple of 4. Values that do not fit are
+/// clamped when written.
+#[derive(Default, Debug, Clone)]
+pub struct MpegLookupFrame {
+    pub frames: u16,
+    pub bytes: u32,
+    pub millis: u32,
+    pub bytes_bits: u8,
+    pub millis_bits: u8,
+    pub refs: Vec<MpegReference>,
+}
+
+impl MpegLookupFrame {
+    pub(crate) fn parse(stream: &mut BufStream) -> ParseResult<Self> {
+        let frames = stream.read_be_u16()?;
+        let bytes = read_u24(stream)?;
+        let millis = read_u24(stream)?;
+        let bytes_bits = stream.read_u8()?;
+        let millis_bits = stream.read_u8()?;
+
+        if bytes_bits > 32 || millis_bits > 32 {
+            error!("deviations cannot be larger than 32 bits");
+            return Err(ParseError::MalformedData);
+        }
+
+        if !(bytes_bits + millis_bits).is_multiple_of(4) {
+            error!("deviation bits must add up to a multiple of 4");
+            return Err(ParseError::MalformedData);
+        }
+
+        let data = stream.take_rest();
+        let ref_bits = usize::from(bytes_bits) + usize::from(millis_bits);
+        let mut refs = Vec::new();
+
+        // The table is packed as tightly as possible, so it must be read bit-by-bit. Any bits
+        // that don't make up a full reference are padding.
+        if let Some(count) = (data.len() * 8).checked_div(ref_bits) {
+            let mut pos = 0;
+
+            for _ in 0..count {
+                refs.push(MpegReference {
+                    bytes_deviation: read_bits(data, &mut pos, bytes_bits),
+                    millis_deviation: read_bits(data, &mut pos, millis_bits),
+                })
+            }
+        }
+
+        Ok(Self {
+            frames,
+            bytes,
+            millis,
+            bytes_bits,
+            millis_bits,
+            refs,
+        })
+    }
+
+    /// Returns the byte offset and the time in milliseconds of the last reference at or
+    /// before `millis`.
+    ///
+    /// The offset is relative to the first MPEG frame of the file. If there is no such
+    /// reference, the start of the audio is returned.
+    pub fn seek(&self, millis: u64) -> (u64, u64) {
+        let mut offset = 0;
+        let mut time = 0;
+
+        for reference in &self.refs {
+            let next = time + u64::from(self.millis) + u64::from(reference.millis_deviation);
+
+            if next > millis {
+                break;
+            }
+
+            time = next;
+            offset += u64::from(self.bytes) + u64::from(reference.bytes_deviation);
+        }
+
+        (offset, time)
+    }
+}
+
+impl Frame for MpegLookupFrame {
+    fn id(&self) -> FrameId {
+        FrameId::new(b"MLLT")
+    }
+
+    fn key(&self) -> String {
+        String::from("MLLT")
+    }
+
+    fn is_empty(&self) -> bool {
+        self.refs.is_empty()
+    }
+
+    fn render(&self, _: &TagHeader) -> Vec<u8> {
+        let mut result = Vec::new();
+
+        if self.bytes > U24_MAX || self.millis > U24_MAX {
+            error!("reference distances cannot be larger than 24 bits, clamping them");
+        }
+
+        result.extend(self.frames.to_be_bytes());
+        result.extend(&u32::min(self.bytes, U24_MAX).to_be_bytes()[1..]);
+        result.extend(&u32::min(self.millis, U24_MAX).to_be_bytes()[1..]);
+
+        let bytes_bits = u8::min(self.bytes_bits, 32);
+        let millis_bits = u8::min(self.millis_bits, 32);
+
+        if bytes_bits != self.bytes_bits || millis_bits != self.millis_bits {
+            error!("deviations cannot be larger than 32 bits, clamping them");
+        }
+
+        if !(bytes_bits + millis_bits).is_multiple_of(4) {
+            error!("deviation bits do not add up to a multiple of 4, frame will be malformed");
+        }
+
+        result.push(bytes_bits);
+        result.push(millis_bits);
+
+        let mut table = Vec::new();
+        let mut pos = 0;
+        let mut clamped = false;
+
+        for reference in &self.refs {
+            let bytes_deviation = clamp_bits(reference.bytes_deviation, bytes_bits);
+            let millis_deviation = clamp_bits(reference.millis_deviation, millis_bits);
+
+            clamped |= bytes_deviation != reference.bytes_deviation
+                || millis_deviation != reference.millis_deviation;
+
+            write_bits(&mut table, &mut pos, bytes_deviation, bytes_bits);
+            write_bits(&mut table, &mut pos, millis_deviation, millis_bits);
+        }
+
+        if clamped {
+            error!("deviations do not fit in their bit widths, clamping them");
+        }
+
+        result.extend(table);
+
+        result
+    }
+}
+
+impl Display for MpegLookupFrame {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write![f, "{} references", self.refs.len()]
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, Eq, PartialEq)]
+pub struct MpegReference {
+    pub bytes_deviation: u32,
+    pub millis_deviation: u32,
+}
+
+/// A frame that indexes the audio data of a file for seeking.
+///
+/// The audio data starts `start` bytes after the end of the tag and is `length` bytes long.
+/// The audio is split into equally long segments, with each fraction being the position of
+/// a segment in the audio data as a fraction of `length`. Fractions are either 8 or 16 bits,
+/// as given by `bits`. Any other amount of bits is written as 16 bits.
+///
+/// This frame only exists in ID3v2.4, and is dropped when the tag is downgraded to ID3v2.3.
+#[derive(Default, Debug, Clone)]
+pub struct SeekPointIndexFrame {
+    pub start: u32,
+    pub length: u32,
+    pub bits: u8,
+    pub fractions: Vec<u16>,
+}
+
+impl SeekPointIndexFrame {
+    pub(crate) fn parse(stream: &mut BufStream) -> ParseResult<Self> {
+        let start = stream.read_be_u32()?;
+        let length = stream.read_be_u32()?;
+        let points = stream.read_be_u16()?;
+        let bits = stream.read_u8()?;
+        let mut fractions = Vec::new();
+
+        for _ in 0..points {
+            let fraction = match bits {
+                8 => u16::from(stream.read_u8()?),
+                16 => stream.read_be_u16()?,
+                _ => {
+                    error!("index points can only be 8 or 16 bits");
+                    return Err(ParseError::MalformedData);
+                }
+            };
+
+            fractions.push(fraction)
+        }
+
+        Ok(Self {
+            start,
+            length,
+            bits,
+            fractions,
+        })
+    }
+}
+
+impl Frame for SeekPointIndexFrame {
+    fn id(&self) -> FrameId {
+        FrameId::new(b"ASPI")
+    }
+
+    fn key(&self) -> String {
+        String::from("ASPI")
+    }
+
+    fn is_empty(&self) -> bool {
+        self.fractions.is_empty()
+    }
+
+    fn render(&self, _: &TagHeader) -> Vec<u8> {
+        let mut result = Vec::new();
+        let fractions = &self.fractions[..usize::min(self.fractions.len(), u16::MAX as usize)];
+
+        result.extend(self.start.to_be_bytes());
+        result.extend(self.length.to_be_bytes());
+        result.extend((fractions.len() as u16).to_be_bytes());
+
+        if self.bits == 8 {
+            result.push(8);
+            result.extend(fractions.iter().map(|&fraction| fraction as u8));
+        } else {
+            result.push(16);
+
+            for fraction in fractions {
+                result.extend(fraction.to_be_bytes());
+            }
+        }
+
+        result
+    }
+}
+
+impl Display for SeekPointIndexFrame {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write![f, "{} index points", self.fractions.len()]
+    }
+}
+
+const U24_MAX: u32 = 0xFFFFFF;
+
+fn read_u24(stream: &mut BufStream) -> ParseResult<u32> {
+    let [a, b, c] = stream.read_array()?;
+    Ok(u32::from_be_bytes([0, a, b, c]))
+}
+
+fn read_bits(data: &[u8], pos: &mut usize, n: u8) -> u32 {
+    let mut value = 0;
+
+    for _ in 0..n {
+        let bit = (data[*pos / 8] >> (7 - *pos % 8)) & 1;
+        value = (value << 1) | u32::from(bit);
+        *pos += 1;
+    }
+
+    value
+}
+
+/// Clamps `value` to the largest value that fits in `n` bits, where `n` is at most 32.
+fn clamp_bits(value: u32, n: u8) -> u32 {
+    let max = u32::MAX.checked_shr(32 - u32::from(n)).unwrap_or(0);
+    u32::min(value, max)
+}
+
+fn write_bits(data: &mut Vec<u8>, pos: &mut usize, value: u32, n: u8) {
+    for i in (0..n).rev() {
+        if pos.is_multiple_of(8) {
+            data.push(0);
+        }
+
+        let bit = ((value >> i) & 1) as u8;
+        *data.last_mut().unwrap() |= bit << (7 - *pos % 8);
+        *pos += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id3v2::tag::Version;
+
+    const SEEK_DATA: &[u8] = b"SEEK\x00\x00\x00\x04\x00\x00\
+                               \x00\x01\x16\x16";
+
+    #[test]
+    fn parse_seek() {
+        make_frame!(SeekFrame, SEEK_DATA, frame);
+
+        assert_eq!(frame.offset, 0x11616);
+    }
+
+    #[test]
+    fn render_seek() {
+        let frame = SeekFrame { offset: 0x11616 };
+
+        assert_render!(frame, SEEK_DATA);
+    }
+
+    const MLLT_DATA: &[u8] = b"MLLT\x00\x00\x00\x0D\x00\x00\
+                               \x00\x02\
+                               \x00\x04\x00\
+                               \x00\x00\x1A\
+                               \x06\x06\
+                               \x04\x20\xC4";
+
+    const ASPI_DATA: &[u8] = b"ASPI\x00\x00\x00\x0E\x00\x00\
+                               \x00\x00\x10\x00\
+                               \x00\x10\x00\x00\
+                               \x00\x03\
+                               \x08\
+                               \x00\x80\xFF";
+
+    const ASPI_DATA_16: &[u8] = b"ASPI\x00\x00\x00\x0F\x00\x00\
+                                  \x00\x00\x10\x00\
+                                  \x00\x10\x00\x00\
+                                  \x00\x02\
+                                  \x10\
+                                  \x00\x00\xAB\xCD";
+
+    #[test]
+    fn parse_aspi() {
+        make_frame!(SeekPointIndexFrame, ASPI_DATA, frame);
+
+        assert_eq!(frame.start, 0x1000);
+        assert_eq!(frame.length, 0x100000);
+        assert_eq!(frame.bits, 8);
+        assert_eq!(frame.fractions, [0x00, 0x80, 0xFF]);
+    }
+
+    #[test]
+    fn parse_aspi_16() {
+        make_frame!(SeekPointIndexFrame, ASPI_DATA_16, frame);
+
+        assert_eq!(frame.bits, 16);
+        assert_eq!(frame.fractions, [0x0000, 0xABCD]);
+    }
+
+    #[test]
+    fn render_aspi() {
+        let frame = SeekPointIndexFrame {
+            start: 0x1000,
+            length: 0x100000,
+            bits: 8,
+            fractions: vec![0x00, 0x80, 0xFF],
+        };
+
+        assert_render!(frame, ASPI_DATA);
+
+        let frame = SeekPointIndexFrame {
+            start: 0x1000,
+            length: 0x100000,
+            bits: 16,
+            fractions: vec![0x0000, 0xABCD],
+        };
+
+        assert_render!(frame, ASPI_DATA_16);
+    }
+
+    #[test]
+    fn parse_mllt() {
+        make_frame!(MpegLookupFrame, MLLT_DATA, frame);
+
+        assert_eq!(frame.frames, 2);
+        assert_eq!(frame.bytes, 1024);
+        assert_eq!(frame.millis, 26);
+        assert_eq!(frame.bytes_bits, 6);
+        assert_eq!(frame.millis_bits, 6);
+        assert_eq!(
+            frame.refs,
+            [
+                MpegReference {
+                    bytes_deviation: 1,
+                    millis_deviation: 2
+                },
+                MpegReference {
+                    bytes_deviation: 3,
+                    millis_deviation: 4
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn render_mllt() {
+        let frame = MpegLookupFrame {
+            frames: 2,
+            bytes: 1024,
+            millis: 26,
+            bytes_bits: 6,
+            millis_bits: 6,
+            refs: vec![
+                MpegReference {
+                    bytes_deviation: 1,
+                    millis_deviation: 2,
+                },
+                MpegReference {
+                    bytes_deviation: 3,
+                    millis_deviation: 4,
+                },
+            ],
+        };
+
+        assert_render!(frame, MLLT_DATA);
+
+        // Values that are too large are clamped instead of being truncated.
+        let frame = MpegLookupFrame {
+            bytes: 0x1000000,
+            bytes_bits: 4,
+            millis_bits: 4,
+            refs: vec![MpegReference {
+                bytes_deviation: 0x1F,
+                millis_deviation: 0x2,
+            }],
+            ..Default::default()
+        };
+
+        let data = frame.render(&TagHeader::with_version(Version::V24));
+        assert_eq!(data, b"\x00\x00\xFF\xFF\xFF\x00\x00\x00\x04\x04\xF2");
+    }
+
+    #[test]
+    fn parse_mllt_widths() {
+        // The deviations of a reference must add up to a multiple of 4 bits.
+        let data = b"\x00\x02\x00\x04\x00\x00\x00\x1A\x06\x05\x04\x20";
+        let result = MpegLookupFrame::parse(&mut BufStream::new(data));
+
+        assert!(matches!(result, Err(ParseError::MalformedData)));
+    }
+
+    #[test]
+    fn seek_mllt() {
+        make_frame!(MpegLookupFrame, MLLT_DATA, frame);
+
+        assert_eq!(frame.seek(0), (0, 0));
+        assert_eq!(frame.seek(30), (1025, 28));
+        assert_eq!(frame.seek(58), (2052, 58));
+        assert_eq!(frame.seek(1000), (2052, 58));
+    }
+}
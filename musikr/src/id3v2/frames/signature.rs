@@ -0,0 +1,87 @@
+//! Frames that sign groups of frames.
+
+use crate::core::io::BufStream;
+use crate::id3v2::frames::{Frame, FrameId};
+use crate::id3v2::{ParseResult, TagHeader};
+use std::fmt::{self, Display, Formatter};
+
+/// A frame that signs a group of frames.
+///
+/// The signed frames are all frames whose [`FrameFlags::group`](crate::id3v2::frames::FrameFlags)
+/// is the same as the `symbol` of this frame. The method of signing is not defined by the spec,
+/// so signatures are checked with a [`SignatureVerifier`](crate::id3v2::frames::SignatureVerifier)
+/// through [`FrameMap::verify_group`](crate::id3v2::collections::FrameMap::verify_group).
+#[derive(Default, Debug, Clone)]
+pub struct SignatureFrame {
+    pub symbol: u8,
+    pub signature: Vec<u8>,
+}
+
+impl SignatureFrame {
+    pub(crate) fn parse(stream: &mut BufStream) -> ParseResult<Self> {
+        let symbol = stream.read_u8()?;
+        let signature = stream.take_rest().to_vec();
+
+        Ok(Self { symbol, signature })
+    }
+}
+
+impl Frame for SignatureFrame {
+    fn id(&self) -> FrameId {
+        FrameId::new(b"SIGN")
+    }
+
+    fn key(&self) -> String {
+        // Groups can be signed more than once, so the signature itself is part of the key.
+        let signature: String = self
+            .signature
+            .iter()
+            .map(|byte| format!["{:02x}", byte])
+            .collect();
+        format!["SIGN:{}:{}", self.symbol, signature]
+    }
+
+    fn is_empty(&self) -> bool {
+        self.signature.is_empty()
+    }
+
+    fn render(&self, _: &TagHeader) -> Vec<u8> {
+        let mut result = vec![self.symbol];
+        result.extend(self.signature.iter());
+        result
+    }
+}
+
+impl Display for SignatureFrame {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write![f, "{:#x}", self.symbol]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIGN_DATA: &[u8] = b"SIGN\x00\x00\x00\x05\x00\x00\
+                               \x80\
+                               \x16\x16\x16\x16";
+
+    #[test]
+    fn parse_sign() {
+        make_frame!(SignatureFrame, SIGN_DATA, frame);
+
+        assert_eq!(frame.symbol, 0x80);
+        assert_eq!(frame.signature, b"\x16\x16\x16\x16");
+        assert_eq!(frame.key(), "SIGN:128:16161616");
+    }
+
+    #[test]
+    fn render_sign() {
+        let frame = SignatureFrame {
+            symbol: 0x80,
+            signature: b"\x16\x16\x16\x16".to_vec(),
+        };
+
+        assert_render!(frame, SIGN_DATA);
+    }
+}
@@ -5,18 +5,18 @@
 //! couple of details are common across all implementations:
 //!
 //! - Text frames expose an encoding that will be used when the frame is written. More information can be found
-//! in [`Encoding`](crate::core::Encoding).
+//!   in [`Encoding`](crate::core::Encoding).
 //! - A text frame implementation can correspond to multiple Frame IDs.
 //! - A text frame can contain more than one string.
 //!
 //! # Quirks
 //!
 //! - Certain text frames may be an iTunes extension or only exist in a specific ID3v2 version. If this is the case,
-//! then it will be marked accordingly.
+//!   then it will be marked accordingly.
 //! - According to the standard, ID3v2.3 text frames cannot have multiple fields delimited by a null terminator.
-//! While musikr does not enforce this restriction, some taggers might.
+//!   While musikr does not enforce this restriction, some taggers might.
 //! - `UserTextFrame` is not meant to have multiple fields, however the other major tagging libraries all seem to
-//! enable this, so musikr implements it regardless.
+//!   enable this, so musikr implements it regardless.
 
 use crate::core::io::BufStream;
 use crate::core::string::{self, Encoding};
@@ -419,7 +419,7 @@ impl CreditsFrame {
         let encoding = encoding::parse(stream)?;
         let mut text = parse_text(encoding, stream);
 
-        if text.len() % 2 != 0 {
+        if !text.len().is_multiple_of(2) {
             // The spec says that IPLS/TIPL/TMCL must contain an even number of entries.
             // If this frame does have an incomplete pair, we just pop it off and move on.
             info!(
@@ -171,7 +171,7 @@ macro_rules! make_frame {
         )
         .unwrap();
 
-        let frame = if let crate::id3v2::frames::ParsedFrame::Frame(frame) = parsed {
+        let frame = if let crate::id3v2::frames::ParsedFrame::Frame(frame, _) = parsed {
             frame
         } else {
             panic!("cannot parse frame: {:?}", parsed)
@@ -188,7 +188,9 @@ macro_rules! assert_render {
         assert_eq!(
             crate::id3v2::frames::render(
                 &crate::id3v2::tag::TagHeader::with_version(crate::id3v2::tag::Version::V24),
-                &$frame
+                &$frame,
+                &crate::id3v2::frames::FrameFlags::default(),
+                None
             )
             .unwrap(),
             $data
@@ -0,0 +1,271 @@
+//! Enforcement of ID3v2.4 tag restrictions.
+
+use crate::core::io::{BufStream, RestrictionMode};
+use crate::core::string::Encoding;
+use crate::id3v2::collections::FrameMap;
+use crate::id3v2::frames::{
+    AttachedPictureFrame, CommentsFrame, CommercialFrame, CreditsFrame, Frame, GeneralObjectFrame,
+    OwnershipFrame, SyncedLyricsFrame, TermsOfUseFrame, TextFrame, UnsyncLyricsFrame,
+    UserTextFrame, UserUrlFrame,
+};
+use crate::id3v2::tag::{
+    ImageEncodingRestriction, ImageSizeRestriction, Restrictions, TagSizeRestriction,
+    TextEncodingRestriction, TextSizeRestriction,
+};
+use crate::id3v2::{SaveError, SaveResult};
+use log::{error, warn};
+
+/// Checks the frames of a tag against `restrictions`. In [`RestrictionMode::Enforce`], text is
+/// re-encoded and truncated and pictures that cannot comply are removed. Any other violation
+/// results in an error.
+pub(crate) fn apply(
+    frames: &mut FrameMap,
+    unknowns: usize,
+    restrictions: Restrictions,
+    mode: RestrictionMode,
+) -> SaveResult<()> {
+    let enforce = mode == RestrictionMode::Enforce;
+    let mut valid = true;
+
+    for frame in frames.values_mut() {
+        if restrictions.text_encoding == TextEncodingRestriction::Latin1OrUtf8 {
+            if let Some(encoding) = encoding_mut(frame) {
+                if !matches!(encoding, Encoding::Latin1 | Encoding::Utf8) {
+                    if enforce {
+                        *encoding = Encoding::Utf8;
+                    } else {
+                        error!("{} is not encoded in Latin1 or UTF-8", frame.key());
+                        valid = false;
+                    }
+                }
+            }
+        }
+
+        if let Some(max) = max_text_len(restrictions.text_size) {
+            if let Some(text) = text_mut(frame) {
+                if text.iter().any(|string| string.chars().count() > max) {
+                    if enforce {
+                        truncate(text, max);
+                    } else {
+                        error!(
+                            "a string in {} is longer than {} characters",
+                            frame.key(),
+                            max
+                        );
+                        valid = false;
+                    }
+                }
+            }
+        }
+    }
+
+    // Pictures cannot be converted or resized, so the best we can do is remove them.
+    let mut pictures_valid = true;
+
+    frames.retain(
+        |key, frame| match frame.downcast::<AttachedPictureFrame>() {
+            Some(picture) if !is_valid_picture(picture, restrictions) => {
+                if enforce {
+                    warn!(
+                        "removing {}, which does not meet the image restrictions",
+                        key
+                    );
+                } else {
+                    error!("{} does not meet the image restrictions", key);
+                    pictures_valid = false;
+                }
+
+                !enforce
+            }
+            _ => true,
+        },
+    );
+
+    let (max_frames, _) = max_tag_size(restrictions.tag_size);
+
+    if frames.len() + unknowns > max_frames {
+        error!("tag has more than {} frames", max_frames);
+        valid = false;
+    }
+
+    match valid && pictures_valid {
+        true => Ok(()),
+        false => Err(SaveError::Restricted),
+    }
+}
+
+/// Returns the maximum amount of frames and the maximum size in bytes of a tag.
+pub(crate) fn max_tag_size(restriction: TagSizeRestriction) -> (usize, u64) {
+    match restriction {
+        TagSizeRestriction::Max128Frames1Mb => (128, 1024 * 1024),
+        TagSizeRestriction::Max64Frames128Kb => (64, 128 * 1024),
+        TagSizeRestriction::Max32Frames40Kb => (32, 40 * 1024),
+        TagSizeRestriction::Max32Frames4Kb => (32, 4 * 1024),
+    }
+}
+
+fn max_text_len(restriction: TextSizeRestriction) -> Option<usize> {
+    match restriction {
+        TextSizeRestriction::None => None,
+        TextSizeRestriction::LessThan1024Chars => Some(1024),
+        TextSizeRestriction::LessThan128Chars => Some(128),
+        TextSizeRestriction::LessThan30Chars => Some(30),
+    }
+}
+
+fn encoding_mut(frame: &mut dyn Frame) -> Option<&mut Encoding> {
+    macro_rules! encoding_of {
+        ($($frame:ty),*) => {
+            $(
+                if frame.is::<$frame>() {
+                    return frame.downcast_mut::<$frame>().map(|frame| &mut frame.encoding);
+                }
+            )*
+        };
+    }
+
+    encoding_of!(
+        TextFrame,
+        UserTextFrame,
+        CreditsFrame,
+        CommentsFrame,
+        UserUrlFrame,
+        AttachedPictureFrame,
+        GeneralObjectFrame,
+        UnsyncLyricsFrame,
+        SyncedLyricsFrame,
+        OwnershipFrame,
+        TermsOfUseFrame,
+        CommercialFrame
+    );
+
+    None
+}
+
+fn text_mut(frame: &mut dyn Frame) -> Option<&mut Vec<String>> {
+    // The text size restriction only applies to text frames. Descriptions are left alone
+    // since they are part of the frame key.
+    if frame.is::<TextFrame>() {
+        return frame
+            .downcast_mut::<TextFrame>()
+            .map(|frame| &mut frame.text);
+    }
+
+    frame
+        .downcast_mut::<UserTextFrame>()
+        .map(|frame| &mut frame.text)
+}
+
+fn truncate(text: &mut [String], max: usize) {
+    // The restriction applies to each string on its own, rather than to all of them combined.
+    for string in text.iter_mut() {
+        if let Some((end, _)) = string.char_indices().nth(max) {
+            string.truncate(end);
+        }
+    }
+}
+
+fn is_valid_picture(frame: &AttachedPictureFrame, restrictions: Restrictions) -> bool {
+    let mime = frame.mime.to_lowercase();
+
+    if restrictions.image_encoding == ImageEncodingRestriction::OnlyPngOrJpeg
+        && mime != "image/png"
+        && mime != "image/jpeg"
+    {
+        return false;
+    }
+
+    if restrictions.image_size == ImageSizeRestriction::None {
+        return true;
+    }
+
+    match image_size(&frame.picture) {
+        Some((width, height)) => match restrictions.image_size {
+            ImageSizeRestriction::LessThan256x256 => width <= 256 && height <= 256,
+            ImageSizeRestriction::LessThan64x64 => width <= 64 && height <= 64,
+            ImageSizeRestriction::Exactly64x64 => width == 64 && height == 64,
+            ImageSizeRestriction::None => true,
+        },
+        None => false,
+    }
+}
+
+/// Returns the dimensions of a PNG or JPEG image.
+fn image_size(data: &[u8]) -> Option<(u32, u32)> {
+    let mut stream = BufStream::new(data);
+
+    match stream.read_array().ok()? {
+        // The first chunk of a PNG is always IHDR, which starts with the dimensions.
+        [0x89, b'P', b'N', b'G'] => {
+            stream.skip(12).ok()?;
+            Some((stream.read_be_u32().ok()?, stream.read_be_u32().ok()?))
+        }
+
+        [0xFF, 0xD8, ..] => {
+            let mut stream = BufStream::new(&data[2..]);
+
+            // Walk the segments until the start of frame, which contains the dimensions.
+            loop {
+                if stream.read_u8().ok()? != 0xFF {
+                    return None;
+                }
+
+                // Markers can be preceded by any amount of fill bytes.
+                let mut marker = stream.read_u8().ok()?;
+
+                while marker == 0xFF {
+                    marker = stream.read_u8().ok()?;
+                }
+
+                match marker {
+                    // Standalone markers have no length.
+                    0x01 | 0xD0..=0xD7 => continue,
+                    // These share the range of SOF markers, but are not SOF markers.
+                    0xC4 | 0xC8 | 0xCC => {}
+                    0xC0..=0xCF => {
+                        // Skip the length and sample precision.
+                        stream.skip(3).ok()?;
+
+                        let height = stream.read_be_u16().ok()?;
+                        let width = stream.read_be_u16().ok()?;
+
+                        return Some((u32::from(width), u32::from(height)));
+                    }
+                    _ => {}
+                }
+
+                let len = stream.read_be_u16().ok()? as usize;
+                stream.skip(len.checked_sub(2)?).ok()?;
+            }
+        }
+
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_image_size() {
+        let png = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0DIHDR\x00\x00\x01\x00\x00\x00\x00\x40";
+        let jpeg = b"\xFF\xD8\xFF\xE0\x00\x04\x16\x16\xFF\xFF\xC0\x00\x0B\x08\x00\x40\x00\x20";
+
+        assert_eq!(image_size(png), Some((256, 64)));
+        assert_eq!(image_size(jpeg), Some((32, 64)));
+        assert_eq!(image_size(b"GIF89a"), None);
+    }
+
+    #[test]
+    fn truncate_text() {
+        let mut text = vec![
+            String::from("Ünïcödé"),
+            String::from("Text"),
+            String::from("More"),
+        ];
+        truncate(&mut text, 5);
+
+        assert_eq!(text, ["Ünïcö", "Text", "More"]);
+    }
+}
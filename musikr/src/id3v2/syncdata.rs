@@ -20,9 +20,9 @@ pub fn to_u28(raw: [u8; 4]) -> u32 {
 pub fn to_u35(mut raw: [u8; 5]) -> u32 {
     let mut sum: u32 = 0;
 
-    // Remove the last 5 bits of the first byte so that we don't overflow the u32.
+    // Remove the upper 3 bits of the first byte so that we don't overflow the u32.
     // The spec says that these bits shouldn't be used, so this is okay.
-    raw[0] &= 0x7;
+    raw[0] &= 0xF;
 
     for (i, &byte) in raw.iter().enumerate() {
         sum |= u32::from(byte) << ((4 - i) * 7);
@@ -79,9 +79,13 @@ pub fn decode(src: &mut BufStream) -> Vec<u8> {
     }
 
     // Since we have to look ahead, we'll sometimes need to add a lone u8 that wasnt able
-    // to be added initially.
+    // to be added initially. This may also be the 0x00 that guards a trailing 0xFF.
     if src.remaining() == 1 {
-        dest.push(src.read_u8().unwrap());
+        let cur = src.read_u8().unwrap();
+
+        if !(last == 0xFF && cur == 0x00) {
+            dest.push(cur);
+        }
     }
 
     dest.shrink_to_fit();
@@ -89,8 +93,30 @@ pub fn decode(src: &mut BufStream) -> Vec<u8> {
     dest
 }
 
+/// Returns a `Vec<u8>` encoded with the ID3v2 synchronization scheme from `src`.
+///
+/// A 0x00 byte is inserted after every 0xFF byte that is followed by a byte that could form
+/// a false sync [0xE0 or above] or by 0x00, along with after a trailing 0xFF.
+pub fn encode(src: &[u8]) -> Vec<u8> {
+    let mut dest = Vec::with_capacity(src.len());
+
+    for (i, &cur) in src.iter().enumerate() {
+        dest.push(cur);
+
+        if cur == 0xFF {
+            match src.get(i + 1) {
+                Some(&next) if next < 0xE0 && next != 0x00 => {}
+                _ => dest.push(0x00),
+            }
+        }
+    }
+
+    dest
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
     use crate::id3v2::Tag;
     use std::env;
 
@@ -108,4 +134,13 @@ mod tests {
         assert_eq!(tag.frames["TRCK"].to_string(), "03");
         assert_eq!(tag.frames["TLEN"].to_string(), "216000");
     }
+
+    #[test]
+    fn encode_unsync_data() {
+        let data = b"\x16\xFF\xE0\x16\xFF\x00\xFF\x16\xFF";
+        let encoded = encode(data);
+
+        assert_eq!(encoded, b"\x16\xFF\x00\xE0\x16\xFF\x00\x00\xFF\x16\xFF\x00");
+        assert_eq!(decode(&mut BufStream::new(&encoded)), data);
+    }
 }
@@ -8,6 +8,7 @@ use log::error;
 use std::fmt::{self, Display, Formatter};
 
 const ID: &[u8] = b"ID3";
+const FOOTER_ID: &[u8] = b"3DI";
 
 #[derive(Clone, Debug)]
 pub struct TagHeader {
@@ -24,6 +25,27 @@ impl TagHeader {
             return Err(ParseError::NotFound);
         }
 
+        Self::parse_body(raw)
+    }
+
+    /// Parses an ID3v2.4 footer, which is identical to the header except for its identifier.
+    pub(crate) fn parse_footer(raw: [u8; 10]) -> ParseResult<Self> {
+        if &raw[0..3] != FOOTER_ID {
+            error!("no id3v2 footer identifier found");
+            return Err(ParseError::NotFound);
+        }
+
+        let footer = Self::parse_body(raw)?;
+
+        if footer.version != Version::V24 || !footer.flags.footer {
+            error!("footers are only valid on ID3v2.4 tags with the footer flag set");
+            return Err(ParseError::MalformedData);
+        }
+
+        Ok(footer)
+    }
+
+    fn parse_body(raw: [u8; 10]) -> ParseResult<Self> {
         // Get the version of this tag.
         let version = match (raw[3], raw[4]) {
             (2, 0) => Version::V22,
@@ -94,6 +116,19 @@ impl TagHeader {
         header
     }
 
+    pub(crate) fn render_footer(&mut self) -> [u8; 10] {
+        let mut footer = self.render();
+        footer[0..3].copy_from_slice(FOOTER_ID);
+        footer
+    }
+
+    /// Returns whether `footer` describes the same tag as this header.
+    pub(crate) fn matches_footer(&self, footer: &TagHeader) -> bool {
+        self.version == footer.version
+            && self.tag_size == footer.tag_size
+            && self.flags == footer.flags
+    }
+
     pub(crate) fn with_version(version: Version) -> Self {
         Self {
             version,
@@ -128,7 +163,7 @@ impl TagHeader {
 }
 
 /// The overall flags for a tag. This is meant for internal use.
-#[derive(Default, Clone, Copy, Debug)]
+#[derive(Default, Clone, Copy, Debug, Eq, PartialEq)]
 pub(crate) struct TagFlags {
     pub unsync: bool,
     pub extended: bool,
@@ -185,6 +220,8 @@ pub enum SaveVersion {
 #[derive(Default, Debug, Clone)]
 pub struct ExtendedHeader {
     pub padding_size: Option<u32>,
+    /// The CRC-32 of the tag data. If this is present, it will be recomputed when the tag
+    /// is saved. It can be checked against the tag data with [`Tag::verify_crc`](crate::id3v2::Tag::verify_crc).
     pub crc32: Option<u32>,
     pub is_update: bool,
     pub restrictions: Option<Restrictions>,
@@ -224,6 +261,41 @@ impl ExtendedHeader {
     }
 }
 
+/// The CRC-32 used by ID3v2, which has a reflected polynomial of `0xEDB88320`, and an initial
+/// value and final XOR of `0xFFFFFFFF`.
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, &byte| {
+        (crc >> 8) ^ CRC_TABLE[(crc as u8 ^ byte) as usize]
+    })
+}
+
+static CRC_TABLE: [u32; 256] = make_crc_table();
+
+const fn make_crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+}
+
 fn parse_ext_v3(stream: &mut BufStream) -> ParseResult<ExtendedHeader> {
     let size = stream.read_be_u32()?;
 
@@ -295,8 +367,8 @@ fn parse_ext_v4(stream: &mut BufStream) -> ParseResult<ExtendedHeader> {
         header.crc32 = Some(syncdata::to_u35(stream.read_array()?));
     }
 
-    // Tag restrictions. Musikr doesn't really do anything with these since according to the spec
-    // they are only flags for when the tag was *encoded*, now how it should *decode*.
+    // Tag restrictions. According to the spec these are only flags for when the tag was *encoded*,
+    // not how it should *decode*, so they are only checked when the tag is saved.
     if flags & 0x10 != 0 {
         // Restrictions must be 1 byte in length.
         if stream.read_u8()? != 1 {
@@ -334,7 +406,7 @@ fn parse_ext_v4(stream: &mut BufStream) -> ParseResult<ExtendedHeader> {
             _ => unreachable!(),
         };
 
-        let image_size = match (restrictions & 0x3) >> 1 {
+        let image_size = match restrictions & 0x3 {
             0 => ImageSizeRestriction::None,
             1 => ImageSizeRestriction::LessThan256x256,
             2 => ImageSizeRestriction::LessThan64x64,
@@ -403,7 +475,7 @@ fn render_ext_v4(header: &ExtendedHeader) -> Vec<u8> {
         bits |= (restrictions.text_encoding as u8) << 5;
         bits |= (restrictions.text_size as u8) << 3;
         bits |= (restrictions.image_encoding as u8) << 2;
-        bits |= (restrictions.image_size as u8) << 1;
+        bits |= restrictions.image_size as u8;
 
         data.push(bits)
     }
@@ -464,6 +536,11 @@ mod tests {
     const EXT_DATA_V3: &[u8] = b"\x00\x00\x00\x0A\x80\x00\xAB\xCD\xEF\x16\x16\x16\x16\x16";
     const EXT_DATA_V4: &[u8] = b"\x00\x00\x00\x0F\x01\x70\x00\x05\x07\x5E\x37\x5E\x16\x01\xB4";
 
+    #[test]
+    fn crc() {
+        assert_eq!(crc32(b"123456789"), 0xCBF43926);
+    }
+
     #[test]
     fn parse_v3_tag_header() {
         let data = b"\x49\x44\x33\x03\x00\xA0\x00\x08\x49\x30";
@@ -478,6 +555,25 @@ mod tests {
         assert!(flags.experimental);
     }
 
+    #[test]
+    fn parse_v4_tag_footer() {
+        let data = b"\x33\x44\x49\x04\x00\x10\x00\x08\x49\x30";
+        let mut footer = TagHeader::parse_footer(*data).unwrap();
+
+        assert_eq!(footer.size(), 140464);
+        assert!(footer.flags().footer);
+        assert_eq!(&footer.render_footer(), data);
+
+        let mut header = TagHeader::parse(*b"\x49\x44\x33\x04\x00\x10\x00\x08\x49\x30").unwrap();
+        assert!(header.matches_footer(&footer));
+
+        *header.size_mut() = 1;
+        assert!(!header.matches_footer(&footer));
+
+        assert!(TagHeader::parse_footer(*b"\x33\x44\x49\x03\x00\x10\x00\x08\x49\x30").is_err());
+        assert!(TagHeader::parse_footer(*b"\x33\x44\x49\x04\x00\x00\x00\x08\x49\x30").is_err());
+    }
+
     #[test]
     fn parse_v4_tag_header() {
         let data = b"\x49\x44\x33\x04\x00\x50\x00\x08\x49\x30";
@@ -6,4 +6,12 @@
 
 #[macro_use]
 pub mod core;
+pub mod aiff;
+pub mod ape;
+pub mod flac;
+pub mod id3v1;
 pub mod id3v2;
+pub mod mp4;
+pub mod ogg;
+pub mod riff;
+pub mod xiph;
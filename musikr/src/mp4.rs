@@ -0,0 +1,419 @@
+//! MP4 iTunes metadata reading and writing.
+//!
+//! MP4 files (also known as M4A, M4B, and ALAC/AAC files) are made up of a tree of "atoms".
+//! Metadata is stored as a list of items in the `moov/udta/meta/ilst` atom, which this module
+//! exposes as an [`ItemMap`](items::ItemMap):
+//!
+//! ```no_run
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! use musikr::mp4::{items::ItemValue, Tag};
+//! let mut tag = Tag::open("music.m4a")?;
+//! tag.items.insert("\u{a9}nam", ItemValue::Text(vec![String::from("Sunrise")]));
+//! tag.save("music.m4a")?;
+//! #   Ok(())
+//! # }
+//! ```
+//!
+//! Most items have an ID3v2 equivalent, which can be found with
+//! [`items::to_frame_id`](items::to_frame_id) and [`items::from_frame_id`](items::from_frame_id).
+//!
+//! When the `moov` atom grows past any padding that follows it, the audio data after it must be
+//! moved, in which case the chunk offsets of every track are updated to match. Fragmented files
+//! cannot be updated this way and are not supported.
+
+mod atoms;
+pub mod items;
+
+use crate::core::io::{write_spliced_with, SaveOptions, Splice};
+use atoms::{Atom, AtomInfo, Content, OffsetError};
+use items::ItemMap;
+
+use log::{error, info};
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// The amount of padding added after the `moov` atom when the audio data must be moved.
+const PADDING: usize = 1024;
+
+/// The `hdlr` atom that iTunes metadata requires.
+const HANDLER: &[u8] = b"\x00\x00\x00\x00\x00\x00\x00\x00mdirappl\
+                         \x00\x00\x00\x00\x00\x00\x00\x00\x00";
+
+/// The iTunes metadata of an MP4 file.
+///
+/// More information can be found in the [module documentation](self).
+#[derive(Debug, Clone, Default)]
+pub struct Tag {
+    /// The metadata items of this file.
+    pub items: ItemMap,
+}
+
+impl Tag {
+    /// Creates an empty tag.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attempts to open and parse the metadata in `path`.
+    ///
+    /// A file without any metadata will result in an empty tag.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be opened, is not an MP4 file, or if the atoms are malformed, an
+    /// error will be returned with a general reason for why. Specific information about
+    /// parsing errors will be logged.
+    pub fn open<P: AsRef<Path>>(path: P) -> ParseResult<Self> {
+        let mut file = File::open(path)?;
+        let (_, _, moov) = read_moov(&mut file)?;
+
+        let items = match moov
+            .child(b"udta")
+            .and_then(|udta| udta.child(b"meta"))
+            .and_then(|meta| meta.child(b"ilst"))
+            .and_then(|ilst| ilst.data())
+        {
+            Some(data) => ItemMap::parse(data)?,
+            None => ItemMap::new(),
+        };
+
+        Ok(Self { items })
+    }
+
+    /// Saves the metadata to `path`.
+    ///
+    /// If the metadata no longer fits in the space it originally took up, the audio data will
+    /// be moved and the chunk offsets will be updated.
+    ///
+    /// # Errors
+    ///
+    /// If `path` is not an MP4 file, is fragmented, or if the file cannot be written, an error
+    /// with a general reason will be returned. Specific information about saving errors will
+    /// be logged.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> SaveResult<()> {
+        let mut file = File::open(&path)?;
+
+        let (atoms, idx, mut moov) = match read_moov(&mut file) {
+            Ok(moov) => moov,
+            Err(ParseError::IoError(err)) => return Err(SaveError::IoError(err)),
+            Err(_) => {
+                error!("file is not a valid mp4 file");
+                return Err(SaveError::NotMp4);
+            }
+        };
+
+        *ilst_mut(&mut moov) = Atom::leaf(b"ilst", self.items.render());
+
+        // Any free atom right after the movie atom can be used as padding.
+        let start = atoms[idx].start;
+        let end = match atoms.get(idx + 1) {
+            Some(next) if &next.kind == b"free" || &next.kind == b"skip" => next.end,
+            _ => atoms[idx].end,
+        };
+
+        let space = (end - start) as usize;
+        let mut data = moov.render();
+
+        if data.len() == space {
+            // Nothing to do, the new atom fits exactly.
+        } else if data.len() + 8 <= space {
+            data.extend(atoms::render_free(space - data.len()));
+        } else {
+            info!("metadata does not fit in the existing space, moving audio data");
+
+            if atoms.iter().any(|atom| &atom.kind == b"moof") {
+                error!("fragmented mp4 files are not supported");
+                return Err(SaveError::Unsupported);
+            }
+
+            // There is no reason to pad a movie atom at the end of the file.
+            let padding = if end == file.metadata()?.len() {
+                0
+            } else {
+                PADDING
+            };
+
+            let delta = (data.len() + padding) as i64 - space as i64;
+
+            match moov.shift_offsets(end, delta) {
+                Ok(()) => {}
+                Err(OffsetError::Overflow) => {
+                    error!("chunk offsets cannot fit in 32 bits");
+                    return Err(SaveError::TooLarge);
+                }
+                Err(OffsetError::Malformed) => {
+                    error!("chunk offset table is malformed");
+                    return Err(SaveError::NotMp4);
+                }
+            }
+
+            data = moov.render();
+
+            if padding > 0 {
+                data.extend(atoms::render_free(padding));
+            }
+        }
+
+        // Only the movie atom and its padding are replaced, with the data after them being
+        // moved if their size changed.
+        drop(file);
+        write_spliced_with(
+            path,
+            &[Splice::new(start, end, &data)],
+            &SaveOptions::default(),
+            &mut |_, _| {},
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Reads the top-level atoms of `file`, alongside the index and contents of the `moov` atom.
+fn read_moov(file: &mut File) -> ParseResult<(Vec<AtomInfo>, usize, Atom)> {
+    let len = file.metadata()?.len();
+
+    let mut header = [0; 8];
+    file.read_exact(&mut header)?;
+
+    if &header[4..8] != b"ftyp" {
+        return Err(ParseError::NotFound);
+    }
+
+    let atoms = atoms::scan(file, len)?;
+
+    let idx = atoms
+        .iter()
+        .position(|atom| &atom.kind == b"moov")
+        .ok_or_else(|| {
+            error!("file has no movie atom");
+            ParseError::MalformedData
+        })?;
+
+    let info = atoms[idx];
+    let mut data = vec![0; (info.size() - info.header_len) as usize];
+    file.seek(SeekFrom::Start(info.start + info.header_len))?;
+    file.read_exact(&mut data)?;
+
+    let moov = Atom::parse(info.kind, &data)?;
+
+    Ok((atoms, idx, moov))
+}
+
+/// Returns the `ilst` atom of `moov`, creating it and its parents if needed.
+fn ilst_mut(moov: &mut Atom) -> &mut Atom {
+    // All of these atoms are containers, so this cannot fail.
+    moov.child_or_insert(b"udta", || Atom::container(b"udta", Vec::new()))
+        .and_then(|udta| {
+            udta.child_or_insert(b"meta", || Atom {
+                kind: *b"meta",
+                content: Content::Container {
+                    prefix: vec![0; 4],
+                    children: vec![Atom::leaf(b"hdlr", HANDLER.to_vec())],
+                },
+            })
+        })
+        .and_then(|meta| meta.child_or_insert(b"ilst", || Atom::leaf(b"ilst", Vec::new())))
+        .unwrap()
+}
+
+/// The result given after a parsing operation.
+pub type ParseResult<T> = Result<T, ParseError>;
+
+/// The error type returned when parsing MP4 metadata.
+#[derive(Debug)]
+pub enum ParseError {
+    /// Generic IO errors. This either means that a problem occurred while opening the file
+    /// for a tag, or an unexpected EOF was encountered while parsing.
+    IoError(io::Error),
+    /// An atom or item was not valid.
+    MalformedData,
+    /// The file was not an MP4 file.
+    NotFound,
+}
+
+impl From<io::Error> for ParseError {
+    fn from(other: io::Error) -> Self {
+        ParseError::IoError(other)
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(err) => err.fmt(f),
+            Self::MalformedData => write![f, "malformed data"],
+            Self::NotFound => write![f, "not found"],
+        }
+    }
+}
+
+impl error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::IoError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// The result given after a save operation.
+pub type SaveResult<T> = Result<T, SaveError>;
+
+/// The error type returned when saving MP4 metadata.
+#[derive(Debug)]
+pub enum SaveError {
+    /// Generic IO errors. This means that a problem occurred while writing the tag to a file.
+    IoError(io::Error),
+    /// The file being written to was not a valid MP4 file.
+    NotMp4,
+    /// The file is fragmented, and its audio data cannot be moved.
+    Unsupported,
+    /// The chunk offsets of the file could not fit the moved audio data.
+    TooLarge,
+}
+
+impl From<io::Error> for SaveError {
+    fn from(other: io::Error) -> Self {
+        SaveError::IoError(other)
+    }
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(err) => err.fmt(f),
+            Self::NotMp4 => write![f, "file is not a valid mp4 file"],
+            Self::Unsupported => write![f, "fragmented files are not supported"],
+            Self::TooLarge => write![f, "chunk offsets are too large"],
+        }
+    }
+}
+
+impl error::Error for SaveError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::IoError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mp4::items::ItemValue;
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    const AUDIO: &[u8] = b"\x16\x16\x16\x16audio";
+
+    fn make_mp4(name: &str) -> PathBuf {
+        let ftyp = atoms::render_atom(b"ftyp", b"M4A \x00\x00\x00\x00M4A isom");
+
+        // The movie atom is fixed-size, so the audio data offset can be computed first.
+        let stco = |offset: u32| {
+            let mut data = b"\x00\x00\x00\x00\x00\x00\x00\x01".to_vec();
+            data.extend(offset.to_be_bytes());
+
+            let mut moov = Atom::container(b"moov", Vec::new());
+            let stco = Atom::leaf(b"stco", data);
+
+            let stbl = moov
+                .child_or_insert(b"trak", || Atom::container(b"trak", Vec::new()))
+                .and_then(|trak| {
+                    trak.child_or_insert(b"mdia", || Atom::container(b"mdia", Vec::new()))
+                })
+                .and_then(|mdia| {
+                    mdia.child_or_insert(b"minf", || Atom::container(b"minf", Vec::new()))
+                })
+                .and_then(|minf| {
+                    minf.child_or_insert(b"stbl", || Atom::container(b"stbl", Vec::new()))
+                })
+                .unwrap();
+
+            stbl.child_or_insert(b"stco", || stco);
+
+            moov.render()
+        };
+
+        let moov_len = stco(0).len();
+        let offset = (ftyp.len() + moov_len + 8) as u32;
+
+        let mut data = ftyp;
+        data.extend(stco(offset));
+        data.extend(atoms::render_atom(b"mdat", AUDIO));
+
+        let path = env::temp_dir().join(name);
+        fs::write(&path, data).unwrap();
+        path
+    }
+
+    /// Returns the data that the first chunk offset in `path` points to.
+    fn first_chunk(path: &Path) -> Vec<u8> {
+        let data = fs::read(path).unwrap();
+        let pos = data.windows(4).position(|w| w == b"stco").unwrap() + 12;
+        let offset = u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) as usize;
+
+        data[offset..offset + AUDIO.len()].to_vec()
+    }
+
+    #[test]
+    fn open_empty() {
+        let path = make_mp4("musikr_mp4_empty.m4a");
+
+        assert_eq!(first_chunk(&path), AUDIO);
+        assert!(Tag::open(&path).unwrap().items.is_empty());
+    }
+
+    #[test]
+    fn save_moves_audio() {
+        let path = make_mp4("musikr_mp4_moved.m4a");
+
+        let mut tag = Tag::new();
+        tag.items
+            .insert("\u{a9}nam", ItemValue::Text(vec![String::from("Title")]));
+        tag.items.insert(
+            "trkn",
+            ItemValue::Position {
+                number: 1,
+                total: 2,
+            },
+        );
+        tag.save(&path).unwrap();
+
+        assert_eq!(first_chunk(&path), AUDIO);
+        assert_eq!(Tag::open(&path).unwrap().items, tag.items);
+
+        // The padding added by the first save should be reused by the second.
+        let len = fs::metadata(&path).unwrap().len();
+
+        tag.items.insert(
+            "----:com.apple.iTunes:MOOD",
+            ItemValue::Text(vec![String::from("Calm")]),
+        );
+        tag.save(&path).unwrap();
+
+        assert_eq!(fs::metadata(&path).unwrap().len(), len);
+        assert_eq!(first_chunk(&path), AUDIO);
+        assert_eq!(Tag::open(&path).unwrap().items, tag.items);
+
+        // Shrinking the metadata should also leave the audio in place.
+        Tag::new().save(&path).unwrap();
+
+        assert_eq!(fs::metadata(&path).unwrap().len(), len);
+        assert_eq!(first_chunk(&path), AUDIO);
+        assert!(Tag::open(&path).unwrap().items.is_empty());
+    }
+
+    #[test]
+    fn not_mp4() {
+        let path = env::var("CARGO_MANIFEST_DIR").unwrap() + "/res/test/example.mp3";
+
+        assert!(matches!(Tag::open(&path), Err(ParseError::NotFound)));
+    }
+}
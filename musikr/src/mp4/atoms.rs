@@ -0,0 +1,403 @@
+//! MP4 atom parsing and rendering.
+
+use crate::core::io::BufStream;
+use crate::mp4::{ParseError, ParseResult};
+
+use log::error;
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Atoms that contain other atoms that musikr needs to traverse.
+const CONTAINERS: &[&[u8; 4]] = &[
+    b"moov", b"trak", b"mdia", b"minf", b"stbl", b"udta", b"meta", b"edts", b"dinf",
+];
+
+/// The location of a top-level atom in a file.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) struct AtomInfo {
+    pub(crate) kind: [u8; 4],
+    pub(crate) start: u64,
+    pub(crate) header_len: u64,
+    pub(crate) end: u64,
+}
+
+impl AtomInfo {
+    pub(crate) fn size(&self) -> u64 {
+        self.end - self.start
+    }
+}
+
+/// Scans the top-level atoms of `src`, which is `len` bytes long, without reading their
+/// contents.
+pub(crate) fn scan<R: Read + Seek>(src: &mut R, len: u64) -> ParseResult<Vec<AtomInfo>> {
+    let mut atoms = Vec::new();
+    let mut pos = 0;
+
+    while pos + 8 <= len {
+        src.seek(SeekFrom::Start(pos))?;
+
+        let mut header = [0; 8];
+        src.read_exact(&mut header)?;
+
+        let kind = [header[4], header[5], header[6], header[7]];
+        let (size, header_len) =
+            match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+                // A size of zero means that the atom extends to the end of the file.
+                0 => (len - pos, 8),
+                1 => {
+                    let mut ext = [0; 8];
+                    src.read_exact(&mut ext)?;
+                    (u64::from_be_bytes(ext), 16)
+                }
+                size => (u64::from(size), 8),
+            };
+
+        // 64-bit sizes come straight from the file, so they could overflow.
+        let end = match pos.checked_add(size).filter(|&end| end <= len) {
+            Some(end) if size >= header_len => end,
+            _ => {
+                error!("top-level atom {} has an invalid size", kind_name(&kind));
+                return Err(ParseError::MalformedData);
+            }
+        };
+
+        atoms.push(AtomInfo {
+            kind,
+            start: pos,
+            header_len,
+            end,
+        });
+
+        pos = end;
+    }
+
+    Ok(atoms)
+}
+
+/// An atom that has been fully read into memory.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct Atom {
+    pub(crate) kind: [u8; 4],
+    pub(crate) content: Content,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) enum Content {
+    /// An atom containing other atoms, alongside any data that comes before them.
+    Container {
+        prefix: Vec<u8>,
+        children: Vec<Atom>,
+    },
+    Leaf(Vec<u8>),
+}
+
+impl Atom {
+    /// Creates an empty container atom.
+    pub(crate) fn container(kind: &[u8; 4], prefix: Vec<u8>) -> Self {
+        Self {
+            kind: *kind,
+            content: Content::Container {
+                prefix,
+                children: Vec::new(),
+            },
+        }
+    }
+
+    /// Creates a leaf atom.
+    pub(crate) fn leaf(kind: &[u8; 4], data: Vec<u8>) -> Self {
+        Self {
+            kind: *kind,
+            content: Content::Leaf(data),
+        }
+    }
+
+    /// Parses an atom of `kind` from its contents.
+    pub(crate) fn parse(kind: [u8; 4], data: &[u8]) -> ParseResult<Self> {
+        if !CONTAINERS.contains(&&kind) {
+            return Ok(Self {
+                kind,
+                content: Content::Leaf(data.to_vec()),
+            });
+        }
+
+        // The metadata atom is usually a full atom with a version and flags, but older
+        // QuickTime files lay it out as a plain container. Its first child is always a
+        // handler atom, which allows us to tell the two apart.
+        let prefix_len = if &kind == b"meta" && data.get(4..8) != Some(b"hdlr") {
+            4
+        } else {
+            0
+        };
+
+        if data.len() < prefix_len {
+            error!("atom {} is too small", kind_name(&kind));
+            return Err(ParseError::MalformedData);
+        }
+
+        Ok(Self {
+            kind,
+            content: Content::Container {
+                prefix: data[..prefix_len].to_vec(),
+                children: parse_all(&mut BufStream::new(&data[prefix_len..]))?,
+            },
+        })
+    }
+
+    /// Returns the child of `kind`, if this atom is a container and has one.
+    pub(crate) fn child(&self, kind: &[u8; 4]) -> Option<&Atom> {
+        match &self.content {
+            Content::Container { children, .. } => children.iter().find(|atom| &atom.kind == kind),
+            Content::Leaf(_) => None,
+        }
+    }
+
+    /// Returns the child of `kind`, creating it with `make` if it does not exist. Returns
+    /// `None` if this atom is not a container.
+    pub(crate) fn child_or_insert<F: FnOnce() -> Atom>(
+        &mut self,
+        kind: &[u8; 4],
+        make: F,
+    ) -> Option<&mut Atom> {
+        match &mut self.content {
+            Content::Container { children, .. } => {
+                let i = match children.iter().position(|atom| &atom.kind == kind) {
+                    Some(i) => i,
+                    None => {
+                        children.push(make());
+                        children.len() - 1
+                    }
+                };
+
+                Some(&mut children[i])
+            }
+
+            Content::Leaf(_) => None,
+        }
+    }
+
+    /// Returns the data of this atom if it is a leaf atom.
+    pub(crate) fn data(&self) -> Option<&[u8]> {
+        match &self.content {
+            Content::Leaf(data) => Some(data),
+            Content::Container { .. } => None,
+        }
+    }
+
+    /// Adds `delta` to every chunk offset in this atom that is at or past `after`.
+    ///
+    /// Audio data is located through the `stco` and `co64` atoms, which store absolute
+    /// file offsets. These must be updated whenever the data before them moves.
+    pub(crate) fn shift_offsets(&mut self, after: u64, delta: i64) -> Result<(), OffsetError> {
+        match &mut self.content {
+            Content::Container { children, .. } => {
+                for child in children {
+                    child.shift_offsets(after, delta)?
+                }
+            }
+
+            Content::Leaf(data) if &self.kind == b"stco" => {
+                for offset in table_mut(data, 4)? {
+                    let value = u64::from(u32::from_be_bytes(offset.try_into().unwrap()));
+
+                    if value >= after {
+                        let value = u32::try_from(value as i64 + delta)
+                            .map_err(|_| OffsetError::Overflow)?;
+                        offset.copy_from_slice(&value.to_be_bytes());
+                    }
+                }
+            }
+
+            Content::Leaf(data) if &self.kind == b"co64" => {
+                for offset in table_mut(data, 8)? {
+                    let value = u64::from_be_bytes(offset.try_into().unwrap());
+
+                    if value >= after {
+                        let value = (value as i64 + delta) as u64;
+                        offset.copy_from_slice(&value.to_be_bytes());
+                    }
+                }
+            }
+
+            Content::Leaf(_) => {}
+        }
+
+        Ok(())
+    }
+
+    /// Renders this atom, including its header.
+    pub(crate) fn render(&self) -> Vec<u8> {
+        let content = match &self.content {
+            Content::Container { prefix, children } => {
+                let mut content = prefix.clone();
+
+                for child in children {
+                    content.extend(child.render())
+                }
+
+                content
+            }
+
+            Content::Leaf(data) => data.clone(),
+        };
+
+        render_atom(&self.kind, &content)
+    }
+}
+
+/// Parses every atom in `stream` until it is exhausted.
+pub(crate) fn parse_all(stream: &mut BufStream) -> ParseResult<Vec<Atom>> {
+    let mut atoms = Vec::new();
+
+    while !stream.is_empty() {
+        let (kind, mut data) = read_atom(stream)?;
+        atoms.push(Atom::parse(kind, data.take_rest())?);
+    }
+
+    Ok(atoms)
+}
+
+/// Reads the kind and contents of the next atom in `stream`.
+pub(crate) fn read_atom<'a>(stream: &'a mut BufStream) -> ParseResult<([u8; 4], BufStream<'a>)> {
+    let size = stream.read_be_u32()?;
+    let kind = stream.read_array()?;
+
+    let len = match size {
+        0 => stream.remaining(),
+        1 => (stream.read_be_u64()? as usize)
+            .checked_sub(16)
+            .ok_or_else(|| {
+                error!("atom {} has an invalid size", kind_name(&kind));
+                ParseError::MalformedData
+            })?,
+        size => (size as usize).checked_sub(8).ok_or_else(|| {
+            error!("atom {} has an invalid size", kind_name(&kind));
+            ParseError::MalformedData
+        })?,
+    };
+
+    Ok((kind, stream.slice_stream(len)?))
+}
+
+/// Renders an atom from its kind and contents.
+pub(crate) fn render_atom(kind: &[u8; 4], content: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(content.len() + 16);
+
+    match u32::try_from(content.len() + 8) {
+        Ok(size) => result.extend(size.to_be_bytes()),
+        Err(_) => {
+            result.extend(1u32.to_be_bytes());
+            result.extend(kind);
+            result.extend((content.len() as u64 + 16).to_be_bytes());
+            result.extend(content);
+            return result;
+        }
+    }
+
+    result.extend(kind);
+    result.extend(content);
+    result
+}
+
+/// Renders a `free` atom that is exactly `size` bytes long. `size` must be at least 8.
+pub(crate) fn render_free(size: usize) -> Vec<u8> {
+    render_atom(b"free", &vec![0; size - 8])
+}
+
+/// Returns a human-readable name for an atom kind. Atom kinds are commonly Latin-1,
+/// such as `©nam`.
+pub(crate) fn kind_name(kind: &[u8; 4]) -> String {
+    kind.iter().map(|&ch| ch as char).collect()
+}
+
+/// The error returned when chunk offsets could not be updated.
+#[derive(Debug)]
+pub(crate) enum OffsetError {
+    Malformed,
+    Overflow,
+}
+
+impl From<io::Error> for OffsetError {
+    fn from(_: io::Error) -> Self {
+        OffsetError::Malformed
+    }
+}
+
+/// Returns the entries of a chunk offset table, which has a version, flags, and a count
+/// before the entries.
+fn table_mut(
+    data: &mut [u8],
+    width: usize,
+) -> Result<std::slice::ChunksExactMut<'_, u8>, OffsetError> {
+    let count =
+        BufStream::new(data.get(4..8).ok_or(OffsetError::Malformed)?).read_be_u32()? as usize;
+
+    data.get_mut(8..8 + count * width)
+        .map(|table| table.chunks_exact_mut(width))
+        .ok_or(OffsetError::Malformed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const STCO_DATA: &[u8] = b"\x00\x00\x00\x18stco\
+                               \x00\x00\x00\x00\
+                               \x00\x00\x00\x02\
+                               \x00\x00\x00\x20\
+                               \x00\x00\x10\x00";
+
+    const META_DATA: &[u8] = b"\x00\x00\x00\x2Ameta\
+                               \x00\x00\x00\x00\
+                               \x00\x00\x00\x0Ehdlr\
+                               \x00\x00\x00\x00\x00\x00\
+                               \x00\x00\x00\x10ilst\
+                               \x00\x00\x00\x06abcd";
+
+    #[test]
+    fn scan_atoms() {
+        let data = b"\x00\x00\x00\x08ftyp\x00\x00\x00\x0Cfree\x16\x16\x16\x16";
+        let atoms = scan(&mut Cursor::new(data), data.len() as u64).unwrap();
+
+        assert_eq!(atoms.len(), 2);
+        assert_eq!(atoms[1].start, 8);
+        assert_eq!(atoms[1].end, 20);
+
+        // A 64-bit size that would overflow the position is malformed.
+        let data = b"\x00\x00\x00\x08ftyp\
+                     \x00\x00\x00\x01free\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF";
+        let result = scan(&mut Cursor::new(data), data.len() as u64);
+
+        assert!(matches!(result, Err(ParseError::MalformedData)));
+    }
+
+    #[test]
+    fn parse_meta() {
+        let mut stream = BufStream::new(META_DATA);
+        let atom = parse_all(&mut stream).unwrap().remove(0);
+
+        assert_eq!(&atom.kind, b"meta");
+        assert!(matches!(&atom.content, Content::Container { prefix, .. } if prefix == &[0; 4]));
+        assert_eq!(
+            atom.child(b"ilst").unwrap().data(),
+            Some(&b"\x00\x00\x00\x06abcd"[..])
+        );
+        assert_eq!(atom.render(), META_DATA);
+    }
+
+    #[test]
+    fn shift_offsets() {
+        let mut stream = BufStream::new(STCO_DATA);
+        let mut atom = parse_all(&mut stream).unwrap().remove(0);
+
+        atom.shift_offsets(0x100, 0x10).unwrap();
+
+        let data = atom.data().unwrap();
+        assert_eq!(&data[8..12], b"\x00\x00\x00\x20");
+        assert_eq!(&data[12..16], b"\x00\x00\x10\x10");
+
+        assert!(matches!(
+            atom.shift_offsets(0, i64::from(u32::MAX)),
+            Err(OffsetError::Overflow)
+        ));
+    }
+}
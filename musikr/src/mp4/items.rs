@@ -0,0 +1,708 @@
+//! iTunes metadata items.
+
+use crate::core::io::BufStream;
+use crate::core::string::{self, Encoding};
+use crate::id3v2::frames::FrameId;
+use crate::mp4::atoms::{self, kind_name};
+use crate::mp4::{ParseError, ParseResult};
+
+use log::{error, warn};
+use std::fmt::{self, Display, Formatter};
+use std::ops::Index;
+
+const TYPE_IMPLICIT: u32 = 0;
+const TYPE_UTF8: u32 = 1;
+const TYPE_UTF16: u32 = 2;
+const TYPE_JPEG: u32 = 13;
+const TYPE_PNG: u32 = 14;
+const TYPE_INTEGER: u32 = 21;
+const TYPE_UNSIGNED: u32 = 22;
+const TYPE_BMP: u32 = 27;
+
+const FREEFORM: &str = "----";
+
+/// The value of an iTunes metadata item.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ItemValue {
+    /// One or more UTF-8 strings.
+    Text(Vec<String>),
+    /// A signed integer, such as `tmpo` or `cpil`.
+    Integer(i64),
+    /// A position within a set, such as `trkn` or `disk`. A total of zero means that
+    /// the total is unknown.
+    Position { number: u16, total: u16 },
+    /// One or more images, such as `covr`.
+    Pictures(Vec<Picture>),
+    /// Data that could not be mapped to any other value.
+    Binary(Vec<BinaryData>),
+}
+
+impl ItemValue {
+    pub(crate) fn parse(key: &str, data: Vec<BinaryData>) -> Self {
+        let all = |kinds: &[u32]| data.iter().all(|data| kinds.contains(&data.kind));
+
+        match key {
+            "trkn" | "disk"
+                if data.len() == 1 && data[0].kind == TYPE_IMPLICIT && data[0].data.len() >= 6 =>
+            {
+                let raw = &data[0].data;
+
+                return Self::Position {
+                    number: u16::from_be_bytes([raw[2], raw[3]]),
+                    total: u16::from_be_bytes([raw[4], raw[5]]),
+                };
+            }
+
+            "covr" if all(&[TYPE_IMPLICIT, TYPE_JPEG, TYPE_PNG, TYPE_BMP]) => {
+                return Self::Pictures(
+                    data.into_iter()
+                        .map(|data| Picture {
+                            format: ImageFormat::parse(data.kind, &data.data),
+                            data: data.data,
+                        })
+                        .collect(),
+                );
+            }
+
+            _ => {}
+        }
+
+        if all(&[TYPE_UTF8]) {
+            return Self::Text(
+                data.iter()
+                    .map(|data| string::read(Encoding::Utf8, &mut BufStream::new(&data.data)))
+                    .collect(),
+            );
+        }
+
+        if all(&[TYPE_UTF16]) {
+            return Self::Text(
+                data.iter()
+                    .map(|data| string::read(Encoding::Utf16Be, &mut BufStream::new(&data.data)))
+                    .collect(),
+            );
+        }
+
+        if let [int] = &data[..] {
+            if let Some(value) = parse_integer(int) {
+                return Self::Integer(value);
+            }
+        }
+
+        Self::Binary(data)
+    }
+
+    pub(crate) fn render(&self, key: &str) -> Vec<BinaryData> {
+        match self {
+            Self::Text(text) => text
+                .iter()
+                .map(|string| BinaryData::new(TYPE_UTF8, string::render(Encoding::Utf8, string)))
+                .collect(),
+
+            Self::Integer(value) => {
+                let bytes = value.to_be_bytes();
+                let width = integer_width(key, *value);
+
+                vec![BinaryData::new(TYPE_INTEGER, bytes[8 - width..].to_vec())]
+            }
+
+            Self::Position { number, total } => {
+                let mut data = vec![0; 2];
+                data.extend(number.to_be_bytes());
+                data.extend(total.to_be_bytes());
+
+                // Track numbers have two extra reserved bytes that disc numbers do not.
+                if key != "disk" {
+                    data.extend([0; 2]);
+                }
+
+                vec![BinaryData::new(TYPE_IMPLICIT, data)]
+            }
+
+            Self::Pictures(pictures) => pictures
+                .iter()
+                .map(|picture| BinaryData::new(picture.format.code(), picture.data.clone()))
+                .collect(),
+
+            Self::Binary(data) => data.clone(),
+        }
+    }
+}
+
+impl Display for ItemValue {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Text(text) => write![f, "{}", text.join(", ")],
+            Self::Integer(value) => write![f, "{}", value],
+            Self::Position { number, total: 0 } => write![f, "{}", number],
+            Self::Position { number, total } => write![f, "{}/{}", number, total],
+            Self::Pictures(pictures) => {
+                for (i, picture) in pictures.iter().enumerate() {
+                    if i > 0 {
+                        write![f, ", "]?;
+                    }
+
+                    write![f, "{}", picture]?;
+                }
+
+                Ok(())
+            }
+            Self::Binary(data) => write![
+                f,
+                "[{} bytes of binary data]",
+                data.iter().map(|data| data.data.len()).sum::<usize>()
+            ],
+        }
+    }
+}
+
+/// An image stored in a `covr` item.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Picture {
+    /// The format of the image.
+    pub format: ImageFormat,
+    /// The raw image data.
+    pub data: Vec<u8>,
+}
+
+impl Display for Picture {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write![f, "{} image [{} bytes]", self.format, self.data.len()]
+    }
+}
+
+/// The format of an image stored in a `covr` item.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ImageFormat {
+    Jpeg,
+    Png,
+    Bmp,
+}
+
+impl ImageFormat {
+    fn parse(kind: u32, data: &[u8]) -> Self {
+        match kind {
+            TYPE_PNG => Self::Png,
+            TYPE_BMP => Self::Bmp,
+            TYPE_JPEG => Self::Jpeg,
+
+            // Some taggers leave the type implicit, so sniff the format instead.
+            _ if data.starts_with(b"\x89PNG") => Self::Png,
+            _ if data.starts_with(b"BM") => Self::Bmp,
+            _ => Self::Jpeg,
+        }
+    }
+
+    fn code(&self) -> u32 {
+        match self {
+            Self::Jpeg => TYPE_JPEG,
+            Self::Png => TYPE_PNG,
+            Self::Bmp => TYPE_BMP,
+        }
+    }
+}
+
+impl Display for ImageFormat {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Jpeg => write![f, "JPEG"],
+            Self::Png => write![f, "PNG"],
+            Self::Bmp => write![f, "BMP"],
+        }
+    }
+}
+
+/// The contents of a single `data` atom.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BinaryData {
+    /// The well-known type of this data.
+    pub kind: u32,
+    /// The raw data.
+    pub data: Vec<u8>,
+}
+
+impl BinaryData {
+    /// Creates new data with the type `kind`.
+    pub fn new(kind: u32, data: Vec<u8>) -> Self {
+        Self { kind, data }
+    }
+}
+
+/// A collection of iTunes metadata items.
+///
+/// Items are keyed by their atom name, which is usually four Latin-1 characters such as
+/// `©nam` or `trkn`. Freeform items are keyed as `----:mean:name`, such as
+/// `----:com.apple.iTunes:MusicBrainz Track Id`. Keys are case-sensitive, and items are
+/// kept in the order that they were added.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ItemMap {
+    items: Vec<(String, ItemValue)>,
+}
+
+impl ItemMap {
+    /// Creates an empty collection.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the item that matches `key`, if present.
+    pub fn get(&self, key: &str) -> Option<&ItemValue> {
+        self.position(key).map(|i| &self.items[i].1)
+    }
+
+    /// Returns a mutable reference to the item that matches `key`, if present.
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut ItemValue> {
+        self.position(key).map(move |i| &mut self.items[i].1)
+    }
+
+    /// Returns whether an item matching `key` is present.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// Inserts an item, replacing and returning any item with the same key.
+    ///
+    /// # Panics
+    /// This function will panic if `key` is not a valid item key. If the validity of the key
+    /// cannot be assured, [`try_insert`](ItemMap::try_insert) should be used instead.
+    pub fn insert(&mut self, key: &str, value: ItemValue) -> Option<ItemValue> {
+        self.try_insert(key, value).unwrap()
+    }
+
+    /// Fallibly inserts an item, replacing and returning any item with the same key.
+    ///
+    /// # Errors
+    /// If `key` is not four Latin-1 characters or a freeform key in the form of
+    /// `----:mean:name`, then an error will be returned.
+    pub fn try_insert(
+        &mut self,
+        key: &str,
+        value: ItemValue,
+    ) -> Result<Option<ItemValue>, ItemKeyError> {
+        if !is_valid_key(key) {
+            return Err(ItemKeyError(()));
+        }
+
+        match self.position(key) {
+            Some(i) => Ok(Some(std::mem::replace(&mut self.items[i].1, value))),
+            None => {
+                self.items.push((String::from(key), value));
+                Ok(None)
+            }
+        }
+    }
+
+    /// Removes and returns the item matching `key`, if present.
+    pub fn remove(&mut self, key: &str) -> Option<ItemValue> {
+        self.position(key).map(|i| self.items.remove(i).1)
+    }
+
+    /// Removes all items that do not satisfy the predicate.
+    pub fn retain<F: FnMut(&str, &mut ItemValue) -> bool>(&mut self, mut f: F) {
+        self.items.retain_mut(|(key, value)| f(key, value))
+    }
+
+    /// Removes all items.
+    pub fn clear(&mut self) {
+        self.items.clear()
+    }
+
+    /// Returns an iterator over the keys and items in this collection.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ItemValue)> {
+        self.items.iter().map(|(key, value)| (key.as_str(), value))
+    }
+
+    /// Returns a mutable iterator over the keys and items in this collection.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&str, &mut ItemValue)> {
+        self.items
+            .iter_mut()
+            .map(|(key, value)| (key.as_str(), value))
+    }
+
+    /// Returns an iterator over the keys in this collection.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.items.iter().map(|(key, _)| key.as_str())
+    }
+
+    /// Returns the number of items in this collection.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns whether this collection is empty.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Parses the contents of an `ilst` atom.
+    pub(crate) fn parse(data: &[u8]) -> ParseResult<Self> {
+        let mut stream = BufStream::new(data);
+        let mut items = Self::new();
+
+        while !stream.is_empty() {
+            let (kind, mut data) = atoms::read_atom(&mut stream)?;
+
+            match parse_item(kind, data.take_rest())? {
+                Some((key, value)) => {
+                    if items.contains_key(&key) {
+                        warn!("dropping duplicate item {}", key);
+                        continue;
+                    }
+
+                    items.items.push((key, value))
+                }
+                None => warn!("dropping empty item {}", kind_name(&kind)),
+            }
+        }
+
+        Ok(items)
+    }
+
+    /// Renders the contents of an `ilst` atom.
+    pub(crate) fn render(&self) -> Vec<u8> {
+        self.items
+            .iter()
+            .flat_map(|(key, value)| render_item(key, value))
+            .collect()
+    }
+
+    fn position(&self, key: &str) -> Option<usize> {
+        self.items.iter().position(|(other, _)| other == key)
+    }
+}
+
+impl Index<&str> for ItemMap {
+    type Output = ItemValue;
+
+    fn index(&self, key: &str) -> &Self::Output {
+        self.get(key).unwrap()
+    }
+}
+
+impl_newtype_err! {
+    /// The error returned when an iTunes item key is not valid.
+    ItemKeyError => "item key was not 4 latin-1 characters or a freeform key"
+}
+
+/// Returns the ID3v2 frame that corresponds to the item `key`, if any.
+///
+/// This mapping is symmetric with [`from_frame_id`](from_frame_id).
+pub fn to_frame_id(key: &str) -> Option<FrameId> {
+    ID3V2_MAPPING
+        .iter()
+        .find(|(other, _)| *other == key)
+        .map(|(_, id)| FrameId::new(id))
+}
+
+/// Returns the item key that corresponds to the ID3v2 frame `id`, if any.
+///
+/// This mapping is symmetric with [`to_frame_id`](to_frame_id).
+pub fn from_frame_id(id: FrameId) -> Option<&'static str> {
+    ID3V2_MAPPING
+        .iter()
+        .find(|(_, other)| id == *other)
+        .map(|(key, _)| *key)
+}
+
+// Every item that has an exact ID3v2 equivalent. Each key and frame only appears once,
+// so that a value can be moved between the two formats without changing meaning.
+#[rustfmt::skip]
+static ID3V2_MAPPING: &[(&str, &[u8; 4])] = &[
+    ("\u{a9}nam", b"TIT2"),
+    ("\u{a9}ART", b"TPE1"),
+    ("aART", b"TPE2"),
+    ("\u{a9}alb", b"TALB"),
+    ("\u{a9}wrt", b"TCOM"),
+    ("\u{a9}day", b"TDRC"),
+    ("\u{a9}gen", b"TCON"),
+    ("\u{a9}cmt", b"COMM"),
+    ("\u{a9}lyr", b"USLT"),
+    ("\u{a9}too", b"TSSE"),
+    ("\u{a9}wrk", b"TIT1"),
+    ("\u{a9}grp", b"GRP1"), // [iTunes]
+    ("\u{a9}mvn", b"MVNM"), // [iTunes]
+    ("\u{a9}mvi", b"MVIN"), // [iTunes]
+    ("cprt", b"TCOP"),
+    ("trkn", b"TRCK"),
+    ("disk", b"TPOS"),
+    ("tmpo", b"TBPM"),
+    ("covr", b"APIC"),
+    ("sonm", b"TSOT"),
+    ("soar", b"TSOP"),
+    ("soal", b"TSOA"),
+    ("soaa", b"TSO2"), // [iTunes]
+    ("soco", b"TSOC"), // [iTunes]
+    ("cpil", b"TCMP"), // [iTunes]
+    ("pcst", b"PCST"), // [iTunes]
+    ("purl", b"WFED"), // [iTunes]
+    ("catg", b"TCAT"), // [iTunes]
+    ("desc", b"TDES"), // [iTunes]
+    ("keyw", b"TKWD"), // [iTunes]
+    ("egid", b"TGID"), // [iTunes]
+];
+
+// Integer items that iTunes expects to have a specific width.
+#[rustfmt::skip]
+static INTEGER_WIDTHS: &[(&str, usize)] = &[
+    ("cpil", 1), ("pgap", 1), ("pcst", 1), ("hdvd", 1), ("stik", 1), ("rtng", 1),
+    ("shwm", 1), ("akID", 1), ("tmpo", 2), ("\u{a9}mvi", 2), ("\u{a9}mvc", 2),
+    ("tvsn", 4), ("tves", 4), ("cnID", 4), ("atID", 4), ("sfID", 4), ("cmID", 4),
+    ("geID", 4), ("plID", 8),
+];
+
+fn integer_width(key: &str, value: i64) -> usize {
+    if let Some((_, width)) = INTEGER_WIDTHS.iter().find(|(other, _)| *other == key) {
+        return *width;
+    }
+
+    [1, 2, 4]
+        .into_iter()
+        .find(|width| {
+            let bits = width * 8 - 1;
+            (-(1 << bits)..(1 << bits)).contains(&value)
+        })
+        .unwrap_or(8)
+}
+
+fn parse_integer(data: &BinaryData) -> Option<i64> {
+    let raw = &data.data;
+
+    if !matches!(raw.len(), 1 | 2 | 3 | 4 | 8) {
+        return None;
+    }
+
+    let unsigned = raw
+        .iter()
+        .fold(0u64, |acc, &byte| (acc << 8) | u64::from(byte));
+
+    match data.kind {
+        TYPE_UNSIGNED => Some(unsigned as i64),
+        TYPE_INTEGER => {
+            // Sign-extend the value to 64 bits.
+            let shift = 64 - raw.len() * 8;
+            Some(((unsigned << shift) as i64) >> shift)
+        }
+        _ => None,
+    }
+}
+
+fn parse_item(kind: [u8; 4], data: &[u8]) -> ParseResult<Option<(String, ItemValue)>> {
+    let mut stream = BufStream::new(data);
+    let mut key = kind_name(&kind);
+    let mut mean = None;
+    let mut name = None;
+    let mut values = Vec::new();
+
+    while !stream.is_empty() {
+        let (child, mut data) = atoms::read_atom(&mut stream)?;
+
+        match &child {
+            b"data" => {
+                // The type is preceded by a version byte that is always zero.
+                let kind = data.read_be_u32()? & 0xFFFFFF;
+                data.skip(4)?;
+
+                values.push(BinaryData::new(kind, data.take_rest().to_vec()))
+            }
+
+            b"mean" | b"name" if key == FREEFORM => {
+                data.skip(4)?;
+                let string = string::read(Encoding::Utf8, &mut data);
+
+                if &child == b"mean" {
+                    mean = Some(string)
+                } else {
+                    name = Some(string)
+                }
+            }
+
+            _ => warn!(
+                "dropping unknown atom {} in item {}",
+                kind_name(&child),
+                key
+            ),
+        }
+    }
+
+    if key == FREEFORM {
+        match (mean, name) {
+            (Some(mean), Some(name)) => key = format!["{}:{}:{}", FREEFORM, mean, name],
+            _ => {
+                error!("freeform item has no mean or name");
+                return Err(ParseError::MalformedData);
+            }
+        }
+    }
+
+    if values.is_empty() {
+        return Ok(None);
+    }
+
+    let value = ItemValue::parse(&key, values);
+
+    Ok(Some((key, value)))
+}
+
+fn render_item(key: &str, value: &ItemValue) -> Vec<u8> {
+    let mut content = Vec::new();
+
+    let kind = match key
+        .strip_prefix("----:")
+        .and_then(|rest| rest.split_once(':'))
+    {
+        Some((mean, name)) => {
+            for (kind, string) in [(b"mean", mean), (b"name", name)] {
+                let mut data = vec![0; 4];
+                data.extend(string::render(Encoding::Utf8, string));
+                content.extend(atoms::render_atom(kind, &data));
+            }
+
+            *b"----"
+        }
+
+        None => {
+            let mut kind = [0; 4];
+
+            for (byte, ch) in kind.iter_mut().zip(key.chars()) {
+                *byte = ch as u8;
+            }
+
+            kind
+        }
+    };
+
+    for data in value.render(key) {
+        let mut atom = Vec::with_capacity(data.data.len() + 8);
+        atom.extend(data.kind.to_be_bytes());
+        atom.extend([0; 4]);
+        atom.extend(data.data);
+
+        content.extend(atoms::render_atom(b"data", &atom));
+    }
+
+    atoms::render_atom(&kind, &content)
+}
+
+fn is_valid_key(key: &str) -> bool {
+    match key.strip_prefix("----:") {
+        Some(rest) => {
+            matches!(rest.split_once(':'), Some((mean, name)) if !mean.is_empty() && !name.is_empty())
+        }
+        None => {
+            key != FREEFORM && key.chars().count() == 4 && key.chars().all(|ch| (ch as u32) <= 0xFF)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ILST_DATA: &[u8] = b"\x00\x00\x00\x1D\xA9nam\
+                               \x00\x00\x00\x15data\
+                               \x00\x00\x00\x01\x00\x00\x00\x00\
+                               Title\
+                               \x00\x00\x00\x20trkn\
+                               \x00\x00\x00\x18data\
+                               \x00\x00\x00\x00\x00\x00\x00\x00\
+                               \x00\x00\x00\x03\x00\x0C\x00\x00\
+                               \x00\x00\x00\x1Atmpo\
+                               \x00\x00\x00\x12data\
+                               \x00\x00\x00\x15\x00\x00\x00\x00\
+                               \x00\x78\
+                               \x00\x00\x00\x1Dcovr\
+                               \x00\x00\x00\x15data\
+                               \x00\x00\x00\x0E\x00\x00\x00\x00\
+                               \x89PNG\x16\
+                               \x00\x00\x00\x52----\
+                               \x00\x00\x00\x1Cmean\x00\x00\x00\x00com.apple.iTunes\
+                               \x00\x00\x00\x15name\x00\x00\x00\x00ISRC CODE\
+                               \x00\x00\x00\x19data\
+                               \x00\x00\x00\x01\x00\x00\x00\x00\
+                               USABC1234";
+
+    #[test]
+    fn parse_items() {
+        let items = ItemMap::parse(ILST_DATA).unwrap();
+
+        assert_eq!(items.len(), 5);
+        assert_eq!(
+            items["\u{a9}nam"],
+            ItemValue::Text(vec![String::from("Title")])
+        );
+        assert_eq!(
+            items["trkn"],
+            ItemValue::Position {
+                number: 3,
+                total: 12
+            }
+        );
+        assert_eq!(items["tmpo"], ItemValue::Integer(120));
+        assert_eq!(
+            items["covr"],
+            ItemValue::Pictures(vec![Picture {
+                format: ImageFormat::Png,
+                data: b"\x89PNG\x16".to_vec()
+            }])
+        );
+        assert_eq!(
+            items["----:com.apple.iTunes:ISRC CODE"],
+            ItemValue::Text(vec![String::from("USABC1234")])
+        );
+    }
+
+    #[test]
+    fn render_items() {
+        let items = ItemMap::parse(ILST_DATA).unwrap();
+        assert_eq!(items.render(), ILST_DATA);
+    }
+
+    #[test]
+    fn parse_integers() {
+        let data = BinaryData::new(TYPE_INTEGER, vec![0xFF, 0xFE]);
+        assert_eq!(parse_integer(&data), Some(-2));
+
+        let data = BinaryData::new(TYPE_UNSIGNED, vec![0xFF, 0xFE]);
+        assert_eq!(parse_integer(&data), Some(0xFFFE));
+
+        assert_eq!(integer_width("cpil", 0), 1);
+        assert_eq!(integer_width("xxxx", 300), 2);
+        assert_eq!(integer_width("xxxx", -70000), 4);
+    }
+
+    #[test]
+    fn item_keys() {
+        let mut items = ItemMap::new();
+        let title = ItemValue::Text(vec![String::from("Title")]);
+
+        assert!(items.insert("\u{a9}nam", title.clone()).is_none());
+        assert!(items.insert("\u{a9}nam", title.clone()).is_some());
+        assert!(items.insert("\u{a9}NAM", title.clone()).is_none());
+        assert_eq!(items.len(), 2);
+
+        assert!(items
+            .try_insert("----:com.apple.iTunes:MOOD", title.clone())
+            .is_ok());
+        assert!(items.try_insert("----", title.clone()).is_err());
+        assert!(items
+            .try_insert("----:com.apple.iTunes", title.clone())
+            .is_err());
+        assert!(items.try_insert("nam", title.clone()).is_err());
+        assert!(items.try_insert("\u{2603}nam", title).is_err());
+    }
+
+    #[test]
+    fn id3v2_mapping() {
+        for (key, id) in ID3V2_MAPPING {
+            let frame_id = to_frame_id(key).unwrap();
+
+            assert_eq!(frame_id, *id);
+            assert_eq!(from_frame_id(frame_id), Some(*key));
+        }
+
+        assert_eq!(to_frame_id("soaa"), Some(FrameId::new(b"TSO2")));
+        assert_eq!(from_frame_id(FrameId::new(b"PCST")), Some("pcst"));
+        assert_eq!(from_frame_id(FrameId::new(b"GRP1")), Some("\u{a9}grp"));
+        assert_eq!(from_frame_id(FrameId::new(b"TXXX")), None);
+    }
+}
@@ -0,0 +1,576 @@
+//! Ogg Vorbis and Opus comment reading and writing.
+//!
+//! Ogg is a container format that splits the packets of a codec into "pages". Both Vorbis and
+//! Opus store their metadata in a [`VorbisComment`](crate::xiph::VorbisComment) packet, which
+//! comes right after the identification packet at the beginning of the stream:
+//!
+//! ```no_run
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! use musikr::ogg::Tag;
+//! let mut tag = Tag::open("music.opus")?;
+//! tag.comments.set("TITLE", "Archangel");
+//! tag.save("music.opus")?;
+//! #   Ok(())
+//! # }
+//! ```
+//!
+//! When saved, the header packets are re-paginated. If the number of header pages changes, the
+//! pages of the rest of the stream are renumbered in place. Only the first logical stream in
+//! a file is read or written, and multiplexed streams are not supported.
+//!
+//! More information about the container can be found [here](https://xiph.org/ogg/doc/framing.html).
+
+mod page;
+
+use crate::core::io::{write_spliced_with, BufStream, SaveOptions, Splice};
+use crate::xiph::VorbisComment;
+use page::{Page, FLAG_BOS};
+
+use log::{error, info, warn};
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const VORBIS_ID: &[u8] = b"\x01vorbis";
+const VORBIS_COMMENT_ID: &[u8] = b"\x03vorbis";
+const OPUS_ID: &[u8] = b"OpusHead";
+const OPUS_COMMENT_ID: &[u8] = b"OpusTags";
+
+/// The comments of an Ogg Vorbis or Opus file.
+///
+/// More information can be found in the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct Tag {
+    codec: Codec,
+    extra: Vec<u8>,
+    /// The Vorbis comment of this file.
+    pub comments: VorbisComment,
+}
+
+impl Tag {
+    /// Attempts to open and parse the comments in `path`.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be opened, is not an Ogg Vorbis or Opus file, or if a page or the
+    /// comments are malformed, an error will be returned with a general reason for why. Specific
+    /// information about parsing errors will be logged.
+    pub fn open<P: AsRef<Path>>(path: P) -> ParseResult<Self> {
+        let mut file = BufReader::new(File::open(path)?);
+        let headers = read_headers(&mut file)?;
+        let mut stream = BufStream::new(&headers.packets[1]);
+
+        let comments = match headers.codec {
+            Codec::Vorbis => {
+                if stream.slice(7)? != VORBIS_COMMENT_ID {
+                    error!("second vorbis packet was not a comment header");
+                    return Err(ParseError::MalformedData);
+                }
+
+                let comments = VorbisComment::parse(&mut stream)?;
+
+                if stream.read_u8()? & 0x1 == 0 {
+                    warn!("framing bit is not set on vorbis comment header")
+                }
+
+                comments
+            }
+
+            Codec::Opus => {
+                if stream.slice(8)? != OPUS_COMMENT_ID {
+                    error!("second opus packet was not an OpusTags packet");
+                    return Err(ParseError::MalformedData);
+                }
+
+                VorbisComment::parse(&mut stream)?
+            }
+        };
+
+        // Opus allows binary data after the comments, which should be kept if the first bit is
+        // set. Otherwise it's just padding.
+        let extra = match stream.peek(0..1) {
+            Ok(&[byte]) if headers.codec == Codec::Opus && byte & 0x1 != 0 => {
+                stream.take_rest().to_vec()
+            }
+            _ => Vec::new(),
+        };
+
+        Ok(Self {
+            codec: headers.codec,
+            extra,
+            comments,
+        })
+    }
+
+    /// Returns the codec of the stream that these comments were read from.
+    pub fn codec(&self) -> Codec {
+        self.codec
+    }
+
+    /// Saves the comments to `path`.
+    ///
+    /// The comments will be written in the format of the codec in `path`, regardless of
+    /// [`Tag::codec`](Tag::codec).
+    ///
+    /// # Errors
+    ///
+    /// If `path` is not an Ogg Vorbis or Opus file, or if the file cannot be written, an error
+    /// with a general reason will be returned. Specific information about saving errors will
+    /// be logged.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> SaveResult<()> {
+        let mut headers = match read_headers(&mut BufReader::new(File::open(&path)?)) {
+            Ok(headers) => headers,
+            Err(ParseError::IoError(err)) => return Err(SaveError::IoError(err)),
+            Err(_) => {
+                error!("file is not a supported ogg file");
+                return Err(SaveError::Unsupported);
+            }
+        };
+
+        headers.packets[1] = self.render(headers.codec);
+
+        // The identification header must always be alone on the first page.
+        let serial = headers.serial;
+        let sequence = headers.sequence;
+
+        let mut pages = page::paginate(&headers.packets[..1], serial, sequence, FLAG_BOS);
+        pages.extend(page::paginate(
+            &headers.packets[1..],
+            serial,
+            sequence + 1,
+            0,
+        ));
+
+        let out: Vec<u8> = pages.iter().flat_map(|page| page.render()).collect();
+
+        write_spliced_with(
+            &path,
+            &[Splice::new(0, headers.size, &out)],
+            &SaveOptions::default(),
+            &mut |_, _| {},
+        )?;
+
+        if pages.len() == headers.page_count {
+            return Ok(());
+        }
+
+        // The number of pages changed, so every page after the headers must be renumbered.
+        info!("header page count changed, renumbering stream");
+
+        let delta = pages.len() as i64 - headers.page_count as i64;
+        renumber(path.as_ref(), out.len() as u64, serial, delta)?;
+
+        Ok(())
+    }
+
+    fn render(&self, codec: Codec) -> Vec<u8> {
+        match codec {
+            Codec::Vorbis => {
+                let mut result = VORBIS_COMMENT_ID.to_vec();
+                result.extend(self.comments.render());
+                result.push(1);
+                result
+            }
+
+            Codec::Opus => {
+                let mut result = OPUS_COMMENT_ID.to_vec();
+                result.extend(self.comments.render());
+                result.extend(&self.extra);
+                result
+            }
+        }
+    }
+}
+
+/// The codec of an Ogg stream.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Codec {
+    Vorbis,
+    Opus,
+}
+
+impl Codec {
+    fn detect(packet: &[u8]) -> ParseResult<Self> {
+        if packet.starts_with(VORBIS_ID) {
+            Ok(Self::Vorbis)
+        } else if packet.starts_with(OPUS_ID) {
+            Ok(Self::Opus)
+        } else {
+            error!("ogg stream codec is not supported");
+            Err(ParseError::Unsupported)
+        }
+    }
+
+    fn header_count(&self) -> usize {
+        match self {
+            Self::Vorbis => 3,
+            Self::Opus => 2,
+        }
+    }
+}
+
+impl Display for Codec {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Vorbis => write![f, "Vorbis"],
+            Self::Opus => write![f, "Opus"],
+        }
+    }
+}
+
+/// Shifts the sequence numbers of every page of `serial` from `start` onwards by `delta`.
+///
+/// The size of a page does not depend on its sequence number, so each page is rewritten in
+/// place, one page at a time.
+fn renumber(path: &Path, start: u64, serial: u32, delta: i64) -> io::Result<()> {
+    let mut dst = OpenOptions::new().write(true).open(path)?;
+    let mut src = BufReader::new(File::open(path)?);
+    let len = src.get_ref().metadata()?.len();
+    let mut pos = src.seek(SeekFrom::Start(start))?;
+
+    while pos < len {
+        let mut page = match Page::read(&mut src) {
+            Ok(page) => page,
+            Err(_) => {
+                warn!("could not parse page, keeping the rest of the file as-is");
+                break;
+            }
+        };
+
+        if page.serial == serial {
+            page.sequence = (page.sequence as i64 + delta) as u32;
+
+            // Only the sequence number and the checksum have changed.
+            dst.seek(SeekFrom::Start(pos + 18))?;
+            dst.write_all(&page.render()[18..26])?;
+        }
+
+        pos += page.size() as u64;
+    }
+
+    Ok(())
+}
+
+/// The header packets of the first logical stream.
+struct Headers {
+    codec: Codec,
+    serial: u32,
+    sequence: u32,
+    packets: Vec<Vec<u8>>,
+    page_count: usize,
+    size: u64,
+}
+
+fn read_headers<R: Read>(src: &mut R) -> ParseResult<Headers> {
+    let first = Page::read(src)?;
+
+    if first.flags & FLAG_BOS == 0 {
+        error!("first page is not the beginning of a stream");
+        return Err(ParseError::MalformedData);
+    }
+
+    let serial = first.serial;
+    let sequence = first.sequence;
+
+    let mut codec = None;
+    let mut packets = Vec::new();
+    let mut current = Vec::new();
+    let mut page_count = 0;
+    let mut size = 0;
+    let mut page = first;
+
+    loop {
+        page_count += 1;
+        size += page.size() as u64;
+
+        for (segment, finished) in page.packets() {
+            current.extend(segment);
+
+            if finished {
+                packets.push(std::mem::take(&mut current));
+            }
+        }
+
+        if codec.is_none() && !packets.is_empty() {
+            codec = Some(Codec::detect(&packets[0])?);
+        }
+
+        if let Some(codec) = codec {
+            if packets.len() >= codec.header_count() {
+                // The spec requires audio data to start on a fresh page, which we rely on
+                // so that we don't have to re-paginate the audio.
+                if packets.len() > codec.header_count() || !current.is_empty() {
+                    error!("audio data starts on a header page");
+                    return Err(ParseError::Unsupported);
+                }
+
+                return Ok(Headers {
+                    codec,
+                    serial,
+                    sequence,
+                    packets,
+                    page_count,
+                    size,
+                });
+            }
+        }
+
+        page = Page::read(src)?;
+
+        if page.serial != serial {
+            error!("multiplexed ogg streams are not supported");
+            return Err(ParseError::Unsupported);
+        }
+    }
+}
+
+/// The result given after a parsing operation.
+pub type ParseResult<T> = Result<T, ParseError>;
+
+/// The error type returned when parsing Ogg comments.
+#[derive(Debug)]
+pub enum ParseError {
+    /// Generic IO errors. This either means that a problem occurred while opening the file
+    /// for a tag, or an unexpected EOF was encountered while parsing.
+    IoError(io::Error),
+    /// A page or the comments were not valid.
+    MalformedData,
+    /// The codec or the structure of the stream is unsupported.
+    Unsupported,
+    /// The file was not an Ogg file.
+    NotFound,
+}
+
+impl From<io::Error> for ParseError {
+    fn from(other: io::Error) -> Self {
+        ParseError::IoError(other)
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(err) => err.fmt(f),
+            Self::MalformedData => write![f, "malformed data"],
+            Self::Unsupported => write![f, "unsupported"],
+            Self::NotFound => write![f, "not found"],
+        }
+    }
+}
+
+impl error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::IoError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// The result given after a save operation.
+pub type SaveResult<T> = Result<T, SaveError>;
+
+/// The error type returned when saving Ogg comments.
+#[derive(Debug)]
+pub enum SaveError {
+    /// Generic IO errors. This means that a problem occurred while writing the tag to a file.
+    IoError(io::Error),
+    /// The file being written to was not a supported Ogg file.
+    Unsupported,
+}
+
+impl From<io::Error> for SaveError {
+    fn from(other: io::Error) -> Self {
+        SaveError::IoError(other)
+    }
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(err) => err.fmt(f),
+            Self::Unsupported => write![f, "file is not a supported ogg file"],
+        }
+    }
+}
+
+impl error::Error for SaveError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::IoError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    const SERIAL: u32 = 0x16161616;
+
+    fn make_ogg(name: &str, codec: Codec, extra: &[u8]) -> PathBuf {
+        let mut comments = VorbisComment::new();
+        comments.vendor = String::from("musikr");
+        comments.add("TITLE", "Title");
+
+        let mut headers = match codec {
+            Codec::Vorbis => vec![VORBIS_ID.to_vec(), VORBIS_COMMENT_ID.to_vec(), vec![5; 300]],
+            Codec::Opus => vec![OPUS_ID.to_vec(), OPUS_COMMENT_ID.to_vec()],
+        };
+
+        headers[0].resize(19, 0x16);
+        headers[1].extend(comments.render());
+
+        match codec {
+            Codec::Vorbis => headers[1].push(1),
+            Codec::Opus => headers[1].extend(extra),
+        }
+
+        let mut pages = page::paginate(&headers[..1], SERIAL, 0, FLAG_BOS);
+        pages.extend(page::paginate(&headers[1..], SERIAL, 1, 0));
+
+        let audio = [vec![0x16; 400], vec![0x16; 100]];
+        pages.extend(page::paginate(&audio, SERIAL, pages.len() as u32, 0));
+
+        let path = env::temp_dir().join(name);
+        fs::write(
+            &path,
+            pages
+                .iter()
+                .flat_map(|page| page.render())
+                .collect::<Vec<u8>>(),
+        )
+        .unwrap();
+        path
+    }
+
+    fn read_pages(path: &Path) -> Vec<Page> {
+        let data = fs::read(path).unwrap();
+        let mut src = &data[..];
+        let mut pages = Vec::new();
+
+        while !src.is_empty() {
+            pages.push(Page::read(&mut src).unwrap())
+        }
+
+        pages
+    }
+
+    #[test]
+    fn page_round_trip() {
+        let pages = page::paginate(&[vec![0x16; 255 * 300], vec![0x16; 10]], SERIAL, 4, 0);
+
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].segments.len(), 255);
+        assert_eq!(pages[0].granule, u64::MAX);
+        assert_eq!(pages[1].flags, page::FLAG_CONTINUED);
+        assert_eq!(pages[1].sequence, 5);
+        assert_eq!(pages[1].granule, 0);
+
+        let packets = pages[1].packets();
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].0.len() + pages[0].data.len(), 255 * 300);
+        assert_eq!(packets[1], (&[0x16; 10][..], true));
+
+        let mut data = pages[1].render();
+        assert_eq!(Page::read(&mut &data[..]).unwrap(), pages[1]);
+
+        // Any corruption should fail the checksum.
+        data[30] ^= 0xFF;
+        assert!(matches!(
+            Page::read(&mut &data[..]),
+            Err(ParseError::MalformedData)
+        ));
+    }
+
+    #[test]
+    fn crc() {
+        assert_eq!(page::crc32(b"123456789"), 0x89A1897F);
+    }
+
+    #[test]
+    fn read_vorbis() {
+        let path = make_ogg("musikr_ogg_read.ogg", Codec::Vorbis, &[]);
+        let tag = Tag::open(&path).unwrap();
+
+        assert_eq!(tag.codec(), Codec::Vorbis);
+        assert_eq!(tag.comments.vendor, "musikr");
+        assert_eq!(tag.comments.get("TITLE"), Some("Title"));
+    }
+
+    #[test]
+    fn save_same_pages() {
+        let path = make_ogg("musikr_ogg_same.ogg", Codec::Vorbis, &[]);
+        let old_pages = read_pages(&path);
+
+        let mut tag = Tag::open(&path).unwrap();
+        tag.comments.add("ARTIST", "Artist");
+        tag.save(&path).unwrap();
+
+        let pages = read_pages(&path);
+        assert_eq!(pages.len(), old_pages.len());
+        assert_eq!(pages[2..], old_pages[2..]);
+        assert_eq!(Tag::open(&path).unwrap().comments, tag.comments);
+    }
+
+    #[test]
+    fn save_more_pages() {
+        let path = make_ogg("musikr_ogg_more.ogg", Codec::Vorbis, &[]);
+        let old_pages = read_pages(&path);
+
+        let mut tag = Tag::open(&path).unwrap();
+        tag.comments.add("LYRICS", &"A".repeat(70000));
+        tag.save(&path).unwrap();
+
+        // The audio pages should have been renumbered.
+        let pages = read_pages(&path);
+        let delta = pages.len() - old_pages.len();
+
+        assert!(delta > 0);
+
+        for (i, page) in pages.iter().enumerate() {
+            assert_eq!(page.sequence, i as u32);
+        }
+
+        for (old, new) in old_pages[2..].iter().zip(&pages[2 + delta..]) {
+            assert_eq!(old.data, new.data);
+        }
+
+        assert_eq!(Tag::open(&path).unwrap().comments, tag.comments);
+
+        // Removing the pages again should renumber them back.
+        tag.comments.remove_all("LYRICS");
+        tag.save(&path).unwrap();
+
+        assert_eq!(read_pages(&path), old_pages);
+    }
+
+    #[test]
+    fn save_opus() {
+        let path = make_ogg("musikr_ogg_opus.opus", Codec::Opus, b"\x01extra");
+
+        let mut tag = Tag::open(&path).unwrap();
+        assert_eq!(tag.codec(), Codec::Opus);
+
+        tag.comments.set("TITLE", "New Title");
+        tag.save(&path).unwrap();
+
+        let new_tag = Tag::open(&path).unwrap();
+        assert_eq!(new_tag.comments.get("title"), Some("New Title"));
+        assert_eq!(new_tag.extra, b"\x01extra");
+    }
+
+    #[test]
+    fn not_ogg() {
+        let path = env::var("CARGO_MANIFEST_DIR").unwrap() + "/res/test/example.mp3";
+
+        assert!(matches!(Tag::open(&path), Err(ParseError::NotFound)));
+    }
+}
@@ -0,0 +1,230 @@
+//! Ogg page parsing and rendering.
+
+use crate::core::io::BufStream;
+use crate::ogg::{ParseError, ParseResult};
+
+use log::error;
+use std::io::Read;
+
+const ID: &[u8] = b"OggS";
+
+pub(crate) const FLAG_CONTINUED: u8 = 0x01;
+pub(crate) const FLAG_BOS: u8 = 0x02;
+
+/// A single Ogg page.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct Page {
+    pub(crate) flags: u8,
+    pub(crate) granule: u64,
+    pub(crate) serial: u32,
+    pub(crate) sequence: u32,
+    pub(crate) segments: Vec<u8>,
+    pub(crate) data: Vec<u8>,
+}
+
+impl Page {
+    /// Reads and verifies a page from `src`.
+    pub(crate) fn read<R: Read>(src: &mut R) -> ParseResult<Self> {
+        let mut header_raw = [0; 27];
+        src.read_exact(&mut header_raw)?;
+
+        let mut header = BufStream::new(&header_raw);
+
+        if header.slice(4)? != ID {
+            return Err(ParseError::NotFound);
+        }
+
+        if header.read_u8()? != 0 {
+            error!("ogg page version is not supported");
+            return Err(ParseError::Unsupported);
+        }
+
+        let flags = header.read_u8()?;
+        let granule = header.read_le_u64()?;
+        let serial = header.read_le_u32()?;
+        let sequence = header.read_le_u32()?;
+        let crc = header.read_le_u32()?;
+
+        let mut segments = vec![0; header.read_u8()? as usize];
+        src.read_exact(&mut segments)?;
+
+        let mut data = vec![0; segments.iter().map(|&seg| seg as usize).sum()];
+        src.read_exact(&mut data)?;
+
+        let page = Self {
+            flags,
+            granule,
+            serial,
+            sequence,
+            segments,
+            data,
+        };
+
+        if page.crc() != crc {
+            error!("ogg page {} failed its checksum", sequence);
+            return Err(ParseError::MalformedData);
+        }
+
+        Ok(page)
+    }
+
+    /// Renders this page, computing its checksum.
+    pub(crate) fn render(&self) -> Vec<u8> {
+        let mut result = self.render_raw();
+        let crc = crc32(&result);
+        result[22..26].copy_from_slice(&crc.to_le_bytes());
+        result
+    }
+
+    /// Returns the size of this page when rendered.
+    pub(crate) fn size(&self) -> usize {
+        27 + self.segments.len() + self.data.len()
+    }
+
+    /// Returns every packet segment in this page, alongside whether the segment finishes
+    /// a packet.
+    pub(crate) fn packets(&self) -> Vec<(&[u8], bool)> {
+        let mut result = Vec::new();
+        let mut start = 0;
+        let mut len = 0;
+
+        for &seg in &self.segments {
+            len += seg as usize;
+
+            // A lacing value of 255 means that the packet continues into the next segment.
+            if seg < 255 {
+                result.push((&self.data[start..start + len], true));
+                start += len;
+                len = 0;
+            }
+        }
+
+        if len > 0 || self.segments.last() == Some(&255) {
+            result.push((&self.data[start..start + len], false));
+        }
+
+        result
+    }
+
+    fn crc(&self) -> u32 {
+        crc32(&self.render_raw())
+    }
+
+    /// Renders this page with a zeroed checksum.
+    fn render_raw(&self) -> Vec<u8> {
+        let mut result = Vec::with_capacity(self.size());
+
+        result.extend(ID);
+        result.push(0);
+        result.push(self.flags);
+        result.extend(self.granule.to_le_bytes());
+        result.extend(self.serial.to_le_bytes());
+        result.extend(self.sequence.to_le_bytes());
+        result.extend([0; 4]);
+        result.push(self.segments.len() as u8);
+        result.extend(&self.segments);
+        result.extend(&self.data);
+
+        result
+    }
+}
+
+/// Splits `packets` into as many pages as needed, starting at `sequence`. The first page
+/// will have `flags` set.
+pub(crate) fn paginate(packets: &[Vec<u8>], serial: u32, sequence: u32, flags: u8) -> Vec<Page> {
+    let mut pages = Vec::new();
+
+    let mut page = Page {
+        flags,
+        granule: 0,
+        serial,
+        sequence,
+        segments: Vec::new(),
+        data: Vec::new(),
+    };
+
+    // Header pages have a granule position of zero, unless no packet finishes on
+    // the page, in which case it is -1.
+    let mut finished = false;
+
+    for packet in packets {
+        let mut chunks: Vec<&[u8]> = packet.chunks(255).collect();
+
+        // A packet that is a multiple of 255 bytes needs a zero-length segment to terminate it.
+        if packet.len() % 255 == 0 {
+            chunks.push(&[]);
+        }
+
+        let last = chunks.len() - 1;
+
+        for (i, chunk) in chunks.into_iter().enumerate() {
+            if page.segments.len() == 255 {
+                if !finished {
+                    page.granule = u64::MAX;
+                }
+
+                // A packet that spans pages must be marked as continued on the next page.
+                let next = Page {
+                    flags: if i > 0 { FLAG_CONTINUED } else { 0 },
+                    granule: 0,
+                    serial,
+                    sequence: page.sequence + 1,
+                    segments: Vec::new(),
+                    data: Vec::new(),
+                };
+
+                pages.push(std::mem::replace(&mut page, next));
+                finished = false;
+            }
+
+            page.segments.push(chunk.len() as u8);
+            page.data.extend(chunk);
+
+            if i == last {
+                finished = true;
+            }
+        }
+    }
+
+    if !finished {
+        page.granule = u64::MAX;
+    }
+
+    pages.push(page);
+    pages
+}
+
+/// The CRC-32 used by Ogg, which has a polynomial of `0x04C11DB7`, no reflection, and no
+/// final XOR.
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    data.iter().fold(0, |crc, &byte| {
+        (crc << 8) ^ CRC_TABLE[((crc >> 24) as u8 ^ byte) as usize]
+    })
+}
+
+static CRC_TABLE: [u32; 256] = make_crc_table();
+
+const fn make_crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut crc = (i as u32) << 24;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04C1_1DB7
+            } else {
+                crc << 1
+            };
+
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+}
@@ -0,0 +1,364 @@
+//! RIFF WAVE metadata reading and writing.
+//!
+//! WAV files can store metadata in two places. The most common is a `LIST` chunk with the
+//! `INFO` type, which is a list of simple text fields exposed as an [`InfoMap`](info::InfoMap).
+//! Many taggers also embed an entire ID3v2 tag in an `id3 ` or `ID3 ` chunk, which is exposed
+//! as an [`id3v2::Tag`](crate::id3v2::Tag):
+//!
+//! ```no_run
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! use musikr::riff::Tag;
+//! let mut tag = Tag::open("music.wav")?;
+//! tag.info.insert("INAM", "Archangel");
+//! tag.save("music.wav")?;
+//! #   Ok(())
+//! # }
+//! ```
+//!
+//! When saved, any old metadata chunks are removed and the new chunks are written to the end
+//! of the file. Other chunks, such as the audio data, are not modified.
+
+pub mod info;
+
+use crate::core::iff::{self, ByteOrder, ChunkInfo, Form};
+use crate::core::io::{BufStream, SaveOptions};
+use crate::id3v2;
+use info::InfoMap;
+
+use log::{error, warn};
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+
+/// The metadata of a RIFF WAVE file.
+///
+/// More information can be found in the [module documentation](self).
+#[derive(Debug, Clone, Default)]
+pub struct Tag {
+    id3v2_id: Option<[u8; 4]>,
+    /// The ID3v2 tag embedded in this file, if present.
+    pub id3v2: Option<id3v2::Tag>,
+    /// The fields of the `LIST`/`INFO` chunk.
+    pub info: InfoMap,
+}
+
+impl Tag {
+    /// Creates an empty tag.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attempts to open and parse the metadata in `path`.
+    ///
+    /// A file without any metadata will result in an empty tag.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be opened, is not a WAV file, or if the metadata is malformed, an
+    /// error will be returned with a general reason for why. Specific information about
+    /// parsing errors will be logged.
+    pub fn open<P: AsRef<Path>>(path: P) -> ParseResult<Self> {
+        let mut file = BufReader::new(File::open(path)?);
+        let form = read_form(&mut file)?;
+        let mut tag = Self::new();
+
+        for chunk in &form.chunks {
+            if is_id3v2(chunk) {
+                if tag.id3v2.is_some() {
+                    warn!("dropping duplicate id3v2 chunk");
+                    continue;
+                }
+
+                let data = Form::read_chunk(&mut file, chunk)?;
+
+                let id3v2 = id3v2::Tag::from_bytes(&data).map_err(|err| {
+                    error!("could not parse id3v2 chunk: {}", err);
+                    ParseError::MalformedData
+                })?;
+
+                tag.id3v2_id = Some(chunk.id);
+                tag.id3v2 = Some(id3v2);
+            } else if is_info(chunk) {
+                let data = Form::read_chunk(&mut file, chunk)?;
+                let fields = InfoMap::parse(&mut BufStream::new(&data[4..]));
+
+                // Some files split their fields across multiple chunks.
+                for (id, value) in fields.iter() {
+                    tag.info.insert(id, value);
+                }
+            }
+        }
+
+        Ok(tag)
+    }
+
+    /// Saves the metadata to `path`.
+    ///
+    /// All existing `INFO` and ID3v2 chunks are replaced with the contents of this tag.
+    /// Empty metadata will not be written. If the ID3v2 tag was read from an `ID3 ` chunk,
+    /// that chunk ID will be kept, otherwise `id3 ` will be used.
+    ///
+    /// # Errors
+    ///
+    /// If `path` is not a WAV file, if the tag is too large, or if the file cannot be
+    /// written, an error with a general reason will be returned. Specific information
+    /// about saving errors will be logged.
+    pub fn save<P: AsRef<Path>>(&mut self, path: P) -> SaveResult<()> {
+        self.save_with(path, &SaveOptions::default())
+    }
+
+    /// Saves the metadata to `path` with the given [`SaveOptions`](crate::core::SaveOptions).
+    ///
+    /// This behaves like [`Tag::save`](Tag::save), except that the file can be written
+    /// atomically. The ID3v2 tag is checked for read-only frames and restrictions like it is
+    /// in [`id3v2::Tag::save_with`](crate::id3v2::Tag::save_with), but is never padded.
+    ///
+    /// # Errors
+    ///
+    /// If `path` is not a WAV file, if the ID3v2 tag cannot be rendered, or if the file
+    /// cannot be written, an error with a general reason will be returned. Specific information
+    /// about saving errors will be logged.
+    pub fn save_with<P: AsRef<Path>>(&mut self, path: P, options: &SaveOptions) -> SaveResult<()> {
+        let form = match read_form(&mut File::open(&path)?) {
+            Ok(form) => form,
+            Err(ParseError::IoError(err)) => return Err(SaveError::IoError(err)),
+            Err(_) => {
+                error!("file is not a wav file");
+                return Err(SaveError::NotWave);
+            }
+        };
+
+        let mut new = Vec::new();
+
+        if !self.info.is_empty() {
+            new.extend(iff::render_chunk(
+                b"LIST",
+                &self.info.render(),
+                ByteOrder::Little,
+            ));
+        }
+
+        if let Some(tag) = &mut self.id3v2 {
+            let data = tag.render_with(options, |_| 0)?;
+
+            if !data.is_empty() {
+                let id = self.id3v2_id.unwrap_or(*b"id3 ");
+                new.extend(iff::render_chunk(&id, &data, ByteOrder::Little));
+            }
+        }
+
+        form.replace_chunks(
+            path,
+            |chunk| is_id3v2(chunk) || is_info(chunk),
+            &new,
+            options,
+        )?;
+
+        Ok(())
+    }
+}
+
+fn read_form<R: io::Read + io::Seek>(src: &mut R) -> ParseResult<Form> {
+    match Form::read(src, b"RIFF", ByteOrder::Little)? {
+        Some(form) if &form.kind == b"WAVE" => Ok(form),
+        _ => Err(ParseError::NotFound),
+    }
+}
+
+fn is_id3v2(chunk: &ChunkInfo) -> bool {
+    &chunk.id == b"id3 " || &chunk.id == b"ID3 "
+}
+
+fn is_info(chunk: &ChunkInfo) -> bool {
+    &chunk.id == b"LIST" && chunk.list_type.as_ref() == Some(b"INFO")
+}
+
+/// The result given after a parsing operation.
+pub type ParseResult<T> = Result<T, ParseError>;
+
+/// The error type returned when parsing WAV metadata.
+#[derive(Debug)]
+pub enum ParseError {
+    /// Generic IO errors. This either means that a problem occurred while opening the file
+    /// for a tag, or an unexpected EOF was encountered while parsing.
+    IoError(io::Error),
+    /// A metadata chunk was not valid.
+    MalformedData,
+    /// The file was not a WAV file.
+    NotFound,
+}
+
+impl From<io::Error> for ParseError {
+    fn from(other: io::Error) -> Self {
+        ParseError::IoError(other)
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(err) => err.fmt(f),
+            Self::MalformedData => write![f, "malformed data"],
+            Self::NotFound => write![f, "not found"],
+        }
+    }
+}
+
+impl error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::IoError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// The result given after a save operation.
+pub type SaveResult<T> = Result<T, SaveError>;
+
+/// The error type returned when saving WAV metadata.
+#[derive(Debug)]
+pub enum SaveError {
+    /// Generic IO errors. This means that a problem occurred while writing the tag to a file.
+    IoError(io::Error),
+    /// The ID3v2 tag could not be rendered.
+    Id3v2(id3v2::SaveError),
+    /// The file being written to was not a WAV file.
+    NotWave,
+}
+
+impl From<io::Error> for SaveError {
+    fn from(other: io::Error) -> Self {
+        SaveError::IoError(other)
+    }
+}
+
+impl From<id3v2::SaveError> for SaveError {
+    fn from(other: id3v2::SaveError) -> Self {
+        SaveError::Id3v2(other)
+    }
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(err) => err.fmt(f),
+            Self::Id3v2(err) => err.fmt(f),
+            Self::NotWave => write![f, "file is not a wav file"],
+        }
+    }
+}
+
+impl error::Error for SaveError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::IoError(err) => Some(err),
+            Self::Id3v2(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id3v2::frames::{FrameId, TextFrame};
+    use std::env;
+    use std::fs;
+
+    const WAV_DATA: &[u8] = b"RIFF\x34\x00\x00\x00WAVE\
+                              fmt \x03\x00\x00\x00abc\x00\
+                              LIST\x10\x00\x00\x00INFO\
+                              INAM\x04\x00\x00\x00abc\0\
+                              data\x04\x00\x00\x00\x16\x16\x16\x16";
+
+    #[test]
+    fn read_info() {
+        let path = env::temp_dir().join("musikr_riff_read.wav");
+        fs::write(&path, WAV_DATA).unwrap();
+
+        let tag = Tag::open(&path).unwrap();
+        assert_eq!(tag.info.get("INAM"), Some("abc"));
+        assert!(tag.id3v2.is_none());
+    }
+
+    #[test]
+    fn save_wav() {
+        let path = env::temp_dir().join("musikr_riff_save.wav");
+        fs::write(&path, WAV_DATA).unwrap();
+
+        let mut tag = Tag::open(&path).unwrap();
+        tag.info.insert("IART", "Artist");
+
+        let mut frame = TextFrame::new(FrameId::new(b"TIT2"));
+        frame.text = vec![String::from("Title")];
+
+        let mut id3v2 = id3v2::Tag::new();
+        id3v2.frames.insert(frame);
+        tag.id3v2 = Some(id3v2);
+
+        tag.save(&path).unwrap();
+
+        let data = fs::read(&path).unwrap();
+        let form = read_form(&mut File::open(&path).unwrap()).unwrap();
+        let ids: Vec<&[u8; 4]> = form.chunks.iter().map(|chunk| &chunk.id).collect();
+
+        assert_eq!(ids, [b"fmt ", b"data", b"LIST", b"id3 "]);
+        assert_eq!(
+            u32::from_le_bytes(data[4..8].try_into().unwrap()) as usize,
+            data.len() - 8
+        );
+
+        // All chunks must be even-length.
+        assert!(form.chunks.iter().all(|chunk| chunk.start % 2 == 0));
+
+        let new_tag = Tag::open(&path).unwrap();
+        assert_eq!(new_tag.info, tag.info);
+        assert_eq!(new_tag.id3v2.unwrap().frames["TIT2"].to_string(), "Title");
+
+        // Removing the metadata should only leave the audio chunks.
+        Tag::new().save(&path).unwrap();
+        assert_eq!(fs::read(&path).unwrap().len(), 12 + 12 + 12);
+    }
+
+    #[test]
+    fn save_read_only() {
+        let path = env::temp_dir().join("musikr_riff_read_only.wav");
+        fs::write(&path, WAV_DATA).unwrap();
+
+        let id3v2 = b"ID3\x03\x00\x00\x00\x00\x00\x10\
+                      TIT2\x00\x00\x00\x06\x20\x00\x00Title";
+
+        let mut tag = Tag::open(&path).unwrap();
+        tag.id3v2 = Some(id3v2::Tag::from_bytes(id3v2).unwrap());
+
+        let frame = &mut tag.id3v2.as_mut().unwrap().frames["TIT2"];
+        frame.downcast_mut::<TextFrame>().unwrap().text = vec![String::from("New")];
+
+        assert!(matches!(
+            tag.save(&path),
+            Err(SaveError::Id3v2(id3v2::SaveError::ReadOnly))
+        ));
+        assert_eq!(fs::read(&path).unwrap(), WAV_DATA);
+
+        let options = SaveOptions {
+            force: true,
+            ..SaveOptions::default()
+        };
+
+        tag.save_with(&path, &options).unwrap();
+
+        let new_tag = Tag::open(&path).unwrap();
+        assert_eq!(new_tag.id3v2.unwrap().frames["TIT2"].to_string(), "New");
+    }
+
+    #[test]
+    fn not_wave() {
+        let path = env::var("CARGO_MANIFEST_DIR").unwrap() + "/res/test/example.mp3";
+
+        assert!(matches!(Tag::open(&path), Err(ParseError::NotFound)));
+    }
+}
@@ -0,0 +1,216 @@
+//! RIFF `LIST`/`INFO` fields.
+
+use crate::core::io::BufStream;
+use crate::core::string::{self, Encoding};
+
+use log::warn;
+use std::ops::Index;
+
+/// A collection of RIFF `INFO` fields.
+///
+/// Fields are keyed by a four character ASCII ID, such as `INAM` for the title or `IART` for
+/// the artist. Each field has a single value, and fields are kept in the order that they
+/// were added.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct InfoMap {
+    fields: Vec<(String, String)>,
+}
+
+impl InfoMap {
+    /// Creates an empty collection.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the value of the field `id`, if present.
+    pub fn get(&self, id: &str) -> Option<&str> {
+        self.position(id).map(|i| self.fields[i].1.as_str())
+    }
+
+    /// Returns whether the field `id` is present.
+    pub fn contains_key(&self, id: &str) -> bool {
+        self.position(id).is_some()
+    }
+
+    /// Inserts a field, replacing and returning any value with the same ID.
+    ///
+    /// # Panics
+    /// This function will panic if `id` is not a valid field ID. If the validity of the ID
+    /// cannot be assured, [`try_insert`](InfoMap::try_insert) should be used instead.
+    pub fn insert(&mut self, id: &str, value: &str) -> Option<String> {
+        self.try_insert(id, value).unwrap()
+    }
+
+    /// Fallibly inserts a field, replacing and returning any value with the same ID.
+    ///
+    /// # Errors
+    /// If `id` is not four printable ASCII characters, then an error will be returned.
+    pub fn try_insert(&mut self, id: &str, value: &str) -> Result<Option<String>, InfoIdError> {
+        if !is_valid_id(id) {
+            return Err(InfoIdError(()));
+        }
+
+        match self.position(id) {
+            Some(i) => Ok(Some(std::mem::replace(
+                &mut self.fields[i].1,
+                String::from(value),
+            ))),
+            None => {
+                self.fields.push((String::from(id), String::from(value)));
+                Ok(None)
+            }
+        }
+    }
+
+    /// Removes and returns the value of the field `id`, if present.
+    pub fn remove(&mut self, id: &str) -> Option<String> {
+        self.position(id).map(|i| self.fields.remove(i).1)
+    }
+
+    /// Removes all fields that do not satisfy the predicate.
+    pub fn retain<F: FnMut(&str, &mut String) -> bool>(&mut self, mut f: F) {
+        self.fields.retain_mut(|(id, value)| f(id, value))
+    }
+
+    /// Removes all fields.
+    pub fn clear(&mut self) {
+        self.fields.clear()
+    }
+
+    /// Returns an iterator over the IDs and values in this collection.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields
+            .iter()
+            .map(|(id, value)| (id.as_str(), value.as_str()))
+    }
+
+    /// Returns an iterator over the IDs in this collection.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.fields.iter().map(|(id, _)| id.as_str())
+    }
+
+    /// Returns the number of fields in this collection.
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Returns whether this collection is empty.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Parses the contents of a `LIST` chunk after the `INFO` type.
+    pub(crate) fn parse(stream: &mut BufStream) -> Self {
+        let mut fields = Vec::new();
+
+        while stream.remaining() >= 8 {
+            let id = stream.read_array::<4>().unwrap();
+            let size = stream.read_le_u32().unwrap() as usize;
+
+            // Fields should be NUL-terminated, but not all writers do this. Text should
+            // also be ASCII, but it's often UTF-8 in practice.
+            let value = match stream.slice_stream(size) {
+                Ok(mut data) => string::read_terminated(Encoding::Utf8, &mut data),
+                Err(_) => {
+                    warn!("info field is truncated, dropping the rest of the fields");
+                    break;
+                }
+            };
+
+            // Fields are padded to an even length.
+            if !size.is_multiple_of(2) {
+                stream.skip(1).ok();
+            }
+
+            let id: String = id.iter().map(|&ch| ch as char).collect();
+
+            if !is_valid_id(&id) {
+                warn!("dropping info field with invalid id");
+                continue;
+            }
+
+            fields.push((id, value))
+        }
+
+        Self { fields }
+    }
+
+    /// Renders the contents of a `LIST` chunk, including the `INFO` type.
+    pub(crate) fn render(&self) -> Vec<u8> {
+        let mut result = b"INFO".to_vec();
+
+        for (id, value) in &self.fields {
+            let data = string::render_terminated(Encoding::Utf8, value);
+
+            result.extend(id.as_bytes());
+            result.extend((data.len() as u32).to_le_bytes());
+            result.extend(&data);
+
+            if !data.len().is_multiple_of(2) {
+                result.push(0);
+            }
+        }
+
+        result
+    }
+
+    fn position(&self, id: &str) -> Option<usize> {
+        self.fields.iter().position(|(other, _)| other == id)
+    }
+}
+
+impl Index<&str> for InfoMap {
+    type Output = str;
+
+    fn index(&self, id: &str) -> &Self::Output {
+        self.get(id).unwrap()
+    }
+}
+
+impl_newtype_err! {
+    /// The error returned when a RIFF `INFO` field ID is not valid.
+    InfoIdError => "field id was not 4 printable ascii characters"
+}
+
+fn is_valid_id(id: &str) -> bool {
+    id.len() == 4 && id.bytes().all(|ch| (0x20..=0x7E).contains(&ch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INFO_DATA: &[u8] = b"INFO\
+                               INAM\x06\x00\x00\x00Title\0\
+                               IART\x07\x00\x00\x00Artist\0\0\
+                               ICMT\x03\x00\x00\x00abc\0\
+                               I\x01AM\x02\x00\x00\x00a\0";
+
+    const RENDERED_DATA: &[u8] = b"INFO\
+                                   INAM\x06\x00\x00\x00Title\0\
+                                   IART\x07\x00\x00\x00Artist\0\0\
+                                   ICMT\x04\x00\x00\x00abc\0";
+
+    #[test]
+    fn parse_info() {
+        let mut stream = BufStream::new(&INFO_DATA[4..]);
+        let info = InfoMap::parse(&mut stream);
+
+        assert_eq!(info.len(), 3);
+        assert_eq!(&info["INAM"], "Title");
+        assert_eq!(&info["IART"], "Artist");
+        assert_eq!(&info["ICMT"], "abc");
+    }
+
+    #[test]
+    fn render_info() {
+        let mut info = InfoMap::new();
+        info.insert("INAM", "Title");
+        info.insert("IART", "Artist");
+        info.insert("ICMT", "abc");
+
+        assert_eq!(info.render(), RENDERED_DATA);
+        assert!(info.try_insert("INA", "").is_err());
+        assert!(info.try_insert("INAM", "New").unwrap().is_some());
+    }
+}
@@ -0,0 +1,284 @@
+//! Xiph metadata shared between formats.
+//!
+//! FLAC, Ogg Vorbis, and Opus all store their metadata as a "Vorbis comment", which is a
+//! vendor string followed by a list of `KEY=value` fields. This module exposes that structure
+//! as [`VorbisComment`](VorbisComment), which is then used by the individual format modules.
+//!
+//! More information about the format can be found [here](https://xiph.org/vorbis/doc/v-comment.html).
+
+use crate::core::io::BufStream;
+use crate::core::string::{self, Encoding};
+
+use log::warn;
+use std::io;
+
+/// A Vorbis comment.
+///
+/// This is a multimap between field names and values. Field names are case-insensitive
+/// ASCII strings, with the casing of the name being preserved when written. A field name
+/// can have multiple values, such as a track with multiple artists. All fields are kept in
+/// the order that they were added.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct VorbisComment {
+    /// The name of the software that wrote this comment.
+    pub vendor: String,
+    fields: Vec<(String, String)>,
+}
+
+impl VorbisComment {
+    /// Creates an empty comment with an empty vendor string.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the first value of the field `key`, if present.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.get_all(key).into_iter().next()
+    }
+
+    /// Returns all values of the field `key`.
+    pub fn get_all(&self, key: &str) -> Vec<&str> {
+        self.fields
+            .iter()
+            .filter(|(other, _)| other.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    /// Returns whether any values of the field `key` are present.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.fields
+            .iter()
+            .any(|(other, _)| other.eq_ignore_ascii_case(key))
+    }
+
+    /// Adds a value to the field `key`, keeping any values already present.
+    ///
+    /// # Panics
+    /// This function will panic if `key` is not a valid field name. If the validity of the
+    /// name cannot be assured, [`try_add`](VorbisComment::try_add) should be used instead.
+    pub fn add(&mut self, key: &str, value: &str) {
+        self.try_add(key, value).unwrap()
+    }
+
+    /// Fallibly adds a value to the field `key`, keeping any values already present.
+    ///
+    /// # Errors
+    /// If `key` is empty or contains characters outside of printable ASCII or an `=`,
+    /// then an error will be returned.
+    pub fn try_add(&mut self, key: &str, value: &str) -> Result<(), FieldKeyError> {
+        if !is_valid_key(key) {
+            return Err(FieldKeyError(()));
+        }
+
+        self.fields.push((String::from(key), String::from(value)));
+
+        Ok(())
+    }
+
+    /// Replaces all values of the field `key` with `value`.
+    ///
+    /// # Panics
+    /// This function will panic if `key` is not a valid field name. If the validity of the
+    /// name cannot be assured, [`try_set`](VorbisComment::try_set) should be used instead.
+    pub fn set(&mut self, key: &str, value: &str) {
+        self.try_set(key, value).unwrap()
+    }
+
+    /// Fallibly replaces all values of the field `key` with `value`.
+    ///
+    /// # Errors
+    /// If `key` is empty or contains characters outside of printable ASCII or an `=`,
+    /// then an error will be returned.
+    pub fn try_set(&mut self, key: &str, value: &str) -> Result<(), FieldKeyError> {
+        if !is_valid_key(key) {
+            return Err(FieldKeyError(()));
+        }
+
+        // Keep the new value in the place of the first old value, if there was one.
+        match self
+            .fields
+            .iter()
+            .position(|(other, _)| other.eq_ignore_ascii_case(key))
+        {
+            Some(i) => {
+                self.fields[i].1 = String::from(value);
+
+                let mut idx = 0;
+
+                self.fields.retain(|(other, _)| {
+                    let keep = idx <= i || !other.eq_ignore_ascii_case(key);
+                    idx += 1;
+                    keep
+                });
+            }
+            None => self.fields.push((String::from(key), String::from(value))),
+        }
+
+        Ok(())
+    }
+
+    /// Removes and returns all values of the field `key`.
+    pub fn remove_all(&mut self, key: &str) -> Vec<String> {
+        let mut removed = Vec::new();
+
+        self.fields.retain(|(other, value)| {
+            if other.eq_ignore_ascii_case(key) {
+                removed.push(value.clone());
+                false
+            } else {
+                true
+            }
+        });
+
+        removed
+    }
+
+    /// Removes all fields that do not satisfy the predicate.
+    pub fn retain<F: FnMut(&str, &mut String) -> bool>(&mut self, mut f: F) {
+        self.fields.retain_mut(|(key, value)| f(key, value))
+    }
+
+    /// Removes all fields. The vendor string is kept.
+    pub fn clear(&mut self) {
+        self.fields.clear()
+    }
+
+    /// Returns an iterator over every field name and value in this comment.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// Returns the number of values in this comment.
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Returns whether this comment has no fields.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub(crate) fn parse(stream: &mut BufStream) -> io::Result<Self> {
+        let vendor_len = stream.read_le_u32()? as usize;
+        let vendor = string::read(Encoding::Utf8, &mut stream.slice_stream(vendor_len)?);
+
+        let count = stream.read_le_u32()?;
+        let mut fields = Vec::new();
+
+        for _ in 0..count {
+            let len = stream.read_le_u32()? as usize;
+            let field = string::read(Encoding::Utf8, &mut stream.slice_stream(len)?);
+
+            // Fields without a separator or with an invalid name are garbage, but
+            // don't need to fail the entire comment.
+            match field.split_once('=') {
+                Some((key, value)) if is_valid_key(key) => {
+                    fields.push((String::from(key), String::from(value)))
+                }
+                _ => warn!("dropping malformed field"),
+            }
+        }
+
+        Ok(Self { vendor, fields })
+    }
+
+    pub(crate) fn render(&self) -> Vec<u8> {
+        let mut result = Vec::new();
+
+        let vendor = string::render(Encoding::Utf8, &self.vendor);
+        result.extend((vendor.len() as u32).to_le_bytes());
+        result.extend(vendor);
+
+        result.extend((self.fields.len() as u32).to_le_bytes());
+
+        for (key, value) in &self.fields {
+            let field = string::render(Encoding::Utf8, &format!["{}={}", key, value]);
+            result.extend((field.len() as u32).to_le_bytes());
+            result.extend(field);
+        }
+
+        result
+    }
+}
+
+impl_newtype_err! {
+    /// The error returned when a Vorbis comment field name is not valid.
+    FieldKeyError => "field name was empty or contained non-printable ascii characters or an '='"
+}
+
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .bytes()
+            .all(|ch| (0x20..=0x7D).contains(&ch) && ch != b'=')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMENT_DATA: &[u8] = b"\x09\x00\x00\x00\
+                                  libFLAC 1\
+                                  \x04\x00\x00\x00\
+                                  \x0B\x00\x00\x00TITLE=Title\
+                                  \x0D\x00\x00\x00artist=Test 1\
+                                  \x0A\x00\x00\x00ARTIST=Two\
+                                  \x08\x00\x00\x00BADFIELD";
+
+    const RENDERED_DATA: &[u8] = b"\x09\x00\x00\x00\
+                                   libFLAC 1\
+                                   \x03\x00\x00\x00\
+                                   \x0B\x00\x00\x00TITLE=Title\
+                                   \x0D\x00\x00\x00artist=Test 1\
+                                   \x0A\x00\x00\x00ARTIST=Two";
+
+    #[test]
+    fn parse_comment() {
+        let comment = VorbisComment::parse(&mut BufStream::new(COMMENT_DATA)).unwrap();
+
+        assert_eq!(comment.vendor, "libFLAC 1");
+        assert_eq!(comment.len(), 3);
+        assert_eq!(comment.get("title"), Some("Title"));
+        assert_eq!(comment.get_all("Artist"), vec!["Test 1", "Two"]);
+        assert!(!comment.contains_key("BADFIELD"));
+    }
+
+    #[test]
+    fn render_comment() {
+        let mut comment = VorbisComment::new();
+        comment.vendor = String::from("libFLAC 1");
+        comment.add("TITLE", "Title");
+        comment.add("artist", "Test 1");
+        comment.add("ARTIST", "Two");
+
+        let data = comment.render();
+
+        assert_eq!(data, RENDERED_DATA);
+        assert_eq!(
+            VorbisComment::parse(&mut BufStream::new(&data)).unwrap(),
+            comment
+        );
+    }
+
+    #[test]
+    fn edit_comment() {
+        let mut comment = VorbisComment::new();
+        comment.add("ARTIST", "Test 1");
+        comment.add("TITLE", "Title");
+        comment.add("artist", "Two");
+
+        comment.set("Artist", "Three");
+        assert_eq!(comment.get_all("ARTIST"), vec!["Three"]);
+        assert_eq!(comment.iter().next(), Some(("ARTIST", "Three")));
+
+        assert_eq!(comment.remove_all("title"), vec![String::from("Title")]);
+        assert_eq!(comment.len(), 1);
+
+        assert!(comment.try_add("BAD=KEY", "").is_err());
+        assert!(comment.try_add("", "").is_err());
+        assert!(comment.try_set("~KEY", "").is_err());
+    }
+}